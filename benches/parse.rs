@@ -0,0 +1,47 @@
+//! Compares the cost of building the owned AST with the borrowed AST, as well as decoding the owned
+//! AST from protobuf.
+//!
+//! Run with `cargo bench --bench parse --features protobuf`.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const QUERIES: [&str; 4] = [
+    "SELECT id, name, email FROM contacts WHERE id = $1",
+    "SELECT c.id, c.name, count(o.id) AS orders FROM contacts c LEFT JOIN orders o ON o.contact_id = c.id WHERE c.created_at > now() - interval '30 days' GROUP BY c.id, c.name ORDER BY orders DESC LIMIT 10",
+    "INSERT INTO events (contact_id, kind, payload, created_at) VALUES ($1, 'page_view', '{\"path\": \"/pricing\"}', now()) ON CONFLICT (contact_id, kind) DO UPDATE SET payload = excluded.payload",
+    "UPDATE accounts SET balance = balance - 100.00, updated_at = now() WHERE account_number = 'ACC-0001' AND status IN ('active', 'pending') RETURNING balance",
+];
+
+const ITERATIONS: u32 = 5_000;
+
+fn bench(name: &str, f: impl Fn(&str)) {
+    // Warm up
+    for query in QUERIES {
+        f(query);
+    }
+
+    let mut elapsed = Duration::ZERO;
+    for query in QUERIES {
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            f(black_box(query));
+        }
+        elapsed += start.elapsed();
+    }
+    let per_query = elapsed / (ITERATIONS * QUERIES.len() as u32);
+    println!("{:<10} {:>10.2?} per query", name, per_query);
+}
+
+fn main() {
+    bench("owned", |query| {
+        black_box(pg_parse::parse(query).unwrap());
+    });
+    bench("borrowed", |query| {
+        let tree = pg_parse::parse_borrowed(query).unwrap();
+        black_box(tree.statements().unwrap());
+    });
+    #[cfg(feature = "protobuf")]
+    bench("protobuf", |query| {
+        black_box(pg_parse::parse_protobuf(query).unwrap());
+    });
+}
@@ -22,6 +22,7 @@ fn main() {
 
     // Generate the AST first
     generate_ast(&build_dir, &out_dir).expect("AST generation");
+    generate_tokens(&build_dir, &out_dir).expect("Token generation");
 
     // Now compile the C library.
     // We try to optimize the build a bit by only rebuilding if the directory tree has a detected change
@@ -190,6 +191,7 @@ fn generate_ast(build_dir: &Path, out_dir: &Path) -> std::io::Result<()> {
         "srcdata_dir did not exist: {}",
         srcdata_dir.display()
     );
+    let headers = HeaderLinks::new(&fs::read_to_string(build_dir.join("pg_query.h"))?);
 
     // Common out dir
     let out_file = File::create(out_dir.join("ast.rs"))?;
@@ -226,7 +228,7 @@ fn generate_ast(build_dir: &Path, out_dir: &Path) -> std::io::Result<()> {
             type_resolver.add_type(ty);
         }
     }
-    make_enums(&mut out_file, &enum_defs)?;
+    make_enums(&mut out_file, &enum_defs, &headers)?;
 
     // Structs
     let struct_defs = File::open(srcdata_dir.join("struct_defs.json"))?;
@@ -240,9 +242,141 @@ fn generate_ast(build_dir: &Path, out_dir: &Path) -> std::io::Result<()> {
             }
         }
     }
+    type_resolver.box_large_nodes(&struct_defs);
 
     // Finally make the nodes and the primitives
-    make_nodes(&mut out_file, &struct_defs, &node_types, &type_resolver)?;
+    make_nodes(
+        &mut out_file,
+        &struct_defs,
+        &node_types,
+        &type_resolver,
+        &headers,
+        AstMode::Owned,
+    )?;
+    make_accessors(&mut out_file, &struct_defs, &type_resolver)?;
+    make_json_fields(&mut out_file, &struct_defs, &type_resolver)?;
+
+    // As well as a variant which borrows from the JSON input
+    let borrowed_file = File::create(out_dir.join("ast_borrowed.rs"))?;
+    let mut borrowed_file = BufWriter::new(borrowed_file);
+    make_nodes(
+        &mut borrowed_file,
+        &struct_defs,
+        &node_types,
+        &type_resolver,
+        &headers,
+        AstMode::Borrowed,
+    )?;
+
+    // Builders, visitors and structural comparisons are only generated for the owned variant
+    let builder_file = File::create(out_dir.join("ast_builder.rs"))?;
+    let mut builder_file = BufWriter::new(builder_file);
+    make_builders(&mut builder_file, &struct_defs, &type_resolver)?;
+    for (file, mode) in [
+        ("ast_visit.rs", VisitorMode::Visit),
+        ("ast_visit_mut.rs", VisitorMode::VisitMut),
+        ("ast_fold.rs", VisitorMode::Fold),
+    ] {
+        let visit_file = File::create(out_dir.join(file))?;
+        let mut visit_file = BufWriter::new(visit_file);
+        make_visitors(&mut visit_file, &struct_defs, &type_resolver, mode)?;
+    }
+    let structural_file = File::create(out_dir.join("ast_structural.rs"))?;
+    let mut structural_file = BufWriter::new(structural_file);
+    make_structural(
+        &mut structural_file,
+        &struct_defs,
+        &enum_defs,
+        &type_resolver,
+    )?;
+    let raw_file = File::create(out_dir.join("ast_raw.rs"))?;
+    let mut raw_file = BufWriter::new(raw_file);
+    make_raw_nodes(&mut raw_file, &struct_defs, &type_resolver)?;
+
+    // Decoders and encoders for the protobuf format are only required when that backend is enabled
+    if env::var_os("CARGO_FEATURE_PROTOBUF").is_some() {
+        let proto = fs::read_to_string(build_dir.join("protobuf").join("pg_query.proto"))?;
+        let proto = ProtoDefinitions::parse(&proto);
+        let context = ProtoContext::new(&proto, &struct_defs, &enum_defs, &type_resolver);
+        let decoder_file = File::create(out_dir.join("ast_protobuf_decode.rs"))?;
+        let mut decoder_file = BufWriter::new(decoder_file);
+        make_decoders(&mut decoder_file, &context, &struct_defs, &enum_defs)?;
+        let encoder_file = File::create(out_dir.join("ast_protobuf_encode.rs"))?;
+        let mut encoder_file = BufWriter::new(encoder_file);
+        make_encoders(&mut encoder_file, &context, &struct_defs, &enum_defs)?;
+    }
+    Ok(())
+}
+
+fn generate_tokens(build_dir: &Path, out_dir: &Path) -> std::io::Result<()> {
+    let proto = fs::read_to_string(build_dir.join("protobuf").join("pg_query.proto"))?;
+    let out_file = File::create(out_dir.join("tokens.rs"))?;
+    let mut out = BufWriter::new(out_file);
+
+    // The scanner tokens are only defined within the protobuf definition
+    let mut lines = proto
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| *line != "enum Token" && *line != "enum Token {");
+    assert!(
+        lines.next().is_some(),
+        "Token enum not found in pg_query.proto"
+    );
+    let mut values = Vec::new();
+    for line in lines {
+        if line == "}" {
+            break;
+        }
+        if line.is_empty() || line == "{" {
+            continue;
+        }
+        if line.starts_with("//") {
+            values.push((line.to_string(), None));
+            continue;
+        }
+        let (definition, comment) = match line.split_once("//") {
+            Some((definition, comment)) => (definition, Some(comment.trim())),
+            None => (line, None),
+        };
+        let (name, value) = definition
+            .trim()
+            .trim_end_matches(';')
+            .split_once('=')
+            .unwrap_or_else(|| panic!("Unexpected token definition: {}", line));
+        let name = name.trim().to_string();
+        let value: i32 = value.trim().parse().expect("Token value");
+        if let Some(comment) = comment {
+            values.push((format!("/// `{}`", comment), None));
+        }
+        values.push((name, Some(value)));
+    }
+
+    writeln!(out, "/// A token produced by the PostgreSQL scanner.")?;
+    writeln!(out, "#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]")?;
+    writeln!(out, "pub enum Token {{")?;
+    for (name, value) in &values {
+        match value {
+            Some(value) => writeln!(out, "    {} = {},", name, value)?,
+            None => writeln!(out, "    {}", name)?,
+        }
+    }
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+    writeln!(out, "impl Token {{")?;
+    writeln!(
+        out,
+        "    pub(crate) fn from_i32(value: i32) -> Option<Token> {{"
+    )?;
+    writeln!(out, "        match value {{")?;
+    for (name, value) in &values {
+        if let Some(value) = value {
+            writeln!(out, "            {} => Some(Token::{}),", value, name)?;
+        }
+    }
+    writeln!(out, "            _ => None,")?;
+    writeln!(out, "        }}")?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
     Ok(())
 }
 
@@ -301,35 +435,118 @@ fn make_aliases(
     Ok(())
 }
 
+/// The headers from which enums are generated.
+const ENUM_SECTIONS: [&str; 4] = [
+    "nodes/parsenodes",
+    "nodes/primnodes",
+    "nodes/lockoptions",
+    "nodes/nodes",
+];
+
+/// The headers from which node structs are generated.
+const NODE_SECTIONS: [&str; 3] = ["nodes/parsenodes", "nodes/primnodes", "nodes/pg_list"];
+
+/// The largest struct (in bytes, as estimated by `TypeResolver`) which is stored inline within
+/// `Node`. Larger structs are boxed so that small nodes don't pay for them.
+const MAX_INLINE_NODE_SIZE: usize = 32;
+
+/// Structs which aren't generated.
+const IGNORE_NODES: [&str; 1] = [
+    "Expr", // Generic Superclass - never constructed directly.
+];
+
+/// Nodes which are only created by parse analysis, the planner or the executor, and so never
+/// appear in a raw parse tree. These are excluded from `ast::raw::RawNode`.
+const ANALYZED_NODES: [&str; 50] = [
+    // nodes/parsenodes
+    "CallContext",
+    "InlineCodeBlock",
+    "MergeAction",
+    "Query",
+    "RTEPermissionInfo",
+    "RangeTblEntry",
+    "RangeTblFunction",
+    "RowMarkClause",
+    "SetOperationStmt",
+    "SortGroupClause",
+    "TableSampleClause",
+    "WindowClause",
+    "WithCheckOption",
+    // nodes/primnodes
+    "Aggref",
+    "AlternativeSubPlan",
+    "ArrayCoerceExpr",
+    "ArrayExpr",
+    "CaseTestExpr",
+    "CoerceToDomain",
+    "CoerceToDomainValue",
+    "CoerceViaIO",
+    "CollateExpr",
+    "Const",
+    "ConvertRowtypeExpr",
+    "FieldSelect",
+    "FieldStore",
+    "FromExpr",
+    "FuncExpr",
+    "InferenceElem",
+    "JsonConstructorExpr",
+    "JsonExpr",
+    "JsonTablePath",
+    "JsonTablePathScan",
+    "JsonTablePlan",
+    "JsonTableSiblingJoin",
+    "NextValueExpr",
+    "OnConflictExpr",
+    "OpExpr",
+    "Param",
+    "RangeTblRef",
+    "RelabelType",
+    "RowCompareExpr",
+    "ScalarArrayOpExpr",
+    "SubPlan",
+    "SubscriptingRef",
+    "TableFunc",
+    "TargetEntry",
+    "Var",
+    "WindowFunc",
+    "WindowFuncRunCondition",
+];
+
 fn make_enums(
     out: &mut BufWriter<File>,
     enum_defs: &HashMap<String, HashMap<String, Enum>>,
+    headers: &HeaderLinks,
 ) -> std::io::Result<()> {
-    const SECTIONS: [&str; 4] = [
-        "nodes/parsenodes",
-        "nodes/primnodes",
-        "nodes/lockoptions",
-        "nodes/nodes",
-    ];
-    for section in &SECTIONS {
+    for section in &ENUM_SECTIONS {
         let map = &enum_defs[*section];
         let mut map = map.iter().collect::<Vec<_>>();
         map.sort_by_key(|x| x.0);
 
         for (name, def) in map {
+            write_doc(out, "", def.comment.as_deref(), Some(headers.link(section)))?;
             writeln!(
                 out,
-                "#[derive(Copy, Clone, Eq, PartialEq, Debug, serde::Deserialize)]"
+                "#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, serde::Serialize, serde::Deserialize)]"
             )?;
             writeln!(out, "pub enum {} {{", name)?;
             // This enum has duplicate values - I don't think these are really necessary
             let ignore_value = name.eq("PartitionRangeDatumKind");
 
+            // The default is the zero value, the same as a node created using `makeNode`
+            let default = def
+                .values
+                .iter()
+                .filter(|value| value.name.is_some())
+                .find(|value| value.value == Some(0))
+                .or_else(|| def.values.iter().find(|value| value.name.is_some()))
+                .and_then(|value| value.name.as_ref());
+
             for value in &def.values {
-                if let Some(comment) = &value.comment {
-                    writeln!(out, "    {}", comment)?;
-                }
                 if let Some(name) = &value.name {
+                    write_doc(out, "    ", value.comment.as_deref(), None)?;
+                    if default == Some(name) {
+                        writeln!(out, "    #[default]")?;
+                    }
                     if ignore_value {
                         writeln!(out, "    {},", name)?;
                     } else if let Some(v) = &value.value {
@@ -346,28 +563,49 @@ fn make_enums(
     Ok(())
 }
 
+/// The flavor of AST to generate.
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum AstMode {
+    /// Owns all strings, e.g. `Option<String>`.
+    Owned,
+    /// Borrows strings from the JSON input where possible, e.g. `Option<Cow<'a, str>>`.
+    Borrowed,
+}
+
 fn make_nodes(
     out: &mut BufWriter<File>,
     struct_defs: &HashMap<String, HashMap<String, Struct>>,
     node_types: &HashSet<String>,
     type_resolver: &TypeResolver,
+    headers: &HeaderLinks,
+    mode: AstMode,
 ) -> std::io::Result<()> {
-    const SECTIONS: [&str; 3] = ["nodes/parsenodes", "nodes/primnodes", "nodes/pg_list"];
-    const IGNORE: [&str; 1] = [
-        "Expr", // Generic Superclass - never constructed directly.
-    ];
     let mut added = Vec::new();
 
-    writeln!(out, "#[derive(Debug, serde::Deserialize)]")?;
-    writeln!(out, "pub enum Node {{")?;
-
-    for section in &SECTIONS {
+    // When borrowing, work out which structs (transitively) contain strings and therefore need a
+    // lifetime parameter.
+    let lifetimes = match mode {
+        AstMode::Owned => HashSet::new(),
+        AstMode::Borrowed => find_borrowed_structs(&NODE_SECTIONS, struct_defs, type_resolver),
+    };
+    let (lifetime, borrow) = match mode {
+        AstMode::Owned => ("", ""),
+        AstMode::Borrowed => ("<'a>", "#[serde(borrow)]\n    "),
+    };
+
+    writeln!(
+        out,
+        "#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]"
+    )?;
+    writeln!(out, "pub enum Node{lifetime} {{")?;
+
+    for section in &NODE_SECTIONS {
         let map = &struct_defs[*section];
         let mut map = map.iter().collect::<Vec<_>>();
         map.sort_by_key(|x| x.0);
 
         for (name, def) in map {
-            if IGNORE.iter().any(|x| name.eq(x)) {
+            if IGNORE_NODES.iter().any(|x| name.eq(x)) {
                 continue;
             }
 
@@ -384,8 +622,17 @@ fn make_nodes(
                 continue;
             }
 
-            // Generate with a passable struct
-            writeln!(out, "    {}({}),", name, name)?;
+            // Generate with a passable struct, boxing any which would bloat every other node
+            let (ty, attribute) = if lifetimes.contains(name) {
+                (format!("{name}{lifetime}"), borrow)
+            } else {
+                (name.to_string(), "")
+            };
+            if type_resolver.is_boxed(name) {
+                writeln!(out, "    {attribute}{name}(Box<{ty}>),")?;
+            } else {
+                writeln!(out, "    {attribute}{name}({ty}),")?;
+            }
         }
     }
 
@@ -404,7 +651,20 @@ fn make_nodes(
 
         // If this is an A_Const we handle this specially
         if name.eq("A_Const") {
-            writeln!(out, "    {name}(ConstValue),")?;
+            let (serializer, deserializer) = match mode {
+                AstMode::Owned => ("serialize_const", "deserialize_const"),
+                AstMode::Borrowed => ("serialize_borrowed_const", "deserialize_borrowed_const"),
+            };
+            writeln!(
+                out,
+                "    #[serde(serialize_with = \"crate::serde::{serializer}\", deserialize_with = \"crate::serde::{deserializer}\")]"
+            )?;
+            let val = if type_resolver.is_boxed(name) {
+                format!("Box<ConstValue{lifetime}>")
+            } else {
+                format!("ConstValue{lifetime}")
+            };
+            writeln!(out, "    {name} {{ val: {val}, location: ParseLoc }},")?;
             continue;
         }
 
@@ -412,8 +672,18 @@ fn make_nodes(
         writeln!(out, "    {} {{", name)?;
         for field in &def.fields {
             let field_name = field.name.as_ref().unwrap();
-            let resolved_type = type_resolver.resolve(field.c_type.as_ref().unwrap());
-            writeln!(out, "        #[serde(default)]")?;
+            let c_type = field.c_type.as_ref().unwrap();
+            let resolved_type = resolve_type(type_resolver, c_type, &lifetimes, mode);
+            match borrowed_deserializer(c_type, mode) {
+                Some(deserializer) => writeln!(
+                    out,
+                    "        #[serde(borrow, deserialize_with = \"{deserializer}\", default, skip_serializing_if = \"Option::is_none\")]"
+                )?,
+                None => writeln!(
+                    out,
+                    "        #[serde(default, skip_serializing_if = \"Option::is_none\")]"
+                )?,
+            }
             // We force each of these as an Option so we can be explicit about when we
             // want to handle absence of a field.
             if resolved_type.starts_with("Option<") {
@@ -425,28 +695,90 @@ fn make_nodes(
         writeln!(out, "    }},")?;
     }
 
+    // Nodes which couldn't be deserialized are kept as JSON when parsing leniently
+    if mode == AstMode::Owned {
+        writeln!(
+            out,
+            "    /// A node which couldn't be deserialized, such as a node type introduced by a newer version"
+        )?;
+        writeln!(
+            out,
+            "    /// of `libpg_query`. This is only produced when parsing with `ParseOptions::lenient`."
+        )?;
+        writeln!(
+            out,
+            "    Unknown {{ name: String, raw: Box<serde_json::Value> }},"
+        )?;
+    }
+
     writeln!(out, "}}")?;
 
     // Generate the structs
-    for section in &SECTIONS {
+    for section in &NODE_SECTIONS {
         let map = &struct_defs[*section];
         let mut map = map.iter().collect::<Vec<_>>();
         map.sort_by_key(|x| x.0);
 
         for (name, def) in map {
-            if IGNORE.iter().any(|x| name.eq(x)) {
+            if IGNORE_NODES.iter().any(|x| name.eq(x)) {
                 continue;
             }
 
+            // Floating point fields can't derive `Eq` or `Hash`, so these are compared by their bits
+            let struct_lifetime = if lifetimes.contains(name) {
+                lifetime
+            } else {
+                ""
+            };
+            let mut floats = Vec::new();
+            let mut others = Vec::new();
+            let mut locations = Vec::new();
+
+            // PostgreSQL uses -1 for an unknown location, so structs containing one can't derive
+            // `Default`
+            let has_location = def
+                .fields
+                .iter()
+                .any(|field| match (&field.name, &field.c_type) {
+                    (Some(name), Some(c_type)) => is_location(name, c_type),
+                    _ => false,
+                });
+            let default = if has_location { "" } else { "Default, " };
             writeln!(out)?;
-            writeln!(out, "#[derive(Debug, serde::Deserialize)]")?;
-            writeln!(out, "pub struct {} {{", name)?;
+            write_doc(out, "", def.comment.as_deref(), Some(headers.link(section)))?;
+            if def.fields.iter().any(|field| {
+                field
+                    .c_type
+                    .as_ref()
+                    .is_some_and(|c_type| type_resolver.is_float(c_type))
+            }) {
+                writeln!(
+                    out,
+                    "#[derive(Clone, Debug, {default}serde::Serialize, serde::Deserialize)]"
+                )?;
+            } else {
+                writeln!(
+                    out,
+                    "#[derive(Clone, Debug, {default}PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]"
+                )?;
+            }
+            writeln!(out, "pub struct {name}{struct_lifetime} {{")?;
 
+            // Comments on their own line describe the fields which follow them
+            let mut preceding = Vec::new();
             for field in &def.fields {
                 let (name, c_type) = match (&field.name, &field.c_type) {
                     (Some(name), Some(c_type)) => (name, c_type),
-                    _ => continue,
+                    _ => {
+                        preceding.extend(field.comment.as_deref());
+                        continue;
+                    }
                 };
+                let comment = preceding
+                    .drain(..)
+                    .chain(field.comment.as_deref())
+                    .collect::<Vec<_>>()
+                    .join("\n");
 
                 // These are meta data fields and have no real use
                 if name == "type" || name == "xpr" {
@@ -459,43 +791,63 @@ fn make_nodes(
                 } else {
                     name.to_snake_case()
                 };
-                write!(out, "    #[serde(")?;
-                let mut has_data = false;
+                let resolved_type = resolve_type(type_resolver, c_type, &lifetimes, mode);
+                let mut attributes = Vec::new();
                 if variable_name.ne(name) {
-                    write!(out, "rename = \"{}\"", name)?;
-                    has_data = true;
+                    attributes.push(format!("rename = \"{}\"", name));
                 }
-                if let Some((deserializer, optional)) = TypeResolver::custom_deserializer(c_type) {
-                    if has_data {
-                        write!(out, ", ")?;
-                    }
-                    write!(
-                        out,
+                if resolved_type.contains("'a") {
+                    attributes.push("borrow".to_string());
+                }
+                if let Some(deserializer) = borrowed_deserializer(c_type, mode) {
+                    attributes.push(format!("deserialize_with = \"{}\", default", deserializer));
+                } else if let Some((deserializer, optional)) =
+                    TypeResolver::custom_deserializer(c_type)
+                {
+                    attributes.push(format!(
                         "deserialize_with = \"{}\"{}",
                         deserializer,
                         if optional { ", default" } else { "" }
-                    )?;
+                    ));
                 } else if type_resolver.is_optional(c_type) {
-                    if has_data {
-                        write!(out, ", ")?;
-                    }
-                    write!(out, "default")?;
+                    attributes.push("default".to_string());
+                }
+                // libpg_query omits fields which are null, zero or false, so these are skipped
+                // in order to serialize the same JSON
+                if let Some(serializer) = TypeResolver::custom_serializer(c_type) {
+                    attributes.push(format!("serialize_with = \"{}\"", serializer));
+                }
+                if resolved_type.starts_with("Option<") {
+                    attributes.push("skip_serializing_if = \"Option::is_none\"".to_string());
+                } else if type_resolver.is_optional(c_type) {
+                    attributes
+                        .push("skip_serializing_if = \"crate::serde::is_default\"".to_string());
+                }
+                write_doc(out, "    ", Some(&comment), None)?;
+                writeln!(out, "    #[serde({})]", attributes.join(", "))?;
+                writeln!(out, "    pub {}: {},", variable_name, resolved_type)?;
+                if is_location(name, c_type) {
+                    locations.push(variable_name.clone());
+                }
+                if type_resolver.is_float(c_type) {
+                    floats.push(variable_name);
+                } else {
+                    others.push(variable_name);
                 }
-                writeln!(out, ")]")?;
-                writeln!(
-                    out,
-                    "    pub {}: {},",
-                    variable_name,
-                    type_resolver.resolve(c_type)
-                )?;
             }
 
             writeln!(out, "}}")?;
+            if has_location {
+                make_default_impl(out, name, struct_lifetime, &floats, &others, &locations)?;
+            }
+            if !floats.is_empty() {
+                make_float_impls(out, name, struct_lifetime, &floats, &others)?;
+            }
         }
     }
 
     // Generate a helpful "to_string"
-    writeln!(out, "impl Node {{")?;
+    writeln!(out, "impl{lifetime} Node{lifetime} {{")?;
     writeln!(out, "    pub fn name(&self) -> &'static str {{")?;
     writeln!(out, "        match self {{")?;
     for (variant, is_struct) in added {
@@ -505,6 +857,9 @@ fn make_nodes(
             "            Node::{variant}{modifier} => \"{variant}\",",
         )?;
     }
+    if mode == AstMode::Owned {
+        writeln!(out, "            Node::Unknown {{ .. }} => \"Unknown\",")?;
+    }
     writeln!(out, "        }}")?;
     writeln!(out, "    }}")?;
     writeln!(out, "}}")?;
@@ -512,103 +867,2368 @@ fn make_nodes(
     Ok(())
 }
 
-fn is_reserved(variable: &str) -> bool {
-    matches!(
-        variable,
-        "abstract"
-            | "become"
-            | "box"
-            | "do"
-            | "final"
-            | "macro"
-            | "override"
-            | "priv"
-            | "try"
-            | "typeof"
-            | "unsized"
-            | "virtual"
-            | "yield"
-    )
+/// Links to the PostgreSQL headers that the generated types are defined in, for the version of
+/// PostgreSQL embedded within `libpg_query`.
+struct HeaderLinks {
+    branch: String,
 }
 
-struct TypeResolver {
-    aliases: HashMap<String, bool>, // bool = primitive
-    primitive: HashMap<&'static str, &'static str>,
-    nodes: HashSet<String>,
-    types: HashSet<String>,
+impl HeaderLinks {
+    fn new(pg_query_h: &str) -> Self {
+        let major = pg_query_h.lines().find_map(|line| {
+            let version = line.trim().strip_prefix("#define PG_VERSION_NUM")?;
+            version
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .map(|version| version / 10000)
+        });
+        let branch = match major {
+            Some(major) => format!("REL_{major}_STABLE"),
+            None => "master".into(),
+        };
+        HeaderLinks { branch }
+    }
+
+    /// A link to the header for the given section, e.g. `nodes/parsenodes`.
+    fn link(&self, section: &str) -> String {
+        format!(
+            "Defined in [`{section}.h`](https://github.com/postgres/postgres/blob/{}/src/include/{section}.h).",
+            self.branch
+        )
+    }
 }
 
-impl TypeResolver {
-    pub fn new() -> Self {
-        let mut primitive = HashMap::new();
-        primitive.insert("uint32", "u32");
-        primitive.insert("uint64", "u64");
-        primitive.insert("bits32", "bits32"); // Alias
-        primitive.insert("bool", "bool");
-        primitive.insert("int", "i32");
-        primitive.insert("long", "i64");
-        primitive.insert("int32", "i32");
-        primitive.insert("char*", "Option<String>"); // Make all strings optional
-        primitive.insert("int16", "i16");
-        primitive.insert("char", "char");
-        primitive.insert("double", "f64");
-        primitive.insert("signed int", "i32");
-        primitive.insert("unsigned int", "u32");
-        primitive.insert("uintptr_t", "usize");
+/// Writes a C comment from the PostgreSQL headers as a doc comment, followed by an optional
+/// paragraph such as a link to the header.
+fn write_doc(
+    out: &mut BufWriter<File>,
+    indent: &str,
+    comment: Option<&str>,
+    footer: Option<String>,
+) -> std::io::Result<()> {
+    let mut lines = comment.map(doc_lines).unwrap_or_default();
+    if let Some(footer) = footer {
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(footer);
+    }
+    for line in lines {
+        if line.is_empty() {
+            writeln!(out, "{indent}///")?;
+        } else {
+            writeln!(out, "{indent}/// {line}")?;
+        }
+    }
+    Ok(())
+}
 
-        // Similar to primitives
-        primitive.insert("List*", "Option<Vec<Node>>");
-        primitive.insert("[]Node", "Vec<Node>");
-        primitive.insert("Node*", "Option<Box<Node>>");
-        primitive.insert("Expr*", "Option<Box<Node>>");
-        primitive.insert("String*", "Option<String>");
-        primitive.insert("RelFileNumber", "RelFileNumber");
+/// Converts a C comment into the lines of a doc comment. The comment markers, decoration and
+/// indentation are removed, since indented lines would otherwise be treated as code (and tested),
+/// and any characters with a special meaning to rustdoc are escaped.
+fn doc_lines(comment: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in comment.lines() {
+        let mut line = line.trim();
+        for marker in ["/*", "//"] {
+            line = line.strip_prefix(marker).unwrap_or(line);
+        }
+        line = line.strip_suffix("*/").unwrap_or(line);
+        line = line.trim_start_matches('*').trim();
 
-        // Bitmapset is defined in bitmapset.h and is roughly equivalent to a vector of u32's.
-        primitive.insert("Bitmapset*", "Option<Vec<u32>>");
+        // Lines of dashes are used to box in the name of the struct
+        if !line.is_empty() && line.chars().all(|c| c == '-' || c == '=') {
+            continue;
+        }
+        let mut escaped = String::with_capacity(line.len());
+        for c in line.chars() {
+            match c {
+                '\t' => escaped.push(' '),
+                '[' | ']' | '<' | '>' | '`' | '\\' => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                '#' if escaped.is_empty() => escaped.push_str("\\#"),
+                _ => escaped.push(c),
+            }
+        }
 
-        TypeResolver {
-            primitive,
+        // Collapse blank lines, which separate paragraphs
+        if !escaped.is_empty() || lines.last().is_some_and(|last| !last.is_empty()) {
+            lines.push(escaped);
+        }
+    }
+    while lines.last().is_some_and(|last| last.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
 
-            aliases: HashMap::new(),
-            nodes: HashSet::new(),
-            types: HashSet::new(),
+/// Implements `PartialEq`, `Eq` and `Hash` for a struct containing floating point fields, comparing
+/// and hashing these by their bits.
+fn make_float_impls(
+    out: &mut BufWriter<File>,
+    name: &str,
+    lifetime: &str,
+    floats: &[String],
+    others: &[String],
+) -> std::io::Result<()> {
+    writeln!(out)?;
+    writeln!(out, "impl{lifetime} PartialEq for {name}{lifetime} {{")?;
+    writeln!(out, "    fn eq(&self, other: &Self) -> bool {{")?;
+    let comparisons = others
+        .iter()
+        .map(|field| format!("self.{field} == other.{field}"))
+        .chain(
+            floats
+                .iter()
+                .map(|field| format!("self.{field}.to_bits() == other.{field}.to_bits()")),
+        )
+        .collect::<Vec<_>>();
+    writeln!(out, "        {}", comparisons.join("\n            && "))?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+    writeln!(out, "impl{lifetime} Eq for {name}{lifetime} {{}}")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "impl{lifetime} std::hash::Hash for {name}{lifetime} {{"
+    )?;
+    writeln!(
+        out,
+        "    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {{"
+    )?;
+    for field in others {
+        writeln!(out, "        std::hash::Hash::hash(&self.{field}, state);")?;
+    }
+    for field in floats {
+        writeln!(
+            out,
+            "        std::hash::Hash::hash(&self.{field}.to_bits(), state);"
+        )?;
+    }
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// Whether the field holds the location of a node within the statement.
+fn is_location(name: &str, c_type: &str) -> bool {
+    name == "location" || c_type == "ParseLoc"
+}
+
+/// Implements `Default` for a struct containing locations, which default to -1 (i.e. unknown) as
+/// they do within PostgreSQL rather than to zero, which is the start of the statement.
+fn make_default_impl(
+    out: &mut BufWriter<File>,
+    name: &str,
+    lifetime: &str,
+    floats: &[String],
+    others: &[String],
+    locations: &[String],
+) -> std::io::Result<()> {
+    writeln!(out)?;
+    writeln!(out, "impl{lifetime} Default for {name}{lifetime} {{")?;
+    writeln!(out, "    fn default() -> Self {{")?;
+    writeln!(out, "        Self {{")?;
+    for field in others.iter().chain(floats) {
+        if locations.contains(field) {
+            writeln!(out, "            {field}: -1,")?;
+        } else {
+            writeln!(out, "            {field}: Default::default(),")?;
         }
     }
+    writeln!(out, "        }}")?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
+    Ok(())
+}
 
-    pub fn add_alias(&mut self, ty: &str, target: &str) {
-        self.aliases
-            .insert(ty.to_string(), self.primitive.contains_key(target));
+/// The traits generated by `make_visitors`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum VisitorMode {
+    /// Visits an immutable tree.
+    Visit,
+    /// Visits a mutable tree, allowing nodes to be modified in place.
+    VisitMut,
+    /// Consumes a tree, returning a new tree.
+    Fold,
+}
+
+impl VisitorMode {
+    /// The method used to visit (or fold) the given type.
+    fn method(self, ty: &str) -> String {
+        match self {
+            VisitorMode::Visit => format!("visit_{}", ty.to_snake_case()),
+            VisitorMode::VisitMut => format!("visit_{}_mut", ty.to_snake_case()),
+            VisitorMode::Fold => format!("fold_{}", ty.to_snake_case()),
+        }
     }
 
-    pub fn add_node(&mut self, ty: &str) {
-        self.nodes.insert(ty.to_string());
+    /// The function which recurses into the children of the given type.
+    fn walk(self, ty: &str) -> String {
+        match self {
+            VisitorMode::VisitMut => format!("walk_{}_mut", ty.to_snake_case()),
+            _ => format!("walk_{}", ty.to_snake_case()),
+        }
     }
 
-    pub fn add_type(&mut self, ty: &str) {
-        self.types.insert(ty.to_string());
+    /// The signature of the function which recurses into the given type.
+    fn walk_signature(self, ty: &str) -> String {
+        let walk = self.walk(ty);
+        match self {
+            VisitorMode::Visit => format!(
+                "pub fn {walk}<'ast, V: Visit<'ast> + ?Sized>(visitor: &mut V, node: &'ast {ty})"
+            ),
+            VisitorMode::VisitMut => {
+                format!("pub fn {walk}<V: VisitMut + ?Sized>(visitor: &mut V, node: &mut {ty})")
+            }
+            VisitorMode::Fold => {
+                format!("pub fn {walk}<F: Fold + ?Sized>(folder: &mut F, node: {ty}) -> {ty}")
+            }
+        }
     }
 
-    pub fn contains(&self, ty: &str) -> bool {
-        self.aliases.contains_key(ty)
-            || self.primitive.contains_key(ty)
-            || self.nodes.contains(ty)
-            || self.types.contains(ty)
+    /// The method signature for the given type, which is passed as `node`.
+    fn method_signature(self, ty: &str) -> String {
+        let method = self.method(ty);
+        match self {
+            VisitorMode::Visit => format!("fn {method}(&mut self, node: &'ast {ty})"),
+            VisitorMode::VisitMut => format!("fn {method}(&mut self, node: &mut {ty})"),
+            VisitorMode::Fold => format!("fn {method}(&mut self, node: {ty}) -> {ty}"),
+        }
     }
 
-    pub fn is_primitive(&self, ty: &str) -> bool {
-        self.primitive.contains_key(ty) || self.aliases.get(ty).copied().unwrap_or_default()
+    /// The parameter for a field of a value node.
+    fn parameter(self, variable: &str, rust_type: &str) -> String {
+        match self {
+            VisitorMode::Visit => format!("{variable}: &'ast {rust_type}"),
+            VisitorMode::VisitMut => format!("{variable}: &mut {rust_type}"),
+            VisitorMode::Fold => format!("{variable}: {rust_type}"),
+        }
     }
+}
 
-    pub fn is_optional(&self, ty: &str) -> bool {
-        self.is_primitive(ty) || ty.ends_with('*')
+/// Generates the `Visit`, `VisitMut` or `Fold` trait, containing a method for each node type,
+/// along with the `walk_*` functions which recurse into the children of each node.
+fn make_visitors(
+    out: &mut BufWriter<File>,
+    struct_defs: &HashMap<String, HashMap<String, Struct>>,
+    type_resolver: &TypeResolver,
+    mode: VisitorMode,
+) -> std::io::Result<()> {
+    let mut structs = Vec::new();
+    for section in &NODE_SECTIONS {
+        for (name, def) in &struct_defs[*section] {
+            if !IGNORE_NODES.iter().any(|x| name.eq(x)) && !def.fields.is_empty() {
+                structs.push((name.as_str(), def));
+            }
+        }
     }
+    structs.sort_by_key(|x| x.0);
+    let names = structs
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<HashSet<_>>();
+    let mut values = struct_defs["nodes/value"]
+        .iter()
+        .filter(|(_, def)| !def.fields.is_empty())
+        .collect::<Vec<_>>();
+    values.sort_by_key(|x| x.0);
 
-    pub fn custom_deserializer(ty: &str) -> Option<(&str, bool)> {
-        match ty {
-            "[]Node" => Some(("crate::serde::deserialize_node_array", false)),
-            "List*" => Some(("crate::serde::deserialize_node_array_opt", true)),
-            "String*" => Some(("crate::serde::deserialize_nested_string_opt", true)),
+    // The trait itself, which by default walks every child
+    let header = match mode {
+        VisitorMode::Visit => [
+            "/// A visitor over an immutable abstract syntax tree. Each method walks the children of the",
+            "/// node by default, so implementations only need to override the nodes they're interested in.",
+            "/// When overriding a method, call the corresponding `walk_*` function to continue into its",
+            "/// children.",
+            "pub trait Visit<'ast> {",
+        ],
+        VisitorMode::VisitMut => [
+            "/// A visitor over a mutable abstract syntax tree, allowing nodes to be modified in place. Each",
+            "/// method walks the children of the node by default, so implementations only need to override",
+            "/// the nodes they're interested in. When overriding a method, call the corresponding `walk_*`",
+            "/// function to continue into its children.",
+            "pub trait VisitMut {",
+        ],
+        VisitorMode::Fold => [
+            "/// Consumes an abstract syntax tree, building a new tree from the result of each method. Each",
+            "/// method folds the children of the node by default. Override `fold_node` in order to replace",
+            "/// a node with a different variant, otherwise override the method for the type of interest and",
+            "/// call the corresponding `walk_*` function to continue into its children.",
+            "pub trait Fold {",
+        ],
+    };
+    for line in header {
+        writeln!(out, "{line}")?;
+    }
+    writeln!(out, "    {} {{", mode.method_signature("Node"))?;
+    writeln!(out, "        {}(self, node)", mode.walk("Node"))?;
+    writeln!(out, "    }}")?;
+    for (name, _) in &structs {
+        writeln!(out)?;
+        writeln!(out, "    {} {{", mode.method_signature(name))?;
+        writeln!(out, "        {}(self, node)", mode.walk(name))?;
+        writeln!(out, "    }}")?;
+    }
+    // Value nodes don't contain any children, so receive their fields instead. When folding these
+    // return a node, since there is no other type to return.
+    for (name, def) in &values {
+        let fields = visitor_value_fields(name, def, type_resolver);
+        let parameters = fields
+            .iter()
+            .map(|(variable, rust_type)| mode.parameter(variable, rust_type))
+            .collect::<Vec<_>>();
+        writeln!(out)?;
+        let method = mode.method(name);
+        if mode == VisitorMode::Fold {
+            let variables = fields
+                .iter()
+                .map(|(variable, _)| {
+                    if is_boxed_value(name, variable, type_resolver) {
+                        format!("{variable}: Box::new({variable})")
+                    } else {
+                        variable.clone()
+                    }
+                })
+                .collect::<Vec<_>>();
+            writeln!(
+                out,
+                "    fn {method}(&mut self, {}) -> Node {{",
+                parameters.join(", ")
+            )?;
+            writeln!(out, "        Node::{name} {{ {} }}", variables.join(", "))?;
+            writeln!(out, "    }}")?;
+        } else {
+            writeln!(
+                out,
+                "    fn {method}(&mut self, {}) {{}}",
+                parameters.join(", ")
+            )?;
+        }
+    }
+    writeln!(out, "}}")?;
+
+    // Walking a node dispatches to the method for its type
+    writeln!(out)?;
+    writeln!(out, "{} {{", mode.walk_signature("Node"))?;
+    writeln!(out, "    match node {{")?;
+    for (name, _) in &structs {
+        let method = mode.method(name);
+        match mode {
+            VisitorMode::Fold if type_resolver.is_boxed(name) => writeln!(
+                out,
+                "        Node::{name}(value) => Node::{name}(Box::new(folder.{method}(*value))),"
+            )?,
+            VisitorMode::Fold => writeln!(
+                out,
+                "        Node::{name}(value) => Node::{name}(folder.{method}(value)),"
+            )?,
+            _ => writeln!(
+                out,
+                "        Node::{name}(value) => visitor.{method}(value),"
+            )?,
+        }
+    }
+    for (name, def) in &values {
+        let variables = visitor_value_fields(name, def, type_resolver)
+            .into_iter()
+            .map(|(variable, _)| variable)
+            .collect::<Vec<_>>();
+        let receiver = match mode {
+            VisitorMode::Fold => "folder",
+            _ => "visitor",
+        };
+        // Folding moves a boxed value out of its box, whereas references to it are coerced
+        let arguments = variables
+            .iter()
+            .map(|variable| match mode {
+                VisitorMode::Fold if is_boxed_value(name, variable, type_resolver) => {
+                    format!("*{variable}")
+                }
+                _ => variable.clone(),
+            })
+            .collect::<Vec<_>>();
+        writeln!(
+            out,
+            "        Node::{name} {{ {} }} => {receiver}.{}({}),",
+            variables.join(", "),
+            mode.method(name),
+            arguments.join(", "),
+        )?;
+    }
+    match mode {
+        VisitorMode::Fold => writeln!(out, "        node => node,")?,
+        _ => writeln!(out, "        _ => {{}}")?,
+    }
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
+
+    // With each struct visiting any field which contains a node
+    for (name, def) in &structs {
+        let children = struct_fields(def)
+            .into_iter()
+            .filter_map(|(variable, _, c_type)| {
+                let rust_type = type_resolver.resolve(c_type);
+                visit_child(mode, &rust_type, &names, &variable).map(|visit| (variable, visit))
+            })
+            .collect::<Vec<_>>();
+        writeln!(out)?;
+        writeln!(out, "{} {{", mode.walk_signature(name))?;
+        if mode == VisitorMode::Fold {
+            if children.is_empty() {
+                writeln!(out, "    node")?;
+            } else {
+                writeln!(out, "    {name} {{")?;
+                for (variable, fold) in children {
+                    writeln!(out, "        {variable}: {fold},")?;
+                }
+                writeln!(out, "        ..node")?;
+                writeln!(out, "    }}")?;
+            }
+        } else if !children.is_empty() {
+            let variables = children
+                .iter()
+                .map(|(variable, _)| variable.as_str())
+                .collect::<Vec<_>>();
+            writeln!(
+                out,
+                "    let {name} {{ {}, .. }} = node;",
+                variables.join(", ")
+            )?;
+            for (_, visit) in children {
+                writeln!(out, "    {visit}")?;
+            }
+        }
+        writeln!(out, "}}")?;
+    }
+    Ok(())
+}
+
+/// Whether the field of a value node is boxed within the `Node` enum, which is only the case for
+/// the value of an `A_Const`.
+fn is_boxed_value(name: &str, variable: &str, type_resolver: &TypeResolver) -> bool {
+    name == "A_Const" && variable == "val" && type_resolver.is_boxed(name)
+}
+
+/// The fields of a value node along with their types, as generated within the `Node` enum.
+fn visitor_value_fields(
+    name: &str,
+    def: &Struct,
+    type_resolver: &TypeResolver,
+) -> Vec<(String, String)> {
+    if name == "A_Const" {
+        return vec![
+            ("val".into(), "ConstValue".into()),
+            ("location".into(), "ParseLoc".into()),
+        ];
+    }
+    value_fields(def)
+        .into_iter()
+        .map(|(variable, _, c_type)| {
+            let rust_type = type_resolver.resolve(c_type);
+            if rust_type.starts_with("Option<") {
+                (variable, rust_type)
+            } else {
+                (variable, format!("Option<{rust_type}>"))
+            }
+        })
+        .collect()
+}
+
+/// Determines how a visitor recurses into a field of the given type. When visiting, this is a
+/// statement where `value` is a variable holding a reference to the field. When folding, this is
+/// an expression which folds the field of `node` named `value`. Fields which can't contain a node
+/// are skipped.
+fn visit_child(
+    mode: VisitorMode,
+    rust_type: &str,
+    structs: &HashSet<&str>,
+    value: &str,
+) -> Option<String> {
+    let (optional, inner) = match rust_type.strip_prefix("Option<") {
+        Some(inner) => (true, inner.strip_suffix('>')?),
+        None => (false, rust_type),
+    };
+    let (list, boxed) = match (inner.strip_prefix("Vec<"), inner.strip_prefix("Box<")) {
+        (Some(inner), _) => (true, inner.strip_suffix('>')?),
+        (_, Some(inner)) => (false, inner.strip_suffix('>')?),
+        _ => return None,
+    };
+    if boxed != "Node" && (list || !structs.contains(boxed)) {
+        return None;
+    }
+    let method = mode.method(boxed);
+    let visit = match (mode, optional, list) {
+        (VisitorMode::Fold, false, false) => format!("Box::new(folder.{method}(*node.{value}))"),
+        (VisitorMode::Fold, true, false) => {
+            format!("node.{value}.map(|value| Box::new(folder.{method}(*value)))")
+        }
+        (VisitorMode::Fold, false, true) => {
+            format!("node.{value}.into_iter().map(|node| folder.{method}(node)).collect()")
+        }
+        (VisitorMode::Fold, true, true) => format!(
+            "node.{value}.map(|nodes| nodes.into_iter().map(|node| folder.{method}(node)).collect())"
+        ),
+        (_, false, false) => format!("visitor.{method}({value});"),
+        (_, true, false) => format!("if let Some(value) = {value} {{ visitor.{method}(value); }}"),
+        (_, false, true) => format!("for node in {value} {{ visitor.{method}(node); }}"),
+        (_, true, true) => format!(
+            "if let Some(nodes) = {value} {{ for node in nodes {{ visitor.{method}(node); }} }}"
+        ),
+    };
+    Some(visit)
+}
+
+/// Generates `StructuralHash` for `Node` and every struct and enum within it. This compares and
+/// hashes each field other than locations, which differ between otherwise identical statements.
+fn make_structural(
+    out: &mut BufWriter<File>,
+    struct_defs: &HashMap<String, HashMap<String, Struct>>,
+    enum_defs: &HashMap<String, HashMap<String, Enum>>,
+    type_resolver: &TypeResolver,
+) -> std::io::Result<()> {
+    let mut structs = Vec::new();
+    for section in &NODE_SECTIONS {
+        for (name, def) in &struct_defs[*section] {
+            if !IGNORE_NODES.iter().any(|x| name.eq(x)) {
+                structs.push((name.as_str(), def));
+            }
+        }
+    }
+    structs.sort_by_key(|x| x.0);
+    let mut values = struct_defs["nodes/value"].iter().collect::<Vec<_>>();
+    values.sort_by_key(|x| x.0);
+
+    // Enums don't contain locations, so use their derived implementations
+    let mut enums = ENUM_SECTIONS
+        .iter()
+        .flat_map(|section| enum_defs[*section].keys())
+        .collect::<Vec<_>>();
+    enums.sort();
+    for name in enums {
+        writeln!(out, "impl StructuralHash for {name} {{")?;
+        writeln!(
+            out,
+            "    fn structural_eq(&self, other: &Self, options: &StructuralOptions) -> bool {{"
+        )?;
+        writeln!(out, "        self == other")?;
+        writeln!(out, "    }}")?;
+        writeln!(out)?;
+        writeln!(
+            out,
+            "    fn structural_hash<H: Hasher>(&self, state: &mut H, options: &StructuralOptions) {{"
+        )?;
+        writeln!(out, "        Hash::hash(self, state)")?;
+        writeln!(out, "    }}")?;
+        writeln!(out, "}}")?;
+        writeln!(out)?;
+    }
+
+    // Nodes compare their variants, and then the contents of the variant
+    writeln!(out, "impl StructuralHash for Node {{")?;
+    writeln!(
+        out,
+        "    fn structural_eq(&self, other: &Self, options: &StructuralOptions) -> bool {{"
+    )?;
+    writeln!(out, "        match (self, other) {{")?;
+    for (name, def) in &structs {
+        if def.fields.is_empty() {
+            writeln!(out, "            (Node::{name}, Node::{name}) => true,")?;
+        } else {
+            writeln!(
+                out,
+                "            (Node::{name}(left), Node::{name}(right)) => left.structural_eq(right, options),"
+            )?;
+        }
+    }
+    for (name, def) in &values {
+        let fields = visitor_value_fields(name, def, type_resolver)
+            .into_iter()
+            .filter(|(variable, rust_type)| !is_location(variable, rust_type))
+            .map(|(variable, _)| variable)
+            .collect::<Vec<_>>();
+        let pattern = |prefix: &str| {
+            fields
+                .iter()
+                .map(|field| format!("{field}: {prefix}_{field}, "))
+                .collect::<String>()
+        };
+        let comparisons = fields
+            .iter()
+            .map(|field| format!("left_{field}.structural_eq(right_{field}, options)"))
+            .collect::<Vec<_>>();
+        let comparison = if comparisons.is_empty() {
+            "true".to_string()
+        } else {
+            comparisons.join(" && ")
+        };
+        writeln!(
+            out,
+            "            (Node::{name} {{ {}.. }}, Node::{name} {{ {}.. }}) => {comparison},",
+            pattern("left"),
+            pattern("right"),
+        )?;
+    }
+    writeln!(
+        out,
+        "            (Node::Unknown {{ name: left_name, raw: left_raw }}, Node::Unknown {{ name: right_name, raw: right_raw }}) => left_name == right_name && left_raw == right_raw,"
+    )?;
+    writeln!(out, "            _ => false,")?;
+    writeln!(out, "        }}")?;
+    writeln!(out, "    }}")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "    fn structural_hash<H: Hasher>(&self, state: &mut H, options: &StructuralOptions) {{"
+    )?;
+    writeln!(out, "        std::mem::discriminant(self).hash(state);")?;
+    writeln!(out, "        match self {{")?;
+    for (name, def) in &structs {
+        if !def.fields.is_empty() {
+            writeln!(
+                out,
+                "            Node::{name}(value) => value.structural_hash(state, options),"
+            )?;
+        }
+    }
+    for (name, def) in &values {
+        let fields = visitor_value_fields(name, def, type_resolver)
+            .into_iter()
+            .filter(|(variable, rust_type)| !is_location(variable, rust_type))
+            .map(|(variable, _)| variable)
+            .collect::<Vec<_>>();
+        if fields.is_empty() {
+            continue;
+        }
+        let hashes = fields
+            .iter()
+            .map(|field| format!("{field}.structural_hash(state, options);"))
+            .collect::<Vec<_>>();
+        writeln!(
+            out,
+            "            Node::{name} {{ {}, .. }} => {{ {} }}",
+            fields.join(", "),
+            hashes.join(" "),
+        )?;
+    }
+    writeln!(
+        out,
+        "            Node::Unknown {{ name, raw }} => {{ name.hash(state); raw.hash(state); }}"
+    )?;
+    writeln!(out, "            _ => {{}}")?;
+    writeln!(out, "        }}")?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
+
+    // With each struct comparing and hashing its fields, skipping locations along with alias
+    // names when requested
+    for (name, def) in &structs {
+        let fields = struct_fields(def)
+            .into_iter()
+            .filter(|(_, field, c_type)| !is_location(field, c_type))
+            .map(|(variable, field, _)| {
+                let ignored = (*name == "Alias" && field == "aliasname")
+                    .then_some("options.ignore_alias_names");
+                (variable, ignored)
+            })
+            .collect::<Vec<_>>();
+        writeln!(out)?;
+        writeln!(out, "impl StructuralHash for {name} {{")?;
+        writeln!(
+            out,
+            "    fn structural_eq(&self, other: &Self, options: &StructuralOptions) -> bool {{"
+        )?;
+        let comparisons = fields
+            .iter()
+            .map(|(variable, ignored)| {
+                let comparison =
+                    format!("self.{variable}.structural_eq(&other.{variable}, options)");
+                match ignored {
+                    Some(condition) => format!("({condition} || {comparison})"),
+                    None => comparison,
+                }
+            })
+            .collect::<Vec<_>>();
+        if comparisons.is_empty() {
+            writeln!(out, "        true")?;
+        } else {
+            writeln!(out, "        {}", comparisons.join("\n            && "))?;
+        }
+        writeln!(out, "    }}")?;
+        writeln!(out)?;
+        writeln!(
+            out,
+            "    fn structural_hash<H: Hasher>(&self, state: &mut H, options: &StructuralOptions) {{"
+        )?;
+        for (variable, ignored) in &fields {
+            match ignored {
+                Some(condition) => writeln!(
+                    out,
+                    "        if !{condition} {{ self.{variable}.structural_hash(state, options); }}"
+                )?,
+                None => writeln!(
+                    out,
+                    "        self.{variable}.structural_hash(state, options);"
+                )?,
+            }
+        }
+        writeln!(out, "    }}")?;
+        writeln!(out, "}}")?;
+    }
+    Ok(())
+}
+
+/// Generates `RawNode`, a view of `Node` which excludes the variants that are never produced by the
+/// raw parser, along with the conversion from `Node`.
+fn make_raw_nodes(
+    out: &mut BufWriter<File>,
+    struct_defs: &HashMap<String, HashMap<String, Struct>>,
+    type_resolver: &TypeResolver,
+) -> std::io::Result<()> {
+    let mut structs = Vec::new();
+    let mut analyzed = Vec::new();
+    for section in &NODE_SECTIONS {
+        for (name, def) in &struct_defs[*section] {
+            if IGNORE_NODES.iter().any(|x| name.eq(x)) {
+                continue;
+            }
+            if ANALYZED_NODES.iter().any(|x| name.eq(x)) {
+                analyzed.push((name.as_str(), def));
+            } else {
+                structs.push((name.as_str(), def));
+            }
+        }
+    }
+    structs.sort_by_key(|x| x.0);
+    analyzed.sort_by_key(|x| x.0);
+    let mut values = struct_defs["nodes/value"].iter().collect::<Vec<_>>();
+    values.sort_by_key(|x| x.0);
+
+    writeln!(
+        out,
+        "/// A node which may appear in a raw parse tree, borrowed from a [`Node`]."
+    )?;
+    writeln!(out, "#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]")?;
+    writeln!(out, "pub enum RawNode<'a> {{")?;
+    for (name, def) in &structs {
+        if def.fields.is_empty() {
+            writeln!(out, "    {name},")?;
+        } else {
+            writeln!(out, "    {name}(&'a {name}),")?;
+        }
+    }
+    writeln!(out, "    // Value nodes")?;
+    for (name, def) in &values {
+        let fields = visitor_value_fields(name, def, type_resolver)
+            .into_iter()
+            .map(|(variable, rust_type)| format!("{variable}: &'a {rust_type}"))
+            .collect::<Vec<_>>();
+        writeln!(out, "    {name} {{ {} }},", fields.join(", "))?;
+    }
+    writeln!(
+        out,
+        "    Unknown {{ name: &'a str, raw: &'a serde_json::Value }},"
+    )?;
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+
+    // Analyzed nodes are listed explicitly, so that any new node is treated as a raw node
+    writeln!(out, "impl<'a> TryFrom<&'a Node> for RawNode<'a> {{")?;
+    writeln!(out, "    type Error = NotRawNode<'a>;")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "    fn try_from(node: &'a Node) -> Result<Self, Self::Error> {{"
+    )?;
+    writeln!(out, "        Ok(match node {{")?;
+    for (name, def) in &structs {
+        if def.fields.is_empty() {
+            writeln!(out, "            Node::{name} => RawNode::{name},")?;
+        } else {
+            writeln!(
+                out,
+                "            Node::{name}(value) => RawNode::{name}(value),"
+            )?;
+        }
+    }
+    for (name, def) in &values {
+        let fields = visitor_value_fields(name, def, type_resolver)
+            .into_iter()
+            .map(|(variable, _)| variable)
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(
+            out,
+            "            Node::{name} {{ {fields} }} => RawNode::{name} {{ {fields} }},"
+        )?;
+    }
+    writeln!(
+        out,
+        "            Node::Unknown {{ name, raw }} => RawNode::Unknown {{ name, raw }},"
+    )?;
+    for (name, def) in &analyzed {
+        if def.fields.is_empty() {
+            writeln!(
+                out,
+                "            Node::{name} => return Err(NotRawNode(node)),"
+            )?;
+        } else {
+            writeln!(
+                out,
+                "            Node::{name}(_) => return Err(NotRawNode(node)),"
+            )?;
+        }
+    }
+    writeln!(out, "        }})")?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+
+    // Children are found using `Visit`, which passes each of them on as a raw node
+    writeln!(
+        out,
+        "/// Visits each child of the given node, calling `visit_raw_node` for those produced by the raw"
+    )?;
+    writeln!(out, "/// parser and `visit_analyzed_node` for any other.")?;
+    writeln!(
+        out,
+        "pub fn walk_raw_node<'ast, V: RawVisit<'ast> + ?Sized>(visitor: &mut V, node: RawNode<'ast>) {{"
+    )?;
+    writeln!(out, "    let mut children = RawChildren(visitor);")?;
+    writeln!(out, "    match node {{")?;
+    for (name, def) in &structs {
+        if def.fields.is_empty() {
+            writeln!(out, "        RawNode::{name} => {{}}")?;
+        } else {
+            writeln!(
+                out,
+                "        RawNode::{name}(value) => visit::walk_{}(&mut children, value),",
+                name.to_snake_case()
+            )?;
+        }
+    }
+    for (name, _) in &values {
+        writeln!(out, "        RawNode::{name} {{ .. }} => {{}}")?;
+    }
+    writeln!(out, "        RawNode::Unknown {{ .. }} => {{}}")?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// Generates a lookup of the fields of each struct within the JSON produced by `libpg_query`.
+/// Serde ignores fields which it doesn't recognize, so this is used to find them when parsing
+/// leniently.
+fn make_json_fields(
+    out: &mut BufWriter<File>,
+    struct_defs: &HashMap<String, HashMap<String, Struct>>,
+    type_resolver: &TypeResolver,
+) -> std::io::Result<()> {
+    let mut structs = Vec::new();
+    for section in NODE_SECTIONS.iter().chain(["nodes/value"].iter()) {
+        for (name, def) in &struct_defs[*section] {
+            if !IGNORE_NODES.iter().any(|x| name.eq(x)) {
+                structs.push((name.as_str(), def));
+            }
+        }
+    }
+    structs.sort_by_key(|x| x.0);
+    let is_struct = |ty: &str| structs.iter().any(|(name, _)| *name == ty);
+
+    writeln!(out)?;
+    writeln!(
+        out,
+        "/// The fields of each struct within the JSON produced by `libpg_query`, along with the struct"
+    )?;
+    writeln!(
+        out,
+        "/// held by the field when it is stored inline rather than as a node."
+    )?;
+    writeln!(
+        out,
+        "pub(crate) fn json_fields(name: &str) -> Option<&'static [(&'static str, Option<&'static str>)]> {{"
+    )?;
+    writeln!(out, "    match name {{")?;
+    for (name, def) in &structs {
+        // A_Const holds its value within a union, which is written as the field of that value
+        if *name == "A_Const" {
+            writeln!(
+                out,
+                "        \"A_Const\" => Some(&[(\"isnull\", None), (\"ival\", None), (\"fval\", None), (\"boolval\", None), (\"sval\", None), (\"bsval\", None), (\"location\", None)]),"
+            )?;
+            continue;
+        }
+        let fields = def
+            .fields
+            .iter()
+            .filter_map(|field| match (&field.name, &field.c_type) {
+                (Some(name), Some(c_type)) => {
+                    let inline = c_type
+                        .strip_suffix('*')
+                        .filter(|ty| !type_resolver.is_primitive(c_type) && is_struct(ty));
+                    Some(match inline {
+                        Some(ty) => format!("(\"{name}\", Some(\"{ty}\"))"),
+                        None => format!("(\"{name}\", None)"),
+                    })
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        writeln!(out, "        \"{name}\" => Some(&[{}]),", fields.join(", "))?;
+    }
+    writeln!(out, "        _ => None,")?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// Generates methods on `Node` to check for, and downcast to, each variant. Struct variants also
+/// implement `NodeVariant`, which allows filtering an iterator of nodes by variant.
+fn make_accessors(
+    out: &mut BufWriter<File>,
+    struct_defs: &HashMap<String, HashMap<String, Struct>>,
+    type_resolver: &TypeResolver,
+) -> std::io::Result<()> {
+    let mut structs = Vec::new();
+    for section in &NODE_SECTIONS {
+        for (name, def) in &struct_defs[*section] {
+            if !IGNORE_NODES.iter().any(|x| name.eq(x)) {
+                structs.push((name.as_str(), def));
+            }
+        }
+    }
+    structs.sort_by_key(|x| x.0);
+    let mut values = struct_defs["nodes/value"].iter().collect::<Vec<_>>();
+    values.sort_by_key(|x| x.0);
+
+    writeln!(out)?;
+    writeln!(out, "impl Node {{")?;
+
+    // PostgreSQL records the location of many nodes, using -1 when unknown
+    writeln!(
+        out,
+        "    /// The byte offset of this node within the input, if PostgreSQL recorded one."
+    )?;
+    writeln!(out, "    pub fn location(&self) -> Option<usize> {{")?;
+    writeln!(out, "        let location = match self {{")?;
+    for (name, def) in &structs {
+        if def
+            .fields
+            .iter()
+            .any(|field| field.name.as_deref() == Some("location"))
+        {
+            writeln!(out, "            Node::{name}(value) => value.location,")?;
+        }
+    }
+    for (name, def) in &values {
+        if def
+            .fields
+            .iter()
+            .any(|field| field.name.as_deref() == Some("location"))
+        {
+            writeln!(
+                out,
+                "            Node::{name} {{ location, .. }} => *location,"
+            )?;
+        }
+    }
+    writeln!(out, "            _ => return None,")?;
+    writeln!(out, "        }};")?;
+    writeln!(out, "        usize::try_from(location).ok()")?;
+    writeln!(out, "    }}")?;
+    writeln!(out)?;
+
+    for (name, def) in &structs {
+        let function = name.to_snake_case();
+        if def.fields.is_empty() {
+            writeln!(out, "    pub fn is_{function}(&self) -> bool {{")?;
+            writeln!(out, "        matches!(self, Node::{name})")?;
+            writeln!(out, "    }}")?;
+            writeln!(out)?;
+            continue;
+        }
+        writeln!(out, "    pub fn is_{function}(&self) -> bool {{")?;
+        writeln!(out, "        matches!(self, Node::{name}(_))")?;
+        writeln!(out, "    }}")?;
+        writeln!(out)?;
+        for (suffix, receiver, result) in [
+            ("", "&self", format!("&{name}")),
+            ("_mut", "&mut self", format!("&mut {name}")),
+        ] {
+            writeln!(
+                out,
+                "    pub fn as_{function}{suffix}({receiver}) -> Option<{result}> {{"
+            )?;
+            writeln!(out, "        match self {{")?;
+            writeln!(out, "            Node::{name}(value) => Some(value),")?;
+            writeln!(out, "            _ => None,")?;
+            writeln!(out, "        }}")?;
+            writeln!(out, "    }}")?;
+            writeln!(out)?;
+        }
+        let value = if type_resolver.is_boxed(name) {
+            "*value"
+        } else {
+            "value"
+        };
+        writeln!(out, "    pub fn into_{function}(self) -> Option<{name}> {{")?;
+        writeln!(out, "        match self {{")?;
+        writeln!(out, "            Node::{name}(value) => Some({value}),")?;
+        writeln!(out, "            _ => None,")?;
+        writeln!(out, "        }}")?;
+        writeln!(out, "    }}")?;
+        writeln!(out)?;
+    }
+
+    // Value nodes downcast to their value, being the only field (or `val` for A_Const)
+    for (name, def) in &values {
+        let function = name.to_snake_case();
+        writeln!(out, "    pub fn is_{function}(&self) -> bool {{")?;
+        writeln!(out, "        matches!(self, Node::{name} {{ .. }})")?;
+        writeln!(out, "    }}")?;
+        writeln!(out)?;
+        let value = match visitor_value_fields(name, def, type_resolver)
+            .into_iter()
+            .next()
+        {
+            Some(value) if *name == "A_Const" || def.fields.len() == 1 => value,
+            _ => continue,
+        };
+        let (variable, rust_type) = value;
+        for (suffix, receiver, result) in [
+            ("", "&self", format!("&{rust_type}")),
+            ("_mut", "&mut self", format!("&mut {rust_type}")),
+            ("", "self", rust_type.clone()),
+        ] {
+            let prefix = if receiver == "self" { "into" } else { "as" };
+            // References to a boxed value are coerced, whereas the value must be moved out
+            let deref = if receiver == "self" && type_resolver.is_boxed(name) {
+                "*"
+            } else {
+                ""
+            };
+            writeln!(
+                out,
+                "    pub fn {prefix}_{function}{suffix}({receiver}) -> Option<{result}> {{"
+            )?;
+            writeln!(out, "        match self {{")?;
+            writeln!(
+                out,
+                "            Node::{name} {{ {variable}, .. }} => Some({deref}{variable}),"
+            )?;
+            writeln!(out, "            _ => None,")?;
+            writeln!(out, "        }}")?;
+            writeln!(out, "    }}")?;
+            writeln!(out)?;
+        }
+    }
+    writeln!(out, "}}")?;
+
+    for (name, def) in &structs {
+        if def.fields.is_empty() {
+            continue;
+        }
+        let function = name.to_snake_case();
+        writeln!(out)?;
+        writeln!(out, "impl NodeVariant for {name} {{")?;
+        writeln!(out, "    fn from_node(node: &Node) -> Option<&Self> {{")?;
+        writeln!(out, "        node.as_{function}()")?;
+        writeln!(out, "    }}")?;
+        writeln!(out)?;
+        writeln!(
+            out,
+            "    fn from_node_mut(node: &mut Node) -> Option<&mut Self> {{"
+        )?;
+        writeln!(out, "        node.as_{function}_mut()")?;
+        writeln!(out, "    }}")?;
+        writeln!(out)?;
+        writeln!(out, "    fn from_node_owned(node: Node) -> Option<Self> {{")?;
+        writeln!(out, "        node.into_{function}()")?;
+        writeln!(out, "    }}")?;
+        writeln!(out, "}}")?;
+    }
+    Ok(())
+}
+
+/// Generates a builder for each struct, with a method to set each field. Nodes are accepted as
+/// anything which converts into a `Node`, so that builders can be nested.
+fn make_builders(
+    out: &mut BufWriter<File>,
+    struct_defs: &HashMap<String, HashMap<String, Struct>>,
+    type_resolver: &TypeResolver,
+) -> std::io::Result<()> {
+    for section in &NODE_SECTIONS {
+        let map = &struct_defs[*section];
+        let mut map = map.iter().collect::<Vec<_>>();
+        map.sort_by_key(|x| x.0);
+
+        for (name, def) in map {
+            if IGNORE_NODES.iter().any(|x| name.eq(x)) || def.fields.is_empty() {
+                continue;
+            }
+            let builder = format!("{name}Builder");
+            writeln!(
+                out,
+                "/// A builder for [`{name}`], created using [`{name}::builder`]."
+            )?;
+            writeln!(out, "#[derive(Clone, Debug, Default)]")?;
+            writeln!(out, "pub struct {builder}({name});")?;
+            writeln!(out)?;
+            writeln!(out, "impl {name} {{")?;
+            writeln!(
+                out,
+                "    /// Creates a builder with each field set to its default value."
+            )?;
+            writeln!(out, "    pub fn builder() -> {builder} {{")?;
+            writeln!(out, "        {builder}::default()")?;
+            writeln!(out, "    }}")?;
+            writeln!(out, "}}")?;
+            writeln!(out)?;
+            writeln!(out, "impl {builder} {{")?;
+            for (variable, _, c_type) in struct_fields(def) {
+                let rust_type = type_resolver.resolve(c_type);
+                let (parameter, value) = builder_setter(&rust_type);
+                writeln!(
+                    out,
+                    "    pub fn {variable}(mut self, value: {parameter}) -> Self {{"
+                )?;
+                writeln!(out, "        self.0.{variable} = {value};")?;
+                writeln!(out, "        self")?;
+                writeln!(out, "    }}")?;
+                writeln!(out)?;
+            }
+            writeln!(out, "    pub fn build(self) -> {name} {{")?;
+            writeln!(out, "        self.0")?;
+            writeln!(out, "    }}")?;
+            writeln!(out, "}}")?;
+            writeln!(out)?;
+            writeln!(out, "impl From<{builder}> for {name} {{")?;
+            writeln!(out, "    fn from(builder: {builder}) -> Self {{")?;
+            writeln!(out, "        builder.0")?;
+            writeln!(out, "    }}")?;
+            writeln!(out, "}}")?;
+            writeln!(out)?;
+            writeln!(out, "impl From<{builder}> for Node {{")?;
+            writeln!(out, "    fn from(builder: {builder}) -> Self {{")?;
+            writeln!(out, "        Node::from(builder.0)")?;
+            writeln!(out, "    }}")?;
+            writeln!(out, "}}")?;
+            writeln!(out)?;
+            writeln!(out, "impl From<{name}> for Node {{")?;
+            writeln!(out, "    fn from(value: {name}) -> Self {{")?;
+            if type_resolver.is_boxed(name) {
+                writeln!(out, "        Node::{name}(Box::new(value))")?;
+            } else {
+                writeln!(out, "        Node::{name}(value)")?;
+            }
+            writeln!(out, "    }}")?;
+            writeln!(out, "}}")?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+/// The parameter type of a builder method setting a field of the given type, along with the
+/// expression which converts `value` into the field.
+fn builder_setter(rust_type: &str) -> (String, String) {
+    let (optional, inner) = match rust_type
+        .strip_prefix("Option<")
+        .and_then(|inner| inner.strip_suffix('>'))
+    {
+        Some(inner) => (true, inner),
+        None => (false, rust_type),
+    };
+    let (parameter, value) = if inner == "Vec<Node>" {
+        (
+            "impl IntoIterator<Item = impl Into<Node>>".to_string(),
+            "value.into_iter().map(Into::into).collect()".to_string(),
+        )
+    } else if let Some(boxed) = inner
+        .strip_prefix("Box<")
+        .and_then(|inner| inner.strip_suffix('>'))
+    {
+        (
+            format!("impl Into<{boxed}>"),
+            "Box::new(value.into())".to_string(),
+        )
+    } else if inner == "String" {
+        ("impl Into<String>".into(), "value.into()".into())
+    } else {
+        (inner.to_string(), "value".to_string())
+    };
+    if optional {
+        (parameter, format!("Some({value})"))
+    } else {
+        (parameter, value)
+    }
+}
+
+/// Resolves the Rust type for the given C type. When borrowing, strings are replaced with
+/// `Cow<'a, str>` and any type containing them gains the `'a` lifetime.
+fn resolve_type(
+    type_resolver: &TypeResolver,
+    c_type: &str,
+    lifetimes: &HashSet<String>,
+    mode: AstMode,
+) -> String {
+    let resolved = type_resolver.resolve(c_type);
+    if mode == AstMode::Owned {
+        return resolved;
+    }
+
+    let mut borrowed = String::new();
+    let mut identifier = String::new();
+    for ch in resolved.chars().chain(std::iter::once('\0')) {
+        if ch.is_alphanumeric() || ch == '_' {
+            identifier.push(ch);
+            continue;
+        }
+        match identifier.as_str() {
+            "String" => borrowed.push_str("Cow<'a, str>"),
+            "Node" => borrowed.push_str("Node<'a>"),
+            ty if lifetimes.contains(ty) => {
+                borrowed.push_str(ty);
+                borrowed.push_str("<'a>");
+            }
+            ty => borrowed.push_str(ty),
+        }
+        identifier.clear();
+        if ch != '\0' {
+            borrowed.push(ch);
+        }
+    }
+    borrowed
+}
+
+/// Strings require a custom deserializer in order to borrow, since `Option<Cow<str>>` always
+/// allocates by default.
+fn borrowed_deserializer(c_type: &str, mode: AstMode) -> Option<&'static str> {
+    match (mode, c_type) {
+        (AstMode::Borrowed, "char*") => Some("crate::serde::deserialize_borrowed_str_opt"),
+        (AstMode::Borrowed, "String*") => {
+            Some("crate::serde::deserialize_borrowed_nested_string_opt")
+        }
+        _ => None,
+    }
+}
+
+/// Finds all structs which directly or indirectly contain a string or a node.
+fn find_borrowed_structs(
+    sections: &[&str],
+    struct_defs: &HashMap<String, HashMap<String, Struct>>,
+    type_resolver: &TypeResolver,
+) -> HashSet<String> {
+    let mut lifetimes = HashSet::new();
+    loop {
+        let mut changed = false;
+        for (name, def) in sections.iter().flat_map(|section| &struct_defs[*section]) {
+            if lifetimes.contains(name) {
+                continue;
+            }
+            let borrows = def.fields.iter().any(|field| {
+                let (Some(name), Some(c_type)) = (&field.name, &field.c_type) else {
+                    return false;
+                };
+                if name == "type" || name == "xpr" {
+                    return false;
+                }
+                resolve_type(type_resolver, c_type, &lifetimes, AstMode::Borrowed).contains("'a")
+            });
+            if borrows {
+                lifetimes.insert(name.clone());
+                changed = true;
+            }
+        }
+        if !changed {
+            return lifetimes;
+        }
+    }
+}
+
+/// The messages and enums defined within `pg_query.proto`.
+struct ProtoDefinitions {
+    messages: HashMap<String, Vec<ProtoField>>,
+    enums: HashMap<String, Vec<(String, i32)>>,
+}
+
+struct ProtoField {
+    name: String,
+    json_name: Option<String>,
+    ty: String,
+    number: u32,
+    repeated: bool,
+}
+
+impl ProtoField {
+    /// Whether this field represents the given C struct field. The original name is recorded as
+    /// the `json_name` of the field, falling back to the field name itself.
+    fn matches(&self, c_name: &str) -> bool {
+        match &self.json_name {
+            Some(json_name) => json_name == c_name,
+            None => self.name == c_name || self.name == c_name.to_snake_case(),
+        }
+    }
+}
+
+impl ProtoDefinitions {
+    /// A minimal parser for the subset of the protobuf language used by `pg_query.proto`.
+    fn parse(source: &str) -> Self {
+        let tokens = tokenize_proto(source);
+        let mut tokens = tokens.iter().map(String::as_str);
+        let mut messages = HashMap::new();
+        let mut enums = HashMap::new();
+        while let Some(token) = tokens.next() {
+            match token {
+                "message" => {
+                    let name = tokens.next().expect("message name");
+                    expect_token(&mut tokens, "{");
+                    let mut fields = Vec::new();
+                    parse_proto_fields(&mut tokens, &mut fields);
+                    messages.insert(name.to_string(), fields);
+                }
+                "enum" => {
+                    let name = tokens.next().expect("enum name");
+                    expect_token(&mut tokens, "{");
+                    let mut values = Vec::new();
+                    loop {
+                        match tokens.next().expect("enum value") {
+                            "}" => break,
+                            "option" | "reserved" => skip_statement(&mut tokens),
+                            value => {
+                                expect_token(&mut tokens, "=");
+                                let number = tokens
+                                    .next()
+                                    .and_then(|number| number.parse().ok())
+                                    .unwrap_or_else(|| panic!("Invalid value for {}", value));
+                                skip_statement(&mut tokens);
+                                values.push((value.to_string(), number));
+                            }
+                        }
+                    }
+                    enums.insert(name.to_string(), values);
+                }
+                _ => skip_statement(&mut tokens),
+            }
+        }
+        ProtoDefinitions { messages, enums }
+    }
+}
+
+fn parse_proto_fields<'a>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+    fields: &mut Vec<ProtoField>,
+) {
+    loop {
+        match tokens.next().expect("message field") {
+            "}" => return,
+            "oneof" => {
+                tokens.next().expect("oneof name");
+                expect_token(tokens, "{");
+                parse_proto_fields(tokens, fields);
+            }
+            "option" | "reserved" => skip_statement(tokens),
+            token => {
+                let (repeated, ty) = match token {
+                    "repeated" => (true, tokens.next().expect("field type")),
+                    "optional" => (false, tokens.next().expect("field type")),
+                    ty => (false, ty),
+                };
+                let name = tokens.next().expect("field name");
+                expect_token(tokens, "=");
+                let number = tokens
+                    .next()
+                    .and_then(|number| number.parse().ok())
+                    .unwrap_or_else(|| panic!("Invalid field number for {}", name));
+                let mut json_name = None;
+                loop {
+                    match tokens.next().expect("end of field") {
+                        ";" => break,
+                        "json_name" => {
+                            expect_token(tokens, "=");
+                            let value = tokens.next().expect("json_name value");
+                            json_name = Some(value.trim_matches('"').to_string());
+                        }
+                        _ => {}
+                    }
+                }
+                fields.push(ProtoField {
+                    name: name.to_string(),
+                    json_name,
+                    ty: ty.to_string(),
+                    number,
+                    repeated,
+                });
+            }
+        }
+    }
+}
+
+fn expect_token<'a>(tokens: &mut impl Iterator<Item = &'a str>, expected: &str) {
+    let token = tokens.next();
+    assert_eq!(token, Some(expected), "Unexpected token in pg_query.proto");
+}
+
+fn skip_statement<'a>(tokens: &mut impl Iterator<Item = &'a str>) {
+    for token in tokens.by_ref() {
+        if token == ";" {
+            return;
+        }
+    }
+}
+
+/// Splits the protobuf definition into tokens, discarding any comments.
+fn tokenize_proto(source: &str) -> Vec<String> {
+    fn flush(current: &mut String, tokens: &mut Vec<String>) {
+        if !current.is_empty() {
+            tokens.push(std::mem::take(current));
+        }
+    }
+
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut chars = source.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '/' if chars.peek() == Some(&'/') => {
+                flush(&mut current, &mut tokens);
+                for ch in chars.by_ref() {
+                    if ch == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                flush(&mut current, &mut tokens);
+                chars.next();
+                let mut previous = ' ';
+                for ch in chars.by_ref() {
+                    if previous == '*' && ch == '/' {
+                        break;
+                    }
+                    previous = ch;
+                }
+            }
+            '"' => {
+                flush(&mut current, &mut tokens);
+                current.push(ch);
+                for ch in chars.by_ref() {
+                    current.push(ch);
+                    if ch == '"' {
+                        break;
+                    }
+                }
+                flush(&mut current, &mut tokens);
+            }
+            '{' | '}' | '[' | ']' | '(' | ')' | '<' | '>' | ';' | '=' | ',' => {
+                flush(&mut current, &mut tokens);
+                tokens.push(ch.to_string());
+            }
+            ch if ch.is_whitespace() => flush(&mut current, &mut tokens),
+            ch => current.push(ch),
+        }
+    }
+    flush(&mut current, &mut tokens);
+    tokens
+}
+
+/// The types known when generating protobuf decoders and encoders.
+struct ProtoContext<'a> {
+    proto: &'a ProtoDefinitions,
+    type_resolver: &'a TypeResolver,
+    enums: HashSet<&'a str>,
+    structs: HashSet<&'a str>,
+}
+
+impl<'a> ProtoContext<'a> {
+    fn new(
+        proto: &'a ProtoDefinitions,
+        struct_defs: &'a HashMap<String, HashMap<String, Struct>>,
+        enum_defs: &'a HashMap<String, HashMap<String, Enum>>,
+        type_resolver: &'a TypeResolver,
+    ) -> Self {
+        ProtoContext {
+            proto,
+            type_resolver,
+            enums: ENUM_SECTIONS
+                .iter()
+                .flat_map(|section| enum_defs[*section].keys())
+                .map(String::as_str)
+                .collect(),
+            structs: NODE_SECTIONS
+                .iter()
+                .flat_map(|section| struct_defs[*section].keys())
+                .map(String::as_str)
+                .filter(|name| !IGNORE_NODES.contains(name))
+                .collect(),
+        }
+    }
+}
+
+/// How a single protobuf field is decoded into the local variable holding its value.
+enum FieldDecoder {
+    /// The field value is assigned to the variable.
+    Assign(String),
+    /// The statement updates the variable directly, e.g. to append to a list.
+    Statement(String),
+}
+
+/// Generates decoders for the protobuf output of `libpg_query`. Fields are matched using their
+/// original C names, so these decode straight into the same AST as the JSON output.
+fn make_decoders(
+    out: &mut BufWriter<File>,
+    context: &ProtoContext,
+    struct_defs: &HashMap<String, HashMap<String, Struct>>,
+    enum_defs: &HashMap<String, HashMap<String, Enum>>,
+) -> std::io::Result<()> {
+    let proto = context.proto;
+
+    // Enum values are offset by one within the protobuf definition (zero being undefined), so
+    // these are matched by name instead.
+    for section in &ENUM_SECTIONS {
+        let map = &enum_defs[*section];
+        let mut map = map.iter().collect::<Vec<_>>();
+        map.sort_by_key(|x| x.0);
+
+        for (name, def) in map {
+            let Some(values) = proto.enums.get(name) else {
+                continue;
+            };
+            let variants = def
+                .values
+                .iter()
+                .filter_map(|value| value.name.as_deref())
+                .collect::<HashSet<_>>();
+            writeln!(out, "impl ProtoEnum for {name} {{")?;
+            writeln!(out, "    fn from_proto(value: i32) -> Option<Self> {{")?;
+            writeln!(out, "        match value {{")?;
+            for (variant, number) in values {
+                if variants.contains(variant.as_str()) {
+                    writeln!(out, "            {number} => Some({name}::{variant}),")?;
+                }
+            }
+            writeln!(out, "            _ => None,")?;
+            writeln!(out, "        }}")?;
+            writeln!(out, "    }}")?;
+            writeln!(out, "}}")?;
+            writeln!(out)?;
+        }
+    }
+
+    // Structs, keeping track of how each node variant is constructed
+    let mut node_variants = HashMap::new();
+    for section in &NODE_SECTIONS {
+        let map = &struct_defs[*section];
+        let mut map = map.iter().collect::<Vec<_>>();
+        map.sort_by_key(|x| x.0);
+
+        for (name, def) in map {
+            if IGNORE_NODES.iter().any(|x| name.eq(x)) {
+                continue;
+            }
+            let constructor = if def.fields.is_empty() {
+                format!("|_, _| Ok(Node::{name})")
+            } else {
+                format!("decode_variant::<{name}>")
+            };
+            node_variants.insert(name.as_str(), constructor);
+
+            let fields = struct_fields(def);
+            writeln!(out, "impl Decode for {name} {{")?;
+            writeln!(
+                out,
+                "    fn decode(data: &[u8], depth: Depth) -> Result<Self> {{"
+            )?;
+            writeln!(out, "        let depth = depth.nested()?;")?;
+            make_decoder_body(
+                out,
+                context,
+                proto.messages.get(name.as_str()),
+                &fields,
+                "Self",
+                false,
+                "    ",
+            )?;
+            writeln!(out, "    }}")?;
+            writeln!(out, "}}")?;
+            writeln!(out)?;
+        }
+    }
+
+    // Value nodes are variants with inline fields, which are always optional
+    let values = &struct_defs["nodes/value"];
+    let mut values = values.iter().collect::<Vec<_>>();
+    values.sort_by_key(|x| x.0);
+    for (name, def) in values {
+        // A_Const is handled explicitly, the same as when deserializing
+        if name == "A_Const" {
+            node_variants.insert(
+                name.as_str(),
+                "|data, _| decode_const(data).map(|(val, location)| Node::A_Const { val: val.into(), location })".into(),
+            );
+            continue;
+        }
+        let function = format!("decode_{}_node", name.to_snake_case());
+        node_variants.insert(name.as_str(), function.clone());
+
+        let fields = value_fields(def);
+        writeln!(
+            out,
+            "fn {function}(data: &[u8], depth: Depth) -> Result<Node> {{"
+        )?;
+        make_decoder_body(
+            out,
+            context,
+            proto.messages.get(name.as_str()),
+            &fields,
+            &format!("Node::{name}"),
+            true,
+            "",
+        )?;
+        writeln!(out, "}}")?;
+        writeln!(out)?;
+    }
+
+    // Finally the node itself, which is a oneof containing every node type. Each variant is decoded
+    // through a function pointer, since matching on the variants directly within `decode_node` gives
+    // it a stack frame large enough for all of them, which is paid for at every level of the tree.
+    writeln!(
+        out,
+        "pub(super) fn decode_node(data: &[u8], depth: Depth) -> Result<Option<Node>> {{"
+    )?;
+    writeln!(out, "    let depth = depth.nested()?;")?;
+    writeln!(out, "    let mut reader = Reader::new(data);")?;
+    writeln!(out, "    let mut node = None;")?;
+    writeln!(out, "    while !reader.is_empty() {{")?;
+    writeln!(out, "        match reader.read_key()? {{")?;
+    writeln!(
+        out,
+        "            (field, WireType::LengthDelimited) => match node_decoder(field) {{"
+    )?;
+    writeln!(
+        out,
+        "                Some(decode) => node = Some(decode(reader.read_bytes()?, depth)?),"
+    )?;
+    writeln!(
+        out,
+        "                None => reader.skip(WireType::LengthDelimited)?,"
+    )?;
+    writeln!(out, "            }},")?;
+    writeln!(
+        out,
+        "            (_, wire_type) => reader.skip(wire_type)?,"
+    )?;
+    writeln!(out, "        }}")?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "    Ok(node)")?;
+    writeln!(out, "}}")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "fn node_decoder(field: u32) -> Option<fn(&[u8], Depth) -> Result<Node>> {{"
+    )?;
+    writeln!(
+        out,
+        "    let decode: fn(&[u8], Depth) -> Result<Node> = match field {{"
+    )?;
+    for field in proto.messages.get("Node").into_iter().flatten() {
+        if let Some(constructor) = node_variants.get(field.ty.as_str()) {
+            writeln!(out, "        {} => {},", field.number, constructor)?;
+        }
+    }
+    writeln!(out, "        _ => return None,")?;
+    writeln!(out, "    }};")?;
+    writeln!(out, "    Some(decode)")?;
+    writeln!(out, "}}")?;
+
+    // Along with the message held by each field which holds one, so that the depth of a tree can be
+    // measured without decoding it. Messages are numbered in order of their name.
+    let mut messages = proto
+        .messages
+        .keys()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    messages.sort();
+    let number = |name: &str| messages.binary_search(&name).ok();
+    writeln!(out)?;
+    writeln!(
+        out,
+        "pub(super) const PARSE_RESULT_MESSAGE: u16 = {};",
+        number("ParseResult").expect("ParseResult message")
+    )?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "pub(super) fn nested_message(message: u16, field: u32) -> Option<u16> {{"
+    )?;
+    writeln!(out, "    match (message, field) {{")?;
+    for (index, name) in messages.iter().enumerate() {
+        for field in &proto.messages[*name] {
+            if let Some(nested) = number(&field.ty) {
+                writeln!(
+                    out,
+                    "        ({index}, {}) => Some({nested}),",
+                    field.number
+                )?;
+            }
+        }
+    }
+    writeln!(out, "        _ => None,")?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// Generates the body of a decoder which reads each known field of the message into a local
+/// variable before constructing the result. When `optional` is set every field is wrapped within
+/// an `Option`, as is the case for value nodes.
+fn make_decoder_body(
+    out: &mut BufWriter<File>,
+    context: &ProtoContext,
+    message: Option<&Vec<ProtoField>>,
+    fields: &[(String, &str, &str)],
+    constructor: &str,
+    optional: bool,
+    indent: &str,
+) -> std::io::Result<()> {
+    let mut arms = Vec::new();
+    let mut results = Vec::new();
+    writeln!(out, "{indent}    let mut reader = Reader::new(data);")?;
+    for (variable, c_name, c_type) in fields {
+        let rust_type = context.type_resolver.resolve(c_type);
+        let boxed = rust_type
+            .strip_prefix("Box<")
+            .and_then(|inner| inner.strip_suffix('>'));
+
+        // Required enums and structs are held as an `Option` until the message is complete
+        let (local_type, result) = match boxed {
+            _ if optional && rust_type.starts_with("Option<") => (rust_type.clone(), None),
+            _ if optional => (format!("Option<{}>", rust_type), None),
+            Some(inner) if context.enums.contains(inner) => (
+                format!("Option<{}>", rust_type),
+                Some(format!("required({variable}, \"{c_name}\")?")),
+            ),
+            Some(inner) if context.structs.contains(inner) => (
+                format!("Option<{}>", rust_type),
+                Some(format!(
+                    "match {variable} {{ Some(value) => value, None => Box::new({inner}::decode(&[], depth)?) }}"
+                )),
+            ),
+            _ => (rust_type.clone(), None),
+        };
+        let initial = if local_type.starts_with("Option<") {
+            "None"
+        } else if local_type.starts_with("Vec<") {
+            "Vec::new()"
+        } else {
+            "Default::default()"
+        };
+        writeln!(
+            out,
+            "{indent}    let mut {variable}: {local_type} = {initial};"
+        )?;
+        results.push(match result {
+            Some(result) => format!("{variable}: {result}"),
+            None => variable.clone(),
+        });
+
+        let Some(field) = message
+            .into_iter()
+            .flatten()
+            .find(|field| field.matches(c_name))
+        else {
+            continue;
+        };
+        for (wire_type, decoder) in field_decoders(context, &rust_type, field, variable) {
+            let statement = match decoder {
+                FieldDecoder::Assign(value) if local_type != rust_type => {
+                    format!("{variable} = Some({value})")
+                }
+                FieldDecoder::Assign(value) => format!("{variable} = {value}"),
+                FieldDecoder::Statement(statement) => statement,
+            };
+            arms.push(format!(
+                "({}, WireType::{}) => {},",
+                field.number, wire_type, statement
+            ));
+        }
+    }
+    writeln!(out, "{indent}    while !reader.is_empty() {{")?;
+    writeln!(out, "{indent}        match reader.read_key()? {{")?;
+    for arm in arms {
+        writeln!(out, "{indent}            {arm}")?;
+    }
+    writeln!(
+        out,
+        "{indent}            (_, wire_type) => reader.skip(wire_type)?,"
+    )?;
+    writeln!(out, "{indent}        }}")?;
+    writeln!(out, "{indent}    }}")?;
+    writeln!(out, "{indent}    Ok({constructor} {{")?;
+    for result in results {
+        writeln!(out, "{indent}        {result},")?;
+    }
+    writeln!(out, "{indent}    }})")?;
+    Ok(())
+}
+
+/// Determines how the given protobuf field is decoded into the given Rust type, returning each
+/// wire type that is accepted. Any combination that isn't understood is skipped.
+fn field_decoders(
+    context: &ProtoContext,
+    rust_type: &str,
+    field: &ProtoField,
+    variable: &str,
+) -> Vec<(&'static str, FieldDecoder)> {
+    use FieldDecoder::*;
+
+    let ty = field.ty.as_str();
+    let integer = matches!(ty, "int32" | "int64" | "uint32" | "uint64");
+    let scalar = !rust_type.contains('<') && rust_type != "bool" && rust_type != "char";
+    let boxed = rust_type
+        .strip_prefix("Box<")
+        .and_then(|inner| inner.strip_suffix('>'));
+    match (field.repeated, ty) {
+        (true, "Node") if rust_type == "Option<Vec<Node>>" => vec![(
+            "LengthDelimited",
+            Statement(format!(
+                "decode_nodes({variable}.get_or_insert_with(Vec::new), reader.read_bytes()?, depth)?"
+            )),
+        )],
+        (true, "Node") if rust_type == "Vec<Node>" => vec![(
+            "LengthDelimited",
+            Statement(format!(
+                "decode_nodes(&mut {variable}, reader.read_bytes()?, depth)?"
+            )),
+        )],
+        (true, _) if integer && rust_type == "Option<Vec<u32>>" => vec![
+            (
+                "LengthDelimited",
+                Statement(format!(
+                    "decode_packed({variable}.get_or_insert_with(Vec::new), reader.read_bytes()?)?"
+                )),
+            ),
+            (
+                "Varint",
+                Statement(format!(
+                    "{variable}.get_or_insert_with(Vec::new).push(reader.read_varint()? as u32)"
+                )),
+            ),
+        ],
+        (true, _) => Vec::new(),
+        (false, "Node") if rust_type == "Option<Box<Node>>" => vec![(
+            "LengthDelimited",
+            Statement(format!(
+                "{variable} = decode_node(reader.read_bytes()?, depth)?.map(Box::new)"
+            )),
+        )],
+        (false, "String") if rust_type == "Option<String>" => vec![(
+            "LengthDelimited",
+            Assign("Some(decode_nested_string(reader.read_bytes()?)?)".into()),
+        )],
+        (false, "string") if rust_type == "Option<String>" => vec![(
+            "LengthDelimited",
+            Assign("Some(decode_str(reader.read_bytes()?)?)".into()),
+        )],
+        (false, "string") if rust_type == "char" => vec![(
+            "LengthDelimited",
+            Assign("decode_char(reader.read_bytes()?)?".into()),
+        )],
+        (false, "bool") if rust_type == "bool" => {
+            vec![("Varint", Assign("reader.read_varint()? != 0".into()))]
+        }
+        (false, "double") if scalar => vec![(
+            "Fixed64",
+            Assign(format!("reader.read_double()? as {rust_type}")),
+        )],
+        (false, _) if integer && scalar => vec![(
+            "Varint",
+            Assign(format!("reader.read_varint()? as {rust_type}")),
+        )],
+        (false, _) if integer && boxed.is_some() => vec![(
+            "Varint",
+            Assign(format!(
+                "Box::new(reader.read_varint()? as {})",
+                boxed.unwrap()
+            )),
+        )],
+        (false, _) if context.proto.enums.contains_key(ty) && boxed == Some(ty) => vec![(
+            "Varint",
+            Assign("Box::new(decode_enum(reader.read_int32()?)?)".into()),
+        )],
+        (false, _) if context.structs.contains(ty) && rust_type == format!("Box<{ty}>") => {
+            vec![(
+                "LengthDelimited",
+                Assign(format!(
+                    "Box::new({ty}::decode(reader.read_bytes()?, depth)?)"
+                )),
+            )]
+        }
+        (false, _) if context.structs.contains(ty) && rust_type == format!("Option<Box<{ty}>>") => {
+            vec![(
+                "LengthDelimited",
+                Assign(format!(
+                    "Some(Box::new({ty}::decode(reader.read_bytes()?, depth)?))"
+                )),
+            )]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// The fields of a struct as generated within the AST, returning the variable name along with the
+/// original C name and type of each.
+fn struct_fields(def: &Struct) -> Vec<(String, &str, &str)> {
+    def.fields
+        .iter()
+        .filter_map(|field| match (&field.name, &field.c_type) {
+            (Some(name), Some(c_type)) if name != "type" && name != "xpr" => {
+                let variable_name = if is_reserved(name) {
+                    format!("{}_", name)
+                } else {
+                    name.to_snake_case()
+                };
+                Some((variable_name, name.as_str(), c_type.as_str()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// The fields of a value node, which keep their original C names.
+fn value_fields(def: &Struct) -> Vec<(String, &str, &str)> {
+    def.fields
+        .iter()
+        .filter_map(|field| match (&field.name, &field.c_type) {
+            (Some(name), Some(c_type)) => Some((name.clone(), name.as_str(), c_type.as_str())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Generates encoders which write the AST in the protobuf format understood by `libpg_query`.
+/// These mirror the decoders, so any field that can be decoded is also encoded.
+fn make_encoders(
+    out: &mut BufWriter<File>,
+    context: &ProtoContext,
+    struct_defs: &HashMap<String, HashMap<String, Struct>>,
+    enum_defs: &HashMap<String, HashMap<String, Enum>>,
+) -> std::io::Result<()> {
+    let proto = context.proto;
+
+    // Enum values are matched by name, the same as when decoding
+    for section in &ENUM_SECTIONS {
+        let map = &enum_defs[*section];
+        let mut map = map.iter().collect::<Vec<_>>();
+        map.sort_by_key(|x| x.0);
+
+        for (name, def) in map {
+            let Some(values) = proto.enums.get(name) else {
+                continue;
+            };
+            writeln!(out, "impl ToProto for {name} {{")?;
+            writeln!(out, "    fn to_proto(self) -> Option<i32> {{")?;
+            writeln!(out, "        match self {{")?;
+            for variant in def.values.iter().filter_map(|value| value.name.as_ref()) {
+                match values.iter().find(|(value, _)| value == variant) {
+                    Some((_, number)) => {
+                        writeln!(out, "            {name}::{variant} => Some({number}),")?
+                    }
+                    None => writeln!(out, "            {name}::{variant} => None,")?,
+                }
+            }
+            writeln!(out, "        }}")?;
+            writeln!(out, "    }}")?;
+            writeln!(out, "}}")?;
+            writeln!(out)?;
+        }
+    }
+
+    // Structs, keeping track of how each node variant is written
+    let mut node_variants = HashMap::new();
+    for section in &NODE_SECTIONS {
+        let map = &struct_defs[*section];
+        let mut map = map.iter().collect::<Vec<_>>();
+        map.sort_by_key(|x| x.0);
+
+        for (name, def) in map {
+            if IGNORE_NODES.iter().any(|x| name.eq(x)) {
+                continue;
+            }
+            if def.fields.is_empty() {
+                node_variants.insert(
+                    name.as_str(),
+                    (format!("Node::{name}"), format!("Node::{name}"), None),
+                );
+                continue;
+            }
+            node_variants.insert(
+                name.as_str(),
+                (
+                    format!("Node::{name}(value)"),
+                    format!("Node::{name}(_)"),
+                    Some("value.encode(writer)".to_string()),
+                ),
+            );
+
+            let fields = struct_fields(def);
+            let message = proto.messages.get(name.as_str());
+            writeln!(out, "impl Encode for {name} {{")?;
+            writeln!(out, "    fn encode(&self, writer: &mut Writer) {{")?;
+            let variables = fields
+                .iter()
+                .map(|(variable, _, _)| variable.as_str())
+                .collect::<Vec<_>>();
+            writeln!(
+                out,
+                "        let Self {{ {} }} = self;",
+                variables.join(", ")
+            )?;
+            for (variable, c_name, c_type) in &fields {
+                let rust_type = context.type_resolver.resolve(c_type);
+                let encoder = message
+                    .into_iter()
+                    .flatten()
+                    .find(|field| field.matches(c_name))
+                    .and_then(|field| field_encoder(context, &rust_type, field, variable));
+                if let Some(encoder) = encoder {
+                    writeln!(out, "        {encoder}")?;
+                }
+            }
+            writeln!(out, "    }}")?;
+            writeln!(out, "}}")?;
+            writeln!(out)?;
+        }
+    }
+
+    // Value nodes are variants with inline fields, which are always optional
+    let values = &struct_defs["nodes/value"];
+    let mut values = values.iter().collect::<Vec<_>>();
+    values.sort_by_key(|x| x.0);
+    for (name, def) in values {
+        // A_Const is handled explicitly, the same as when decoding
+        if name == "A_Const" {
+            node_variants.insert(
+                name.as_str(),
+                (
+                    "Node::A_Const { val, location }".into(),
+                    "Node::A_Const { .. }".into(),
+                    Some("encode_const(val, *location, writer)".into()),
+                ),
+            );
+            continue;
+        }
+
+        let fields = value_fields(def);
+        let message = proto.messages.get(name.as_str());
+        let mut encoders = Vec::new();
+        for (variable, c_name, c_type) in &fields {
+            let rust_type = context.type_resolver.resolve(c_type);
+            let Some(field) = message
+                .into_iter()
+                .flatten()
+                .find(|field| field.matches(c_name))
+            else {
+                continue;
+            };
+            if rust_type.starts_with("Option<") {
+                encoders.extend(field_encoder(context, &rust_type, field, variable));
+            } else if let Some(encoder) = field_encoder(context, &rust_type, field, "value") {
+                encoders.push(format!("if let Some(value) = {variable} {{ {encoder} }}"));
+            }
+        }
+        let variables = fields
+            .iter()
+            .map(|(variable, _, _)| variable.as_str())
+            .collect::<Vec<_>>();
+        node_variants.insert(
+            name.as_str(),
+            (
+                format!("Node::{name} {{ {} }}", variables.join(", ")),
+                format!("Node::{name} {{ .. }}"),
+                (!encoders.is_empty()).then(|| encoders.join(" ")),
+            ),
+        );
+    }
+
+    // Finally the node itself, which is a oneof containing every node type. The match is kept
+    // exhaustive so that any variant missing from pg_query.proto is reported rather than dropped.
+    let oneof = proto.messages.get("Node").into_iter().flatten();
+    let numbers = oneof
+        .map(|field| (field.ty.as_str(), field.number))
+        .collect::<HashMap<_, _>>();
+    let mut node_variants = node_variants.into_iter().collect::<Vec<_>>();
+    node_variants.sort_by_key(|x| x.0);
+    writeln!(
+        out,
+        "pub(super) fn encode_node(node: &Node, writer: &mut Writer) {{"
+    )?;
+    writeln!(out, "    match node {{")?;
+    for (name, (pattern, wildcard, encoder)) in node_variants {
+        match (numbers.get(name), encoder) {
+            (Some(number), Some(encoder)) => writeln!(
+                out,
+                "        {pattern} => writer.write_message({number}, |writer| {{ {encoder} }}),"
+            )?,
+            (Some(number), None) => writeln!(
+                out,
+                "        {wildcard} => writer.write_bytes({number}, &[]),"
+            )?,
+            (None, _) => writeln!(
+                out,
+                "        {wildcard} => writer.fail(\"{name} has no protobuf representation\".into()),"
+            )?,
+        }
+    }
+    // Unknown nodes are kept as JSON, so can't be encoded
+    writeln!(
+        out,
+        "        Node::Unknown {{ name, .. }} => writer.fail(format!(\"unknown node `{{name}}` can't be deparsed\")),"
+    )?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+/// Determines how a value of the given Rust type is encoded as the given protobuf field, where
+/// `value` is a variable holding a reference to it. Any combination that isn't understood is
+/// skipped, the same as when decoding.
+fn field_encoder(
+    context: &ProtoContext,
+    rust_type: &str,
+    field: &ProtoField,
+    value: &str,
+) -> Option<String> {
+    let ty = field.ty.as_str();
+    let number = field.number;
+    let integer = matches!(ty, "int32" | "int64" | "uint32" | "uint64");
+    let scalar = !rust_type.contains('<') && rust_type != "bool" && rust_type != "char";
+    let boxed = rust_type
+        .strip_prefix("Box<")
+        .and_then(|inner| inner.strip_suffix('>'));
+    let encoder = match (field.repeated, ty) {
+        (true, "Node") if rust_type == "Option<Vec<Node>>" => {
+            format!("if let Some(nodes) = {value} {{ write_nodes(writer, {number}, nodes) }}")
+        }
+        (true, "Node") if rust_type == "Vec<Node>" => {
+            format!("write_nodes(writer, {number}, {value})")
+        }
+        (true, _) if integer && rust_type == "Option<Vec<u32>>" => {
+            format!("if let Some(values) = {value} {{ writer.write_packed({number}, values) }}")
+        }
+        (true, _) => return None,
+        (false, "Node") if rust_type == "Option<Box<Node>>" => format!(
+            "if let Some(node) = {value} {{ writer.write_message({number}, |writer| encode_node(node, writer)) }}"
+        ),
+        (false, "String") if rust_type == "Option<String>" => format!(
+            "if let Some(value) = {value} {{ writer.write_message({number}, |writer| writer.write_string(1, value)) }}"
+        ),
+        (false, "string") if rust_type == "Option<String>" => {
+            format!("if let Some(value) = {value} {{ writer.write_string({number}, value) }}")
+        }
+        (false, "string") if rust_type == "char" => {
+            format!("writer.write_char({number}, *{value});")
+        }
+        (false, "bool") if rust_type == "bool" => format!("writer.write_bool({number}, *{value});"),
+        (false, "double") if scalar => {
+            format!("writer.write_double({number}, *{value} as f64);")
+        }
+        (false, _) if integer && scalar => format!("writer.write_int({number}, *{value} as i64);"),
+        (false, _) if integer && boxed.is_some() => {
+            format!("writer.write_int({number}, **{value} as i64);")
+        }
+        (false, _) if context.proto.enums.contains_key(ty) && boxed == Some(ty) => {
+            format!("write_enum(writer, {number}, **{value});")
+        }
+        (false, _) if context.structs.contains(ty) && rust_type == format!("Box<{ty}>") => {
+            format!("writer.write_message({number}, |writer| {value}.encode(writer));")
+        }
+        (false, _) if context.structs.contains(ty) && rust_type == format!("Option<Box<{ty}>>") => {
+            format!(
+                "if let Some(value) = {value} {{ writer.write_message({number}, |writer| value.encode(writer)) }}"
+            )
+        }
+        _ => return None,
+    };
+    Some(encoder)
+}
+
+fn is_reserved(variable: &str) -> bool {
+    matches!(
+        variable,
+        "abstract"
+            | "become"
+            | "box"
+            | "do"
+            | "final"
+            | "macro"
+            | "override"
+            | "priv"
+            | "try"
+            | "typeof"
+            | "unsized"
+            | "virtual"
+            | "yield"
+    )
+}
+
+struct TypeResolver {
+    aliases: HashMap<String, bool>, // bool = primitive
+    floats: HashSet<String>,
+    primitive: HashMap<&'static str, &'static str>,
+    nodes: HashSet<String>,
+    types: HashSet<String>,
+    boxed: HashSet<String>,
+}
+
+impl TypeResolver {
+    pub fn new() -> Self {
+        let mut primitive = HashMap::new();
+        primitive.insert("uint32", "u32");
+        primitive.insert("uint64", "u64");
+        primitive.insert("bits32", "bits32"); // Alias
+        primitive.insert("bool", "bool");
+        primitive.insert("int", "i32");
+        primitive.insert("long", "i64");
+        primitive.insert("int32", "i32");
+        primitive.insert("char*", "Option<String>"); // Make all strings optional
+        primitive.insert("int16", "i16");
+        primitive.insert("char", "char");
+        primitive.insert("double", "f64");
+        primitive.insert("signed int", "i32");
+        primitive.insert("unsigned int", "u32");
+        primitive.insert("uintptr_t", "usize");
+
+        // Similar to primitives
+        primitive.insert("List*", "Option<Vec<Node>>");
+        primitive.insert("[]Node", "Vec<Node>");
+        primitive.insert("Node*", "Option<Box<Node>>");
+        primitive.insert("Expr*", "Option<Box<Node>>");
+        primitive.insert("String*", "Option<String>");
+        primitive.insert("RelFileNumber", "RelFileNumber");
+
+        // Bitmapset is defined in bitmapset.h and is roughly equivalent to a vector of u32's.
+        primitive.insert("Bitmapset*", "Option<Vec<u32>>");
+
+        TypeResolver {
+            primitive,
+
+            aliases: HashMap::new(),
+            floats: HashSet::from(["double".to_string()]),
+            nodes: HashSet::new(),
+            types: HashSet::new(),
+            boxed: HashSet::new(),
+        }
+    }
+
+    pub fn add_alias(&mut self, ty: &str, target: &str) {
+        self.aliases
+            .insert(ty.to_string(), self.primitive.contains_key(target));
+        if self.floats.contains(target) {
+            self.floats.insert(ty.to_string());
+        }
+    }
+
+    /// Whether the type is a floating point number, which can't derive `Eq` or `Hash`.
+    pub fn is_float(&self, ty: &str) -> bool {
+        self.floats.contains(ty)
+    }
+
+    pub fn add_node(&mut self, ty: &str) {
+        self.nodes.insert(ty.to_string());
+    }
+
+    pub fn add_type(&mut self, ty: &str) {
+        self.types.insert(ty.to_string());
+    }
+
+    /// Records the nodes which are too large to be stored inline within `Node`, since every node
+    /// (including the many small ones, such as strings) is as large as its largest variant.
+    pub fn box_large_nodes(&mut self, struct_defs: &HashMap<String, HashMap<String, Struct>>) {
+        for section in &NODE_SECTIONS {
+            for (name, def) in &struct_defs[*section] {
+                let size = struct_fields(def)
+                    .into_iter()
+                    .map(|(_, _, c_type)| TypeResolver::estimated_size(&self.resolve(c_type)))
+                    .sum::<usize>();
+                if size.next_multiple_of(8) > MAX_INLINE_NODE_SIZE {
+                    self.boxed.insert(name.to_string());
+                }
+            }
+        }
+
+        // Value nodes are struct variants, so only the value of an A_Const can be boxed
+        for (name, def) in &struct_defs["nodes/value"] {
+            let size = if name == "A_Const" {
+                TypeResolver::estimated_size("ConstValue") + TypeResolver::estimated_size("i32")
+            } else {
+                value_fields(def)
+                    .into_iter()
+                    .map(|(_, _, c_type)| TypeResolver::estimated_size(&self.resolve(c_type)))
+                    .sum::<usize>()
+            };
+            if size.next_multiple_of(8) > MAX_INLINE_NODE_SIZE {
+                assert_eq!(
+                    name, "A_Const",
+                    "Value node {name} is too large to be inline"
+                );
+                self.boxed.insert(name.to_string());
+            }
+        }
+    }
+
+    /// Whether the node struct is boxed within `Node`, e.g. `Node::SelectStmt(Box<SelectStmt>)`.
+    /// For `A_Const` this is its value, e.g. `Node::A_Const { val: Box<ConstValue>, .. }`.
+    pub fn is_boxed(&self, ty: &str) -> bool {
+        self.boxed.contains(ty)
+    }
+
+    /// An upper bound on the size of a resolved type. Aliases are assumed to be 8 bytes.
+    fn estimated_size(rust_type: &str) -> usize {
+        match rust_type {
+            "bool" => 1,
+            "i16" => 2,
+            "char" | "i32" | "u32" => 4,
+            // Several variants hold a string, so the discriminant can't be stored within one
+            ty if ty.starts_with("ConstValue") => 32,
+            ty if ty.starts_with("Box<") || ty.starts_with("Option<Box<") => 8,
+            ty if ty.contains("String") || ty.contains("Vec<") => 24,
+            _ => 8,
+        }
+    }
+
+    pub fn contains(&self, ty: &str) -> bool {
+        self.aliases.contains_key(ty)
+            || self.primitive.contains_key(ty)
+            || self.nodes.contains(ty)
+            || self.types.contains(ty)
+    }
+
+    pub fn is_primitive(&self, ty: &str) -> bool {
+        self.primitive.contains_key(ty) || self.aliases.get(ty).copied().unwrap_or_default()
+    }
+
+    pub fn is_optional(&self, ty: &str) -> bool {
+        self.is_primitive(ty) || ty.ends_with('*')
+    }
+
+    pub fn custom_deserializer(ty: &str) -> Option<(&str, bool)> {
+        match ty {
+            "[]Node" => Some(("crate::serde::deserialize_node_array", false)),
+            "List*" => Some(("crate::serde::deserialize_node_array_opt", true)),
+            "String*" => Some(("crate::serde::deserialize_nested_string_opt", true)),
+            _ => None,
+        }
+    }
+
+    pub fn custom_serializer(ty: &str) -> Option<&str> {
+        match ty {
+            "List*" => Some("crate::serde::serialize_node_array_opt"),
+            "String*" => Some("crate::serde::serialize_nested_string_opt"),
             _ => None,
         }
     }
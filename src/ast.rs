@@ -6,6 +6,16 @@
 
 use serde::Deserializer;
 
+pub mod borrowed;
+pub mod builder;
+pub mod codes;
+pub mod flags;
+pub mod fold;
+pub mod raw;
+pub mod structural;
+pub mod visit;
+pub mod visit_mut;
+
 // Type aliases
 pub type bits32 = u32;
 pub type RelFileNumber = Oid;
@@ -13,10 +23,10 @@ pub type RelFileNumber = Oid;
 // Generated types
 include!(concat!(env!("OUT_DIR"), "/ast.rs"));
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct Value(pub Node);
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ConstValue {
     Bool(bool),
     Integer(i64),
@@ -47,6 +57,74 @@ impl Value {
     }
 }
 
+/// A struct which is contained within a variant of `Node`, allowing a node to be downcast to it.
+pub trait NodeVariant: Sized {
+    fn from_node(node: &Node) -> Option<&Self>;
+    fn from_node_mut(node: &mut Node) -> Option<&mut Self>;
+    fn from_node_owned(node: Node) -> Option<Self>;
+}
+
+/// A node, or reference to a node, which can be downcast to the variant `T`.
+pub trait Downcast<T> {
+    type Output;
+
+    fn downcast(self) -> Option<Self::Output>;
+}
+
+impl<'a, T: NodeVariant + 'a> Downcast<T> for &'a Node {
+    type Output = &'a T;
+
+    fn downcast(self) -> Option<&'a T> {
+        T::from_node(self)
+    }
+}
+
+impl<'a, T: NodeVariant + 'a> Downcast<T> for &'a mut Node {
+    type Output = &'a mut T;
+
+    fn downcast(self) -> Option<&'a mut T> {
+        T::from_node_mut(self)
+    }
+}
+
+impl<T: NodeVariant> Downcast<T> for Node {
+    type Output = T;
+
+    fn downcast(self) -> Option<T> {
+        T::from_node_owned(self)
+    }
+}
+
+/// Iterator adapters for nodes.
+///
+/// # Example
+///
+/// ```rust
+/// use pg_parse::ast::{NodeIteratorExt, RangeVar};
+///
+/// let result = pg_parse::parse("SELECT * FROM a, b, generate_series(1, 10)").unwrap();
+/// let stmt = result[0].as_select_stmt().unwrap();
+/// let tables = stmt
+///     .from_clause
+///     .iter()
+///     .flatten()
+///     .only::<RangeVar>()
+///     .map(|table| table.relname.as_deref().unwrap())
+///     .collect::<Vec<_>>();
+/// assert_eq!(tables, ["a", "b"]);
+/// ```
+pub trait NodeIteratorExt: Iterator + Sized {
+    /// Filters the nodes to those of the variant `T`, downcasting each of them.
+    fn only<T>(self) -> impl Iterator<Item = <Self::Item as Downcast<T>>::Output>
+    where
+        Self::Item: Downcast<T>,
+    {
+        self.filter_map(Downcast::downcast)
+    }
+}
+
+impl<I: Iterator> NodeIteratorExt for I {}
+
 pub(crate) mod constants {
     // FrameOptions is an OR of these bits.  The NONDEFAULT and BETWEEN bits are
     // used so that ruleutils.c can tell which properties were specified and
@@ -125,6 +203,13 @@ pub(crate) mod constants {
      */
     pub const REPLICA_IDENTITY_INDEX: char = 'i';
 
+    /* regular table */
+    pub const RELPERSISTENCE_PERMANENT: char = 'p';
+    /* unlogged permanent table */
+    pub const RELPERSISTENCE_UNLOGGED: char = 'u';
+    /* temporary table */
+    pub const RELPERSISTENCE_TEMP: char = 't';
+
     pub mod interval {
         pub const MONTH: i64 = 2;
         pub const YEAR: i64 = 4;
@@ -0,0 +1,57 @@
+//! A variant of the abstract syntax tree which borrows identifiers and literals from the JSON
+//! produced by `libpg_query`, rather than allocating a `String` for each of them. Strings are
+//! only copied when they contain characters which must be escaped in JSON.
+//!
+//! The structure of this tree is identical to that of [`crate::ast`], with each `Option<String>`
+//! replaced by an `Option<Cow<'a, str>>`. Enums and type aliases are shared between the two.
+//! Use [`crate::parse_borrowed`] to build this tree.
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(unused)]
+#![allow(clippy::all)]
+
+use std::borrow::Cow;
+
+use super::*;
+
+// Generated types
+include!(concat!(env!("OUT_DIR"), "/ast_borrowed.rs"));
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ConstValue<'a> {
+    Bool(bool),
+    Integer(i64),
+    Float(Cow<'a, str>),
+    String(Cow<'a, str>),
+    BitString(Cow<'a, str>),
+    Null,
+    NotNull,
+}
+
+impl ConstValue<'_> {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConstValue::Bool(_) => "ConstBool",
+            ConstValue::Integer(_) => "ConstInteger",
+            ConstValue::Float(_) => "ConstFloat",
+            ConstValue::String(_) => "ConstString",
+            ConstValue::BitString(_) => "ConstBitString",
+            ConstValue::Null => "ConstNull",
+            ConstValue::NotNull => "ConstNotNull",
+        }
+    }
+
+    /// Converts this value into the owned equivalent, copying any borrowed strings.
+    pub fn into_owned(self) -> super::ConstValue {
+        match self {
+            ConstValue::Bool(value) => super::ConstValue::Bool(value),
+            ConstValue::Integer(value) => super::ConstValue::Integer(value),
+            ConstValue::Float(value) => super::ConstValue::Float(value.into_owned()),
+            ConstValue::String(value) => super::ConstValue::String(value.into_owned()),
+            ConstValue::BitString(value) => super::ConstValue::BitString(value.into_owned()),
+            ConstValue::Null => super::ConstValue::Null,
+            ConstValue::NotNull => super::ConstValue::NotNull,
+        }
+    }
+}
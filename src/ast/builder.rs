@@ -0,0 +1,194 @@
+//! Builders for constructing an abstract syntax tree programmatically. Each struct has a
+//! `builder()` function returning a builder with every field set to its default (the same as a
+//! node created by PostgreSQL's `makeNode`), along with a method to set each field. Constructors
+//! for common nodes, such as column references and constants, are available on [`Node`].
+//!
+//! # Example
+//!
+//! ```rust
+//! use pg_parse::ast::{Node, SelectStmt};
+//!
+//! let stmt = SelectStmt::builder()
+//!     .target(Node::column_ref("c", "name"))
+//!     .from(Node::table("contacts"))
+//!     .where_(Node::op("=", Node::column("id"), Node::integer_const(1)))
+//!     .build();
+//! assert_eq!(stmt.target_list.unwrap().len(), 1);
+//! ```
+#![allow(clippy::all)]
+
+use super::*;
+
+// Generated builders
+include!(concat!(env!("OUT_DIR"), "/ast_builder.rs"));
+
+impl SelectStmtBuilder {
+    /// Adds an expression to the target list.
+    pub fn target(self, value: impl Into<Node>) -> Self {
+        self.push_target(value.into(), None)
+    }
+
+    /// Adds an expression to the target list with the given alias, i.e. `value AS name`.
+    pub fn target_as(self, value: impl Into<Node>, name: impl Into<String>) -> Self {
+        self.push_target(value.into(), Some(name.into()))
+    }
+
+    fn push_target(mut self, value: Node, name: Option<String>) -> Self {
+        let target = ResTarget {
+            name,
+            val: Some(Box::new(value)),
+            ..Default::default()
+        };
+        self.0
+            .target_list
+            .get_or_insert_with(Vec::new)
+            .push(target.into());
+        self
+    }
+
+    /// Adds a table (or other item) to the `FROM` clause, e.g. `Node::table("contacts")`.
+    pub fn from(mut self, value: impl Into<Node>) -> Self {
+        self.0
+            .from_clause
+            .get_or_insert_with(Vec::new)
+            .push(value.into());
+        self
+    }
+
+    /// Adds a condition to the `WHERE` clause. If a condition already exists then the two are
+    /// combined using `AND`, extending the existing `AND` rather than nesting another within it.
+    pub fn where_(mut self, value: impl Into<Node>) -> Self {
+        let value = match self.0.where_clause.take().map(|existing| *existing) {
+            Some(Node::BoolExpr(mut existing)) if *existing.boolop == BoolExprType::AND_EXPR => {
+                existing
+                    .args
+                    .get_or_insert_with(Vec::new)
+                    .push(value.into());
+                Node::BoolExpr(existing)
+            }
+            Some(existing) => Node::and([existing, value.into()]),
+            None => value.into(),
+        };
+        self.0.where_clause = Some(Box::new(value));
+        self
+    }
+
+    /// Adds an expression to the `ORDER BY` clause, using the default sort order.
+    pub fn order_by(mut self, value: impl Into<Node>) -> Self {
+        let sort = SortBy {
+            node: Some(Box::new(value.into())),
+            ..Default::default()
+        };
+        self.0
+            .sort_clause
+            .get_or_insert_with(Vec::new)
+            .push(sort.into());
+        self
+    }
+
+    /// Sets the `LIMIT` clause.
+    pub fn limit(mut self, value: impl Into<Node>) -> Self {
+        self.0.limit_count = Some(Box::new(value.into()));
+        self.0.limit_option = Box::new(LimitOption::LIMIT_OPTION_COUNT);
+        self
+    }
+}
+
+impl Node {
+    /// A reference to an unqualified column, e.g. `id`.
+    pub fn column(name: impl Into<String>) -> Node {
+        Node::ColumnRef(ColumnRef {
+            fields: Some(vec![Node::string(name)]),
+            ..Default::default()
+        })
+    }
+
+    /// A reference to a column qualified by the table (or alias) it belongs to, e.g. `c.id`.
+    pub fn column_ref(relation: impl Into<String>, name: impl Into<String>) -> Node {
+        Node::ColumnRef(ColumnRef {
+            fields: Some(vec![Node::string(relation), Node::string(name)]),
+            ..Default::default()
+        })
+    }
+
+    /// A reference to all columns, i.e. `*`.
+    pub fn star() -> Node {
+        Node::ColumnRef(ColumnRef {
+            fields: Some(vec![Node::A_Star(A_Star::default())]),
+            ..Default::default()
+        })
+    }
+
+    /// A reference to a table (including any descendant tables), e.g. `contacts`.
+    pub fn table(name: impl Into<String>) -> Node {
+        Node::from(RangeVar {
+            relname: Some(name.into()),
+            inh: true,
+            relpersistence: 'p',
+            ..Default::default()
+        })
+    }
+
+    /// A string, as used for identifiers within names. Use `string_const` for a string literal.
+    pub fn string(value: impl Into<String>) -> Node {
+        Node::String {
+            sval: Some(value.into()),
+        }
+    }
+
+    /// A string literal, e.g. `'Paul'`.
+    pub fn string_const(value: impl Into<String>) -> Node {
+        Node::constant(ConstValue::String(value.into()))
+    }
+
+    /// An integer literal, e.g. `42`.
+    pub fn integer_const(value: i64) -> Node {
+        Node::constant(ConstValue::Integer(value))
+    }
+
+    /// A boolean literal, i.e. `TRUE` or `FALSE`.
+    pub fn bool_const(value: bool) -> Node {
+        Node::constant(ConstValue::Bool(value))
+    }
+
+    /// The `NULL` literal.
+    pub fn null_const() -> Node {
+        Node::constant(ConstValue::Null)
+    }
+
+    fn constant(val: ConstValue) -> Node {
+        Node::A_Const {
+            val: Box::new(val),
+            location: -1,
+        }
+    }
+
+    /// A binary operator expression, e.g. `Node::op("=", lhs, rhs)`.
+    pub fn op(operator: impl Into<String>, lhs: impl Into<Node>, rhs: impl Into<Node>) -> Node {
+        Node::from(A_Expr {
+            kind: Box::new(A_Expr_Kind::AEXPR_OP),
+            name: Some(vec![Node::string(operator)]),
+            lexpr: Some(Box::new(lhs.into())),
+            rexpr: Some(Box::new(rhs.into())),
+            ..Default::default()
+        })
+    }
+
+    /// Combines the given conditions using `AND`.
+    pub fn and(args: impl IntoIterator<Item = impl Into<Node>>) -> Node {
+        Node::bool_expr(BoolExprType::AND_EXPR, args)
+    }
+
+    /// Combines the given conditions using `OR`.
+    pub fn or(args: impl IntoIterator<Item = impl Into<Node>>) -> Node {
+        Node::bool_expr(BoolExprType::OR_EXPR, args)
+    }
+
+    fn bool_expr(boolop: BoolExprType, args: impl IntoIterator<Item = impl Into<Node>>) -> Node {
+        Node::from(BoolExpr {
+            boolop: Box::new(boolop),
+            args: Some(args.into_iter().map(Into::into).collect()),
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,236 @@
+//! Typed views over the `char` fields which PostgreSQL uses as single-character codes, such as the
+//! actions of a foreign key or the persistence of a relation. The raw fields remain available on
+//! each struct, and hold `'\0'` when the code was not set; the accessors here decode them.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pg_parse::ast::codes::{ForeignKeyAction, Persistence};
+//!
+//! let result = pg_parse::parse(
+//!     "CREATE UNLOGGED TABLE t (id int REFERENCES a ON DELETE CASCADE)",
+//! )
+//! .unwrap();
+//! let stmt = result[0].as_create_stmt().unwrap();
+//! assert_eq!(stmt.relation.as_ref().unwrap().persistence(), Some(Persistence::Unlogged));
+//!
+//! let column = stmt.table_elts.as_ref().unwrap()[0].as_column_def().unwrap();
+//! let constraint = column.constraints.as_ref().unwrap()[0].as_constraint().unwrap();
+//! assert_eq!(constraint.fk_delete_action(), Some(ForeignKeyAction::Cascade));
+//! ```
+
+use super::constants;
+use super::*;
+
+macro_rules! codes {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident = $code:path,
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $(
+                $(#[$variant_meta])*
+                $variant,
+            )*
+        }
+
+        impl $name {
+            /// Decodes the character stored on the owning struct, returning `None` if it was not
+            /// set or is not a known code.
+            pub const fn from_code(code: char) -> Option<Self> {
+                match code {
+                    $($code => Some(Self::$variant),)*
+                    _ => None,
+                }
+            }
+
+            /// The character stored on the owning struct.
+            pub const fn code(self) -> char {
+                match self {
+                    $(Self::$variant => $code,)*
+                }
+            }
+        }
+
+        impl From<$name> for char {
+            fn from(value: $name) -> char {
+                value.code()
+            }
+        }
+    };
+}
+
+codes! {
+    /// The action taken by a foreign key when the referenced row is updated or deleted. See
+    /// `FKCONSTR_ACTION_*` in `nodes/parsenodes.h`.
+    pub enum ForeignKeyAction {
+        NoAction = constants::FKCONSTR_ACTION_NOACTION,
+        Restrict = constants::FKCONSTR_ACTION_RESTRICT,
+        Cascade = constants::FKCONSTR_ACTION_CASCADE,
+        SetNull = constants::FKCONSTR_ACTION_SETNULL,
+        SetDefault = constants::FKCONSTR_ACTION_SETDEFAULT,
+    }
+}
+
+codes! {
+    /// How a foreign key matches composite keys containing nulls. See `FKCONSTR_MATCH_*` in
+    /// `nodes/parsenodes.h`.
+    pub enum ForeignKeyMatchType {
+        Full = constants::FKCONSTR_MATCH_FULL,
+        /// Accepted by the grammar, but not implemented by PostgreSQL.
+        Partial = constants::FKCONSTR_MATCH_PARTIAL,
+        Simple = constants::FKCONSTR_MATCH_SIMPLE,
+    }
+}
+
+codes! {
+    /// Whether an identity or generated column is `ALWAYS` or `BY DEFAULT`. See
+    /// `ATTRIBUTE_IDENTITY_*` in `catalog/pg_attribute.h`.
+    pub enum GeneratedWhen {
+        Always = constants::ATTRIBUTE_IDENTITY_ALWAYS,
+        ByDefault = constants::ATTRIBUTE_IDENTITY_BY_DEFAULT,
+    }
+}
+
+codes! {
+    /// How the value of a generated column is computed. See `ATTRIBUTE_GENERATED_*` in
+    /// `catalog/pg_attribute.h`.
+    pub enum GeneratedKind {
+        Stored = constants::ATTRIBUTE_GENERATED_STORED,
+    }
+}
+
+codes! {
+    /// The columns logged to identify rows during logical replication. See `REPLICA_IDENTITY_*` in
+    /// `catalog/pg_class.h`.
+    pub enum ReplicaIdentity {
+        /// The primary key, if any.
+        Default = constants::REPLICA_IDENTITY_DEFAULT,
+        Nothing = constants::REPLICA_IDENTITY_NOTHING,
+        /// Every column.
+        Full = constants::REPLICA_IDENTITY_FULL,
+        /// The columns of the index given by `ReplicaIdentityStmt::name`.
+        Index = constants::REPLICA_IDENTITY_INDEX,
+    }
+}
+
+codes! {
+    /// The persistence of a relation. See `RELPERSISTENCE_*` in `catalog/pg_class.h`.
+    pub enum Persistence {
+        Permanent = constants::RELPERSISTENCE_PERMANENT,
+        Unlogged = constants::RELPERSISTENCE_UNLOGGED,
+        Temporary = constants::RELPERSISTENCE_TEMP,
+    }
+}
+
+impl PartitionStrategy {
+    /// Decodes a partition strategy stored as a character, e.g. on [`PartitionBoundSpec`].
+    pub const fn from_code(code: char) -> Option<Self> {
+        match code {
+            constants::PARTITION_STRATEGY_LIST => Some(PartitionStrategy::PARTITION_STRATEGY_LIST),
+            constants::PARTITION_STRATEGY_RANGE => {
+                Some(PartitionStrategy::PARTITION_STRATEGY_RANGE)
+            }
+            constants::PARTITION_STRATEGY_HASH => Some(PartitionStrategy::PARTITION_STRATEGY_HASH),
+            _ => None,
+        }
+    }
+
+    /// The character used for this strategy where it is stored as a `char`.
+    pub const fn code(self) -> char {
+        match self {
+            PartitionStrategy::PARTITION_STRATEGY_LIST => constants::PARTITION_STRATEGY_LIST,
+            PartitionStrategy::PARTITION_STRATEGY_RANGE => constants::PARTITION_STRATEGY_RANGE,
+            PartitionStrategy::PARTITION_STRATEGY_HASH => constants::PARTITION_STRATEGY_HASH,
+        }
+    }
+}
+
+impl Constraint {
+    /// The action taken when a referenced row is updated.
+    pub fn fk_update_action(&self) -> Option<ForeignKeyAction> {
+        ForeignKeyAction::from_code(self.fk_upd_action)
+    }
+
+    /// The action taken when a referenced row is deleted.
+    pub fn fk_delete_action(&self) -> Option<ForeignKeyAction> {
+        ForeignKeyAction::from_code(self.fk_del_action)
+    }
+
+    /// The match type of a foreign key.
+    pub fn fk_match_type(&self) -> Option<ForeignKeyMatchType> {
+        ForeignKeyMatchType::from_code(self.fk_matchtype)
+    }
+
+    /// Whether an identity or generated column constraint is `ALWAYS` or `BY DEFAULT`.
+    pub fn generated_when_kind(&self) -> Option<GeneratedWhen> {
+        GeneratedWhen::from_code(self.generated_when)
+    }
+}
+
+impl ColumnDef {
+    /// Whether an identity column is `ALWAYS` or `BY DEFAULT`. Note that the parser records this
+    /// on the column's [`Constraint`] instead.
+    pub fn identity_kind(&self) -> Option<GeneratedWhen> {
+        GeneratedWhen::from_code(self.identity)
+    }
+
+    /// How a generated column is computed. Note that the parser records this on the column's
+    /// [`Constraint`] instead.
+    pub fn generated_kind(&self) -> Option<GeneratedKind> {
+        GeneratedKind::from_code(self.generated)
+    }
+}
+
+impl PartitionBoundSpec {
+    /// The strategy of the partitioned table these bounds belong to.
+    pub fn strategy_kind(&self) -> Option<PartitionStrategy> {
+        PartitionStrategy::from_code(self.strategy)
+    }
+}
+
+impl ReplicaIdentityStmt {
+    /// The replica identity being set.
+    pub fn replica_identity(&self) -> Option<ReplicaIdentity> {
+        ReplicaIdentity::from_code(self.identity_type)
+    }
+}
+
+impl RangeVar {
+    /// The persistence of the relation, e.g. `Temporary` for `CREATE TEMP TABLE`.
+    pub fn persistence(&self) -> Option<Persistence> {
+        Persistence::from_code(self.relpersistence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_round_trips_codes() {
+        for action in [
+            ForeignKeyAction::NoAction,
+            ForeignKeyAction::Restrict,
+            ForeignKeyAction::Cascade,
+            ForeignKeyAction::SetNull,
+            ForeignKeyAction::SetDefault,
+        ] {
+            assert_eq!(ForeignKeyAction::from_code(action.code()), Some(action));
+        }
+        assert_eq!(char::from(Persistence::Temporary), 't');
+        assert_eq!(Persistence::from_code('\0'), None);
+        assert_eq!(
+            PartitionStrategy::from_code('h'),
+            Some(PartitionStrategy::PARTITION_STRATEGY_HASH)
+        );
+        assert_eq!(PartitionStrategy::PARTITION_STRATEGY_LIST.code(), 'l');
+    }
+}
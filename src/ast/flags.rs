@@ -0,0 +1,438 @@
+//! Typed views over the integer fields which PostgreSQL packs bit flags into, such as the frame
+//! options of a window definition or the events of a trigger. The raw fields remain available on
+//! each struct; the accessors here decode them.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pg_parse::ast::flags::{FrameBound, FrameMode};
+//!
+//! let result = pg_parse::parse(
+//!     "SELECT sum(x) OVER (ORDER BY y ROWS BETWEEN 2 PRECEDING AND CURRENT ROW) FROM t",
+//! )
+//! .unwrap();
+//! let stmt = result[0].as_select_stmt().unwrap();
+//! let target = stmt.target_list.as_ref().unwrap()[0].as_res_target().unwrap();
+//! let call = target.val.as_deref().unwrap().as_func_call().unwrap();
+//! let frame = call.over.as_ref().unwrap().frame().unwrap();
+//! assert_eq!(frame.mode, FrameMode::Rows);
+//! assert!(matches!(frame.start, FrameBound::Preceding(_)));
+//! assert_eq!(frame.end, FrameBound::CurrentRow);
+//! ```
+
+use super::constants;
+use super::*;
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign};
+
+macro_rules! flags {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident($ty:ty) {
+            $(
+                $(#[$flag_meta:meta])*
+                const $flag:ident = $value:expr;
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+        pub struct $name($ty);
+
+        impl $name {
+            $(
+                $(#[$flag_meta])*
+                pub const $flag: Self = Self($value);
+            )*
+
+            /// The named flags, in declaration order.
+            const NAMED: &'static [(&'static str, Self)] = &[$((stringify!($flag), Self::$flag)),*];
+
+            /// A set with no flags.
+            pub const fn empty() -> Self {
+                Self(0)
+            }
+
+            /// Wraps the raw value of the field, retaining any bits which do not correspond to a
+            /// named flag.
+            pub const fn from_bits_retain(bits: $ty) -> Self {
+                Self(bits)
+            }
+
+            /// The raw value, as stored on the owning struct.
+            pub const fn bits(&self) -> $ty {
+                self.0
+            }
+
+            /// Returns true if no bits are set.
+            pub const fn is_empty(&self) -> bool {
+                self.0 == 0
+            }
+
+            /// Returns true if every bit of `other` is set.
+            pub const fn contains(&self, other: Self) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            /// Returns true if any bit of `other` is set.
+            pub const fn intersects(&self, other: Self) -> bool {
+                self.0 & other.0 != 0
+            }
+
+            /// Sets every bit of `other`.
+            pub fn insert(&mut self, other: Self) {
+                self.0 |= other.0;
+            }
+
+            /// Clears every bit of `other`.
+            pub fn remove(&mut self, other: Self) {
+                self.0 &= !other.0;
+            }
+        }
+
+        impl BitOr for $name {
+            type Output = Self;
+
+            fn bitor(self, rhs: Self) -> Self {
+                Self(self.0 | rhs.0)
+            }
+        }
+
+        impl BitOrAssign for $name {
+            fn bitor_assign(&mut self, rhs: Self) {
+                self.0 |= rhs.0;
+            }
+        }
+
+        impl BitAnd for $name {
+            type Output = Self;
+
+            fn bitand(self, rhs: Self) -> Self {
+                Self(self.0 & rhs.0)
+            }
+        }
+
+        impl BitAndAssign for $name {
+            fn bitand_assign(&mut self, rhs: Self) {
+                self.0 &= rhs.0;
+            }
+        }
+
+        impl From<$name> for $ty {
+            fn from(value: $name) -> $ty {
+                value.0
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}(", stringify!($name))?;
+                if let Some((name, _)) = Self::NAMED.iter().find(|(_, flag)| flag == self) {
+                    f.write_str(name)?;
+                    return f.write_str(")");
+                }
+                let mut remaining = self.0;
+                let mut first = true;
+                for (name, flag) in Self::NAMED {
+                    if flag.0 != 0 && self.contains(*flag) && remaining & flag.0 != 0 {
+                        if !first {
+                            f.write_str(" | ")?;
+                        }
+                        f.write_str(name)?;
+                        remaining &= !flag.0;
+                        first = false;
+                    }
+                }
+                if remaining != 0 {
+                    if !first {
+                        f.write_str(" | ")?;
+                    }
+                    write!(f, "{:#x}", remaining)?;
+                }
+                f.write_str(")")
+            }
+        }
+    };
+}
+
+flags! {
+    /// The `frameOptions` of a [`WindowDef`]. See `FRAMEOPTION_*` in `nodes/parsenodes.h`.
+    pub struct FrameOptions(i32) {
+        /// Set when a frame clause was specified.
+        const NONDEFAULT = constants::FRAMEOPTION_NONDEFAULT;
+        const RANGE = constants::FRAMEOPTION_RANGE;
+        const ROWS = constants::FRAMEOPTION_ROWS;
+        const GROUPS = constants::FRAMEOPTION_GROUPS;
+        /// Set when the frame was given as `BETWEEN start AND end`.
+        const BETWEEN = constants::FRAMEOPTION_BETWEEN;
+        const START_UNBOUNDED_PRECEDING = constants::FRAMEOPTION_START_UNBOUNDED_PRECEDING;
+        /// Disallowed by PostgreSQL.
+        const END_UNBOUNDED_PRECEDING = constants::FRAMEOPTION_END_UNBOUNDED_PRECEDING;
+        /// Disallowed by PostgreSQL.
+        const START_UNBOUNDED_FOLLOWING = constants::FRAMEOPTION_START_UNBOUNDED_FOLLOWING;
+        const END_UNBOUNDED_FOLLOWING = constants::FRAMEOPTION_END_UNBOUNDED_FOLLOWING;
+        const START_CURRENT_ROW = constants::FRAMEOPTION_START_CURRENT_ROW;
+        const END_CURRENT_ROW = constants::FRAMEOPTION_END_CURRENT_ROW;
+        const START_OFFSET_PRECEDING = constants::FRAMEOPTION_START_OFFSET_PRECEDING;
+        const END_OFFSET_PRECEDING = constants::FRAMEOPTION_END_OFFSET_PRECEDING;
+        const START_OFFSET_FOLLOWING = constants::FRAMEOPTION_START_OFFSET_FOLLOWING;
+        const END_OFFSET_FOLLOWING = constants::FRAMEOPTION_END_OFFSET_FOLLOWING;
+        const EXCLUDE_CURRENT_ROW = constants::FRAMEOPTION_EXCLUDE_CURRENT_ROW;
+        const EXCLUDE_GROUP = constants::FRAMEOPTION_EXCLUDE_GROUP;
+        const EXCLUDE_TIES = constants::FRAMEOPTION_EXCLUDE_TIES;
+    }
+}
+
+flags! {
+    /// The `events` of a [`CreateTrigStmt`]. See `TRIGGER_TYPE_*` in `catalog/pg_trigger.h`.
+    pub struct TriggerEvents(i16) {
+        const INSERT = constants::trigger::TRIGGER_TYPE_INSERT;
+        const DELETE = constants::trigger::TRIGGER_TYPE_DELETE;
+        const UPDATE = constants::trigger::TRIGGER_TYPE_UPDATE;
+        const TRUNCATE = constants::trigger::TRIGGER_TYPE_TRUNCATE;
+    }
+}
+
+flags! {
+    /// The fields of an `interval` type, as stored in the first typmod of its [`TypeName`]. See
+    /// `INTERVAL_MASK` in `utils/timestamp.h`.
+    pub struct IntervalFields(i64) {
+        const YEAR = constants::interval::YEAR;
+        const MONTH = constants::interval::MONTH;
+        const DAY = constants::interval::DAY;
+        const HOUR = constants::interval::HOUR;
+        const MINUTE = constants::interval::MINUTE;
+        const SECOND = constants::interval::SECOND;
+        /// No fields were specified, i.e. a plain `interval`.
+        const FULL_RANGE = constants::interval::FULL_RANGE;
+    }
+}
+
+/// Whether a frame is measured in rows, peer groups or by the value of the ordering column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FrameMode {
+    Range,
+    Rows,
+    Groups,
+}
+
+/// The start or end of a window frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FrameBound<'a> {
+    UnboundedPreceding,
+    /// `offset PRECEDING`
+    Preceding(&'a Node),
+    CurrentRow,
+    /// `offset FOLLOWING`
+    Following(&'a Node),
+    UnboundedFollowing,
+}
+
+/// The rows excluded from a window frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FrameExclusion {
+    CurrentRow,
+    Group,
+    Ties,
+}
+
+/// A window frame clause, decoded from the frame options of a [`WindowDef`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Frame<'a> {
+    pub mode: FrameMode,
+    pub start: FrameBound<'a>,
+    /// The end of the frame. When only a start was given, this is `CURRENT ROW`.
+    pub end: FrameBound<'a>,
+    pub exclusion: Option<FrameExclusion>,
+}
+
+/// When a trigger fires relative to its event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TriggerTiming {
+    Before,
+    After,
+    InsteadOf,
+}
+
+impl WindowDef {
+    /// The frame options of this window.
+    pub fn frame_flags(&self) -> FrameOptions {
+        FrameOptions::from_bits_retain(self.frame_options)
+    }
+
+    /// The frame clause of this window, or `None` if it was not specified. Also returns `None` if
+    /// the frame options are not a combination that PostgreSQL produces.
+    pub fn frame(&self) -> Option<Frame<'_>> {
+        let flags = self.frame_flags();
+        if !flags.contains(FrameOptions::NONDEFAULT) {
+            return None;
+        }
+
+        let mode = if flags.contains(FrameOptions::RANGE) {
+            FrameMode::Range
+        } else if flags.contains(FrameOptions::ROWS) {
+            FrameMode::Rows
+        } else if flags.contains(FrameOptions::GROUPS) {
+            FrameMode::Groups
+        } else {
+            return None;
+        };
+
+        let start = frame_bound(
+            flags,
+            [
+                FrameOptions::START_UNBOUNDED_PRECEDING,
+                FrameOptions::START_OFFSET_PRECEDING,
+                FrameOptions::START_CURRENT_ROW,
+                FrameOptions::START_OFFSET_FOLLOWING,
+                FrameOptions::START_UNBOUNDED_FOLLOWING,
+            ],
+            self.start_offset.as_deref(),
+        )?;
+        let end = frame_bound(
+            flags,
+            [
+                FrameOptions::END_UNBOUNDED_PRECEDING,
+                FrameOptions::END_OFFSET_PRECEDING,
+                FrameOptions::END_CURRENT_ROW,
+                FrameOptions::END_OFFSET_FOLLOWING,
+                FrameOptions::END_UNBOUNDED_FOLLOWING,
+            ],
+            self.end_offset.as_deref(),
+        )?;
+
+        let exclusion = if flags.contains(FrameOptions::EXCLUDE_CURRENT_ROW) {
+            Some(FrameExclusion::CurrentRow)
+        } else if flags.contains(FrameOptions::EXCLUDE_GROUP) {
+            Some(FrameExclusion::Group)
+        } else if flags.contains(FrameOptions::EXCLUDE_TIES) {
+            Some(FrameExclusion::Ties)
+        } else {
+            None
+        };
+
+        Some(Frame {
+            mode,
+            start,
+            end,
+            exclusion,
+        })
+    }
+}
+
+// The bound flags are given in the same order as the variants of `FrameBound`.
+fn frame_bound(
+    flags: FrameOptions,
+    bounds: [FrameOptions; 5],
+    offset: Option<&Node>,
+) -> Option<FrameBound<'_>> {
+    let [
+        unbounded_preceding,
+        preceding,
+        current_row,
+        following,
+        unbounded_following,
+    ] = bounds;
+    if flags.contains(unbounded_preceding) {
+        Some(FrameBound::UnboundedPreceding)
+    } else if flags.contains(preceding) {
+        offset.map(FrameBound::Preceding)
+    } else if flags.contains(current_row) {
+        Some(FrameBound::CurrentRow)
+    } else if flags.contains(following) {
+        offset.map(FrameBound::Following)
+    } else if flags.contains(unbounded_following) {
+        Some(FrameBound::UnboundedFollowing)
+    } else {
+        None
+    }
+}
+
+impl CreateTrigStmt {
+    /// When the trigger fires, or `None` if `timing` is not a value PostgreSQL produces.
+    pub fn trigger_timing(&self) -> Option<TriggerTiming> {
+        match self.timing {
+            constants::trigger::TRIGGER_TYPE_BEFORE => Some(TriggerTiming::Before),
+            constants::trigger::TRIGGER_TYPE_AFTER => Some(TriggerTiming::After),
+            constants::trigger::TRIGGER_TYPE_INSTEAD => Some(TriggerTiming::InsteadOf),
+            _ => None,
+        }
+    }
+
+    /// The events which fire the trigger.
+    pub fn trigger_events(&self) -> TriggerEvents {
+        TriggerEvents::from_bits_retain(self.events)
+    }
+}
+
+impl TypeName {
+    /// The fields of an `interval` type, e.g. `DAY | HOUR` for `interval day to hour`. Returns
+    /// `None` if this is not an interval or no typmods were given.
+    pub fn interval_fields(&self) -> Option<IntervalFields> {
+        self.interval_typmod(0)
+            .map(IntervalFields::from_bits_retain)
+    }
+
+    /// The fractional seconds precision of an `interval` type, e.g. `3` for `interval(3)`.
+    pub fn interval_precision(&self) -> Option<i64> {
+        self.interval_typmod(1)
+            .filter(|precision| *precision != constants::interval::FULL_PRECISION)
+    }
+
+    fn interval_typmod(&self, index: usize) -> Option<i64> {
+        let is_interval = matches!(
+            self.names.as_deref().and_then(<[Node]>::last),
+            Some(Node::String { sval: Some(name) }) if name == "interval"
+        );
+        if !is_interval {
+            return None;
+        }
+        match self.typmods.as_deref()?.get(index)?.as_a_const()? {
+            ConstValue::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_formats_flags() {
+        let flags = FrameOptions::NONDEFAULT
+            | FrameOptions::ROWS
+            | FrameOptions::from_bits_retain(0x100000);
+        assert_eq!(
+            format!("{:?}", flags),
+            "FrameOptions(NONDEFAULT | ROWS | 0x100000)"
+        );
+        assert_eq!(format!("{:?}", TriggerEvents::empty()), "TriggerEvents()");
+    }
+
+    #[test]
+    fn it_formats_composite_flags_by_their_parts() {
+        let fields = IntervalFields::DAY | IntervalFields::SECOND;
+        assert_eq!(format!("{:?}", fields), "IntervalFields(DAY | SECOND)");
+        assert_eq!(
+            format!("{:?}", IntervalFields::FULL_RANGE),
+            "IntervalFields(FULL_RANGE)"
+        );
+    }
+
+    #[test]
+    fn it_combines_flags() {
+        let mut events = TriggerEvents::INSERT | TriggerEvents::UPDATE;
+        assert!(events.contains(TriggerEvents::INSERT));
+        assert!(!events.contains(TriggerEvents::INSERT | TriggerEvents::DELETE));
+        assert!(events.intersects(TriggerEvents::INSERT | TriggerEvents::DELETE));
+
+        events.remove(TriggerEvents::INSERT);
+        assert_eq!(events, TriggerEvents::UPDATE);
+        events.insert(TriggerEvents::TRUNCATE);
+        assert_eq!(
+            events.bits(),
+            constants::trigger::TRIGGER_TYPE_UPDATE | constants::trigger::TRIGGER_TYPE_TRUNCATE
+        );
+    }
+}
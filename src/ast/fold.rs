@@ -0,0 +1,37 @@
+//! Transformation of an abstract syntax tree. The [`Fold`] trait consumes a tree and builds a new
+//! one from the result of each method which, by default, folds every child node. Unlike
+//! [`VisitMut`](super::visit_mut::VisitMut), overriding `fold_node` allows a node to be replaced
+//! with a different variant.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pg_parse::ast::fold::Fold;
+//! use pg_parse::ast::{ConstValue, Node, ParamRef, ParseLoc};
+//!
+//! /// Replaces each constant with a parameter.
+//! #[derive(Default)]
+//! struct Parameterize(i32);
+//!
+//! impl Fold for Parameterize {
+//!     fn fold_a_const(&mut self, _val: ConstValue, location: ParseLoc) -> Node {
+//!         self.0 += 1;
+//!         Node::ParamRef(ParamRef { number: self.0, location })
+//!     }
+//! }
+//!
+//! let result = pg_parse::parse("SELECT * FROM contacts WHERE id = 1").unwrap();
+//! let mut folder = Parameterize::default();
+//! let result = result
+//!     .into_iter()
+//!     .map(|stmt| folder.fold_node(stmt))
+//!     .collect::<Vec<_>>();
+//! assert_eq!(result, pg_parse::parse("SELECT * FROM contacts WHERE id = $1").unwrap());
+//! ```
+#![allow(unused_variables)]
+#![allow(clippy::all)]
+
+use super::*;
+
+// Generated folders
+include!(concat!(env!("OUT_DIR"), "/ast_fold.rs"));
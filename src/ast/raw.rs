@@ -0,0 +1,163 @@
+//! A view of the abstract syntax tree containing only the nodes produced by the raw parser. The
+//! generated [`Node`] also contains the nodes which PostgreSQL creates during parse analysis,
+//! planning and execution, such as `Var`, `OpExpr` and `Query`, none of which are returned by
+//! [`parse`](crate::parse). Matching on a [`RawNode`] instead avoids handling these variants.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pg_parse::ast::raw::RawNode;
+//!
+//! let result = pg_parse::parse("SELECT * FROM contacts").unwrap();
+//! let name = match RawNode::try_from(&result[0]).unwrap() {
+//!     RawNode::SelectStmt(_) => "select",
+//!     RawNode::InsertStmt(_) => "insert",
+//!     _ => "other",
+//! };
+//! assert_eq!(name, "select");
+//! ```
+//!
+//! The children of a raw node can also be visited as raw nodes using [`RawVisit`]:
+//!
+//! ```rust
+//! use pg_parse::ast::raw::{RawNode, RawVisit, walk_raw_node};
+//!
+//! #[derive(Default)]
+//! struct Columns(usize);
+//!
+//! impl<'ast> RawVisit<'ast> for Columns {
+//!     fn visit_raw_node(&mut self, node: RawNode<'ast>) {
+//!         if let RawNode::ColumnRef(_) = node {
+//!             self.0 += 1;
+//!         }
+//!         walk_raw_node(self, node);
+//!     }
+//! }
+//!
+//! let result = pg_parse::parse("SELECT a, b FROM contacts WHERE c > 1").unwrap();
+//! let mut columns = Columns::default();
+//! columns.visit_raw_node(result[0].as_raw().unwrap());
+//! assert_eq!(columns.0, 3);
+//! ```
+#![allow(clippy::all)]
+
+use super::visit::{self, Visit};
+use super::*;
+use std::fmt;
+
+/// The error returned when converting a node which is never produced by the raw parser, containing
+/// the node itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NotRawNode<'a>(pub &'a Node);
+
+impl fmt::Display for NotRawNode<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is not produced by the raw parser", self.0.name())
+    }
+}
+
+impl std::error::Error for NotRawNode<'_> {}
+
+impl Node {
+    /// Converts this node into a [`RawNode`], returning `None` if it is only created by parse
+    /// analysis, the planner or the executor.
+    pub fn as_raw(&self) -> Option<RawNode<'_>> {
+        RawNode::try_from(self).ok()
+    }
+}
+
+/// A visitor over a raw parse tree, which sees every node as a [`RawNode`]. By default each node
+/// walks its children, so implementations only need to override `visit_raw_node` and call
+/// [`walk_raw_node`] to continue into the children.
+pub trait RawVisit<'ast> {
+    fn visit_raw_node(&mut self, node: RawNode<'ast>) {
+        walk_raw_node(self, node)
+    }
+
+    /// Visits a node which is never produced by the raw parser. By default these, along with their
+    /// children, are skipped.
+    fn visit_analyzed_node(&mut self, node: &'ast Node) {}
+}
+
+/// Passes each child reached by [`Visit`] on to a [`RawVisit`], without walking any further.
+struct RawChildren<'v, V: ?Sized>(&'v mut V);
+
+impl<'ast, V: RawVisit<'ast> + ?Sized> Visit<'ast> for RawChildren<'_, V> {
+    fn visit_node(&mut self, node: &'ast Node) {
+        match RawNode::try_from(node) {
+            Ok(raw) => self.0.visit_raw_node(raw),
+            Err(NotRawNode(node)) => self.0.visit_analyzed_node(node),
+        }
+    }
+}
+
+// Generated nodes
+include!(concat!(env!("OUT_DIR"), "/ast_raw.rs"));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_excludes_analyzed_nodes() {
+        let node = Node::from(Var::default());
+        assert_eq!(RawNode::try_from(&node), Err(NotRawNode(&node)));
+        assert_eq!(
+            NotRawNode(&node).to_string(),
+            "Var is not produced by the raw parser"
+        );
+
+        let node = Node::from(Query::default());
+        assert!(node.as_raw().is_none());
+
+        let node = Node::from(RangeVar::default());
+        assert!(matches!(node.as_raw(), Some(RawNode::RangeVar(_))));
+        let node = Node::A_Const {
+            val: Box::new(ConstValue::Integer(1)),
+            location: 0,
+        };
+        assert!(matches!(
+            node.as_raw(),
+            Some(RawNode::A_Const {
+                val: ConstValue::Integer(1),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn it_visits_children_as_raw_nodes() {
+        #[derive(Default)]
+        struct Names(Vec<&'static str>);
+
+        impl<'ast> RawVisit<'ast> for Names {
+            fn visit_raw_node(&mut self, node: RawNode<'ast>) {
+                self.0.push(match node {
+                    RawNode::A_Expr(_) => "A_Expr",
+                    RawNode::ColumnRef(_) => "ColumnRef",
+                    RawNode::String { .. } => "String",
+                    _ => "other",
+                });
+                walk_raw_node(self, node);
+            }
+
+            fn visit_analyzed_node(&mut self, node: &'ast Node) {
+                self.0.push(node.name());
+            }
+        }
+
+        let node = Node::from(A_Expr {
+            lexpr: Some(Box::new(Node::from(ColumnRef {
+                fields: Some(vec![Node::String {
+                    sval: Some("a".into()),
+                }]),
+                ..Default::default()
+            }))),
+            rexpr: Some(Box::new(Node::from(Var::default()))),
+            ..Default::default()
+        });
+        let mut names = Names::default();
+        names.visit_raw_node(node.as_raw().unwrap());
+        assert_eq!(names.0, ["A_Expr", "ColumnRef", "String", "Var"]);
+    }
+}
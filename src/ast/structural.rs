@@ -0,0 +1,271 @@
+//! Comparison and hashing of abstract syntax trees by their structure alone. Parsing the same
+//! statement with different whitespace produces trees which differ only in their locations, so
+//! these are skipped, as are the names of aliases when requested.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pg_parse::ast::structural::{StructuralHash, StructuralKey};
+//! use std::collections::HashMap;
+//!
+//! let first = pg_parse::parse("SELECT a FROM t WHERE b = 1").unwrap();
+//! let second = pg_parse::parse("select a\n  from t\n where b = 1").unwrap();
+//! assert_ne!(first, second);
+//! assert!(first[0].eq_ignoring_locations(&second[0]));
+//!
+//! // Deduplicating statements
+//! let mut counts = HashMap::new();
+//! for stmt in first.iter().chain(&second) {
+//!     *counts.entry(StructuralKey::new(stmt)).or_insert(0) += 1;
+//! }
+//! assert_eq!(counts.len(), 1);
+//! ```
+#![allow(clippy::all)]
+
+use super::*;
+use std::hash::{Hash, Hasher};
+
+/// Options which control which parts of a tree are compared and hashed. Locations are always
+/// skipped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct StructuralOptions {
+    /// Skips the names of aliases, e.g. treating `FROM t AS a` the same as `FROM t AS b`. Note that
+    /// references to the alias elsewhere in the statement are still compared.
+    pub ignore_alias_names: bool,
+}
+
+/// Equality and hashing which skips the locations of nodes. Implementations are consistent with
+/// each other, so two values which are equal given some options also hash the same.
+pub trait StructuralHash {
+    /// Compares two values, skipping the parts excluded by `options`.
+    fn structural_eq(&self, other: &Self, options: &StructuralOptions) -> bool;
+
+    /// Feeds this value into `state`, skipping the parts excluded by `options`.
+    fn structural_hash<H: Hasher>(&self, state: &mut H, options: &StructuralOptions);
+
+    /// Compares two values, skipping their locations.
+    fn eq_ignoring_locations(&self, other: &Self) -> bool {
+        self.structural_eq(other, &StructuralOptions::default())
+    }
+}
+
+/// Wraps a value so that it is compared and hashed structurally, allowing it to be used as the key
+/// of a `HashMap` or `HashSet`. Keys are only equal when created with the same options.
+#[derive(Clone, Debug)]
+pub struct StructuralKey<T> {
+    value: T,
+    options: StructuralOptions,
+}
+
+impl<T> StructuralKey<T> {
+    /// Wraps a value, comparing and hashing everything except its locations.
+    pub fn new(value: T) -> Self {
+        StructuralKey::with_options(value, StructuralOptions::default())
+    }
+
+    /// Wraps a value, comparing and hashing it according to `options`.
+    pub fn with_options(value: T, options: StructuralOptions) -> Self {
+        StructuralKey { value, options }
+    }
+
+    /// Returns a reference to the wrapped value.
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Unwraps the key, returning the wrapped value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: StructuralHash> PartialEq for StructuralKey<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.options == other.options && self.value.structural_eq(&other.value, &self.options)
+    }
+}
+
+impl<T: StructuralHash> Eq for StructuralKey<T> {}
+
+impl<T: StructuralHash> Hash for StructuralKey<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.structural_hash(state, &self.options);
+    }
+}
+
+impl<T: StructuralHash + ?Sized> StructuralHash for &T {
+    fn structural_eq(&self, other: &Self, options: &StructuralOptions) -> bool {
+        (**self).structural_eq(*other, options)
+    }
+
+    fn structural_hash<H: Hasher>(&self, state: &mut H, options: &StructuralOptions) {
+        (**self).structural_hash(state, options)
+    }
+}
+
+impl<T: StructuralHash + ?Sized> StructuralHash for Box<T> {
+    fn structural_eq(&self, other: &Self, options: &StructuralOptions) -> bool {
+        (**self).structural_eq(&**other, options)
+    }
+
+    fn structural_hash<H: Hasher>(&self, state: &mut H, options: &StructuralOptions) {
+        (**self).structural_hash(state, options)
+    }
+}
+
+impl<T: StructuralHash> StructuralHash for Option<T> {
+    fn structural_eq(&self, other: &Self, options: &StructuralOptions) -> bool {
+        match (self, other) {
+            (Some(left), Some(right)) => left.structural_eq(right, options),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    fn structural_hash<H: Hasher>(&self, state: &mut H, options: &StructuralOptions) {
+        self.is_some().hash(state);
+        if let Some(value) = self {
+            value.structural_hash(state, options);
+        }
+    }
+}
+
+impl<T: StructuralHash> StructuralHash for [T] {
+    fn structural_eq(&self, other: &Self, options: &StructuralOptions) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .zip(other)
+                .all(|(left, right)| left.structural_eq(right, options))
+    }
+
+    fn structural_hash<H: Hasher>(&self, state: &mut H, options: &StructuralOptions) {
+        self.len().hash(state);
+        for value in self {
+            value.structural_hash(state, options);
+        }
+    }
+}
+
+impl<T: StructuralHash> StructuralHash for Vec<T> {
+    fn structural_eq(&self, other: &Self, options: &StructuralOptions) -> bool {
+        self.as_slice().structural_eq(other.as_slice(), options)
+    }
+
+    fn structural_hash<H: Hasher>(&self, state: &mut H, options: &StructuralOptions) {
+        self.as_slice().structural_hash(state, options)
+    }
+}
+
+// Primitives (and constants) contain no locations, so are compared as usual
+macro_rules! structural_primitives {
+    ($($ty:ty),*) => {
+        $(
+            impl StructuralHash for $ty {
+                fn structural_eq(&self, other: &Self, _options: &StructuralOptions) -> bool {
+                    self == other
+                }
+
+                fn structural_hash<H: Hasher>(&self, state: &mut H, _options: &StructuralOptions) {
+                    Hash::hash(self, state)
+                }
+            }
+        )*
+    };
+}
+
+structural_primitives!(
+    bool, char, i8, i16, i32, i64, u8, u16, u32, u64, String, ConstValue
+);
+
+// Floating point values are compared by their bits, the same as the derived implementations
+macro_rules! structural_floats {
+    ($($ty:ty),*) => {
+        $(
+            impl StructuralHash for $ty {
+                fn structural_eq(&self, other: &Self, _options: &StructuralOptions) -> bool {
+                    self.to_bits() == other.to_bits()
+                }
+
+                fn structural_hash<H: Hasher>(&self, state: &mut H, _options: &StructuralOptions) {
+                    Hash::hash(&self.to_bits(), state)
+                }
+            }
+        )*
+    };
+}
+
+structural_floats!(f32, f64);
+
+// Generated implementations
+include!(concat!(env!("OUT_DIR"), "/ast_structural.rs"));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+
+    fn hash(node: &Node, options: &StructuralOptions) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        node.structural_hash(&mut hasher, options);
+        hasher.finish()
+    }
+
+    fn table(alias: &str, location: ParseLoc) -> Node {
+        Node::from(RangeVar {
+            relname: Some("t".into()),
+            alias: Some(Box::new(Alias {
+                aliasname: Some(alias.into()),
+                ..Alias::default()
+            })),
+            location,
+            ..RangeVar::default()
+        })
+    }
+
+    #[test]
+    fn it_skips_locations() {
+        let options = StructuralOptions::default();
+        let left = table("a", 14);
+        let right = table("a", 20);
+        assert_ne!(left, right);
+        assert!(left.eq_ignoring_locations(&right));
+        assert_eq!(hash(&left, &options), hash(&right, &options));
+
+        let left = Node::A_Const {
+            val: Box::new(ConstValue::Integer(1)),
+            location: 7,
+        };
+        let right = Node::A_Const {
+            val: Box::new(ConstValue::Integer(1)),
+            location: 9,
+        };
+        assert!(left.eq_ignoring_locations(&right));
+        assert_eq!(hash(&left, &options), hash(&right, &options));
+        assert!(!left.eq_ignoring_locations(&Node::A_Const {
+            val: Box::new(ConstValue::Integer(2)),
+            location: 7,
+        }));
+    }
+
+    #[test]
+    fn it_can_skip_alias_names() {
+        let left = table("a", 14);
+        let right = table("b", 14);
+        assert!(!left.eq_ignoring_locations(&right));
+
+        let options = StructuralOptions {
+            ignore_alias_names: true,
+        };
+        assert!(left.structural_eq(&right, &options));
+        assert_eq!(hash(&left, &options), hash(&right, &options));
+        assert_ne!(
+            StructuralKey::new(&left),
+            StructuralKey::with_options(&right, options)
+        );
+        assert_eq!(
+            StructuralKey::with_options(&left, options),
+            StructuralKey::with_options(&right, options)
+        );
+    }
+}
@@ -0,0 +1,36 @@
+//! Traversal of the abstract syntax tree. The [`Visit`] trait contains a method for each node type
+//! which, by default, recurses into every child node. Override the methods for the nodes of
+//! interest and call the corresponding `walk_*` function to continue into their children.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pg_parse::ast::RangeVar;
+//! use pg_parse::ast::visit::{Visit, walk_range_var};
+//!
+//! #[derive(Default)]
+//! struct Tables<'ast>(Vec<&'ast str>);
+//!
+//! impl<'ast> Visit<'ast> for Tables<'ast> {
+//!     fn visit_range_var(&mut self, node: &'ast RangeVar) {
+//!         if let Some(name) = &node.relname {
+//!             self.0.push(name);
+//!         }
+//!         walk_range_var(self, node);
+//!     }
+//! }
+//!
+//! let result = pg_parse::parse("SELECT * FROM a JOIN b ON a.id = b.id WHERE EXISTS (SELECT 1 FROM c)").unwrap();
+//! let mut tables = Tables::default();
+//! for stmt in &result {
+//!     tables.visit_node(stmt);
+//! }
+//! assert_eq!(tables.0, ["a", "b", "c"]);
+//! ```
+#![allow(unused_variables)]
+#![allow(clippy::all)]
+
+use super::*;
+
+// Generated visitors
+include!(concat!(env!("OUT_DIR"), "/ast_visit.rs"));
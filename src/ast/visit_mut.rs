@@ -0,0 +1,35 @@
+//! Traversal of a mutable abstract syntax tree, allowing nodes to be modified in place. The
+//! [`VisitMut`] trait contains a method for each node type which, by default, recurses into every
+//! child node. Override the methods for the nodes of interest and call the corresponding
+//! `walk_*_mut` function to continue into their children.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pg_parse::ast::visit_mut::{VisitMut, walk_range_var_mut};
+//! use pg_parse::ast::{Node, RangeVar};
+//!
+//! struct Rename;
+//!
+//! impl VisitMut for Rename {
+//!     fn visit_range_var_mut(&mut self, node: &mut RangeVar) {
+//!         if node.relname.as_deref() == Some("contacts") {
+//!             node.relname = Some("people".into());
+//!         }
+//!         walk_range_var_mut(self, node);
+//!     }
+//! }
+//!
+//! let mut result = pg_parse::parse("SELECT * FROM contacts").unwrap();
+//! for stmt in &mut result {
+//!     Rename.visit_node_mut(stmt);
+//! }
+//! assert_eq!(result, pg_parse::parse("SELECT * FROM people").unwrap());
+//! ```
+#![allow(unused_variables)]
+#![allow(clippy::all)]
+
+use super::*;
+
+// Generated visitors
+include!(concat!(env!("OUT_DIR"), "/ast_visit_mut.rs"));
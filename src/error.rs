@@ -1,12 +1,18 @@
+use crate::position::Position;
 use std::fmt::{Display, Formatter};
 
 /// Error structure representing the basic error scenarios for `pg_parse`.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Error {
-    ParseError(String),
+    ParseError(Box<ParseError>),
     InvalidAst(String),
     InvalidAstWithDebug(String, String),
     InvalidJson(String),
+    InvalidProtobuf(String),
+    /// The input contained a NUL byte at the given byte offset.
+    InteriorNul(usize),
+    /// The parse tree was nested deeper than the configured maximum depth.
+    DepthLimitExceeded(usize),
 }
 
 impl Display for Error {
@@ -18,6 +24,13 @@ impl Display for Error {
                 write!(f, "Invalid AST: {}. Debug: {}", value, debug)
             }
             Error::InvalidJson(value) => write!(f, "Invalid JSON: {}", value),
+            Error::InvalidProtobuf(value) => write!(f, "Invalid Protobuf: {}", value),
+            Error::DepthLimitExceeded(max_depth) => {
+                write!(f, "Invalid AST: exceeds the maximum depth of {}", max_depth)
+            }
+            Error::InteriorNul(position) => {
+                write!(f, "Invalid Input: NUL byte found at position {}", position)
+            }
         }
     }
 }
@@ -26,3 +39,30 @@ impl std::error::Error for Error {}
 
 /// Convenient Result alias for returning `pg_parse::Error`.
 pub type Result<T> = core::result::Result<T, Error>;
+
+/// A parse error as reported by PostgreSQL, including where in the input it occurred.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseError {
+    /// The error message, e.g. `syntax error at or near "RANDOM"`.
+    pub message: String,
+    /// The position within the input at which the error occurred, if one was reported.
+    pub position: Option<Position>,
+    /// Additional context for the error, if any. This is typically only provided by PL/pgSQL.
+    pub context: Option<String>,
+    /// The location within the PostgreSQL source code that raised the error.
+    pub source_location: Option<SourceLocation>,
+}
+
+/// A location within the PostgreSQL source code, e.g. `scanner_yyerror` in `scan.l`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SourceLocation {
+    pub function: String,
+    pub file: String,
+    pub line: u32,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
@@ -0,0 +1,90 @@
+use std::borrow::Cow;
+use std::ffi::{CStr, CString};
+
+use crate::error::{Error, Result};
+
+/// SQL input that can be passed to the parser.
+///
+/// This is implemented for the standard string types, as well as for C strings and byte slices.
+/// Callers that already hold a NUL terminated buffer can pass it as a `CStr` to avoid copying the
+/// input. Any other input containing a NUL byte, including a trailing one, results in
+/// `Error::InteriorNul` rather than being truncated. Byte slices need not be valid UTF-8.
+pub trait SqlInput {
+    /// Returns the input as a NUL terminated C string, copying it only if required.
+    fn to_c_str(&self) -> Result<Cow<'_, CStr>>;
+}
+
+impl SqlInput for CStr {
+    fn to_c_str(&self) -> Result<Cow<'_, CStr>> {
+        Ok(Cow::Borrowed(self))
+    }
+}
+
+impl SqlInput for CString {
+    fn to_c_str(&self) -> Result<Cow<'_, CStr>> {
+        Ok(Cow::Borrowed(self.as_c_str()))
+    }
+}
+
+impl SqlInput for [u8] {
+    fn to_c_str(&self) -> Result<Cow<'_, CStr>> {
+        CString::new(self)
+            .map(Cow::Owned)
+            .map_err(|e| Error::InteriorNul(e.nul_position()))
+    }
+}
+
+impl<const N: usize> SqlInput for [u8; N] {
+    fn to_c_str(&self) -> Result<Cow<'_, CStr>> {
+        self.as_slice().to_c_str()
+    }
+}
+
+impl SqlInput for Vec<u8> {
+    fn to_c_str(&self) -> Result<Cow<'_, CStr>> {
+        self.as_slice().to_c_str()
+    }
+}
+
+impl SqlInput for str {
+    fn to_c_str(&self) -> Result<Cow<'_, CStr>> {
+        self.as_bytes().to_c_str()
+    }
+}
+
+impl SqlInput for String {
+    fn to_c_str(&self) -> Result<Cow<'_, CStr>> {
+        self.as_bytes().to_c_str()
+    }
+}
+
+impl<T: SqlInput + ?Sized> SqlInput for &T {
+    fn to_c_str(&self) -> Result<Cow<'_, CStr>> {
+        (**self).to_c_str()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_only_borrows_c_strings() {
+        assert!(matches!("SELECT 1".to_c_str(), Ok(Cow::Owned(_))));
+        assert!(matches!(b"SELECT 1".to_c_str(), Ok(Cow::Owned(_))));
+        assert!(matches!(c"SELECT 1".to_c_str(), Ok(Cow::Borrowed(_))));
+    }
+
+    #[test]
+    fn it_rejects_interior_nul_bytes() {
+        assert_eq!("SELECT\0 1".to_c_str(), Err(Error::InteriorNul(6)));
+        assert_eq!(b"\0SELECT 1\0".to_c_str(), Err(Error::InteriorNul(0)));
+
+        // A trailing NUL is only accepted as the terminator of a C string, rather than truncating
+        assert_eq!(
+            String::from("SELECT 1\0").to_c_str(),
+            Err(Error::InteriorNul(8))
+        );
+        assert_eq!(b"SELECT 1\0".to_c_str(), Err(Error::InteriorNul(8)));
+    }
+}
@@ -35,10 +35,18 @@
 pub mod ast;
 mod bindings;
 mod error;
+mod input;
+mod position;
+mod protobuf;
 mod query;
 mod serde;
 #[cfg(feature = "str")]
 mod str;
+#[allow(non_camel_case_types)]
+mod token;
 
 pub use error::*;
+pub use input::*;
+pub use position::*;
 pub use query::*;
+pub use token::*;
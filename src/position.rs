@@ -0,0 +1,171 @@
+/// The position of a byte offset within the input, such as the `location` of a node. Columns are
+/// available in bytes, characters and UTF-16 code units, since editors differ in which they use
+/// (e.g. the Language Server Protocol defaults to UTF-16).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Position {
+    /// The zero based byte offset into the input.
+    pub offset: usize,
+    /// The one based line number.
+    pub line: usize,
+    /// The one based column number, counted in bytes.
+    pub byte_column: usize,
+    /// The one based column number, counted in characters.
+    pub char_column: usize,
+    /// The one based column number, counted in UTF-16 code units.
+    pub utf16_column: usize,
+}
+
+impl Position {
+    /// Resolves the line and column of the given byte offset within the input. Returns `None` if the
+    /// offset is beyond the end of the input or doesn't fall on a character boundary.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use pg_parse::Position;
+    ///
+    /// let sql = "SELECT 'é',\n  a FROM t";
+    /// let stmt = &pg_parse::parse(sql).unwrap()[0];
+    /// let target = &stmt.as_select_stmt().unwrap().target_list.as_ref().unwrap()[1];
+    /// let position = Position::from_offset(sql, target.location().unwrap()).unwrap();
+    /// assert_eq!((position.line, position.char_column), (2, 3));
+    /// ```
+    pub fn from_offset(input: &str, offset: usize) -> Option<Self> {
+        if !input.is_char_boundary(offset) {
+            return None;
+        }
+        Position::from_bytes(input.as_bytes(), offset)
+    }
+
+    /// Resolves the line and column of the given byte offset within input which may not be valid
+    /// UTF-8. Characters are counted from the original bytes as PostgreSQL counts them.
+    pub(crate) fn from_bytes(input: &[u8], offset: usize) -> Option<Self> {
+        let preceding = input.get(..offset)?;
+        let line_start = preceding
+            .iter()
+            .rposition(|b| *b == b'\n')
+            .map(|index| index + 1)
+            .unwrap_or(0);
+        let mut char_column = 1;
+        let mut utf16_column = 1;
+        let mut index = line_start;
+        while index < preceding.len() {
+            let len = char_len(preceding[index]);
+            index += len;
+            char_column += 1;
+            // Characters outside of the basic multilingual plane are a surrogate pair in UTF-16
+            utf16_column += if len == 4 { 2 } else { 1 };
+        }
+        Some(Position {
+            offset,
+            line: preceding.iter().filter(|b| **b == b'\n').count() + 1,
+            byte_column: preceding.len() - line_start + 1,
+            char_column,
+            utf16_column,
+        })
+    }
+
+    /// Resolves a PostgreSQL cursor position (a one based character index) against the input
+    /// it was reported for. Returns `None` if the cursor position was not set or is out of range.
+    pub(crate) fn from_cursor(input: &[u8], cursor: i32) -> Option<Self> {
+        if cursor <= 0 {
+            return None;
+        }
+        // The cursor may point just past the end of the input, e.g. for an unterminated statement
+        let mut offset = 0;
+        for _ in 1..cursor {
+            if offset >= input.len() {
+                return None;
+            }
+            offset += char_len(input[offset]);
+        }
+        Position::from_bytes(input, offset.min(input.len()))
+    }
+}
+
+/// The length of the character starting with the given byte, following `pg_utf_mblen`. Bytes
+/// which can't start a character are counted as a character of their own.
+fn char_len(lead: u8) -> usize {
+    match lead {
+        b if b & 0x80 == 0 => 1,
+        b if b & 0xE0 == 0xC0 => 2,
+        b if b & 0xF0 == 0xE0 => 3,
+        b if b & 0xF8 == 0xF0 => 4,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Position;
+
+    #[test]
+    fn it_can_resolve_an_offset() {
+        let input = "SELECT 1;\nSELECT * FROM contacts";
+        assert_eq!(
+            Position::from_offset(input, 19),
+            Some(Position {
+                offset: 19,
+                line: 2,
+                byte_column: 10,
+                char_column: 10,
+                utf16_column: 10,
+            })
+        );
+        assert_eq!(Position::from_offset(input, 0).unwrap().line, 1);
+        assert_eq!(Position::from_offset(input, input.len()).unwrap().line, 2);
+    }
+
+    #[test]
+    fn it_counts_columns_in_each_unit() {
+        // "é" is two bytes and one UTF-16 code unit, while "😀" is four bytes and two code units
+        let input = "SELECT 'é😀', x";
+        let position = Position::from_offset(input, 17).unwrap();
+        assert_eq!(&input[position.offset..], "x");
+        assert_eq!(position.byte_column, 18);
+        assert_eq!(position.char_column, 14);
+        assert_eq!(position.utf16_column, 15);
+    }
+
+    #[test]
+    fn it_handles_invalid_offsets() {
+        assert_eq!(Position::from_offset("SELECT", 7), None);
+        // Within the "é"
+        assert_eq!(Position::from_offset("SELECT 'é'", 9), None);
+    }
+
+    #[test]
+    fn it_can_resolve_a_cursor_position() {
+        let input = b"SELECT 1;\nSELECT * FRO contacts";
+        let position = Position::from_cursor(input, 20).unwrap();
+        assert_eq!(position.offset, 19);
+        assert_eq!((position.line, position.char_column), (2, 10));
+        assert_eq!(Position::from_bytes(input, 19), Some(position));
+    }
+
+    #[test]
+    fn it_counts_cursor_positions_in_characters() {
+        // "é" is two bytes, however PostgreSQL counts it as a single character
+        let input = "SELECT 'é' FRO";
+        let position = Position::from_cursor(input.as_bytes(), 12).unwrap();
+        assert_eq!(position.offset, 12);
+        assert_eq!(position.char_column, 12);
+        assert_eq!(&input[position.offset..], "FRO");
+
+        // Invalid bytes are also counted as a character each, rather than as a replacement character
+        let input = b"SELECT '\xFF' FRO";
+        let position = Position::from_cursor(input, 12).unwrap();
+        assert_eq!(position.offset, 11);
+        assert_eq!(position.char_column, 12);
+        assert_eq!(&input[position.offset..], b"FRO");
+        assert_eq!(Position::from_bytes(input, 11), Some(position));
+    }
+
+    #[test]
+    fn it_handles_out_of_range_cursor_positions() {
+        assert_eq!(Position::from_cursor(b"SELECT", 0), None);
+        assert_eq!(Position::from_cursor(b"SELECT", 8), None);
+        let end = Position::from_cursor(b"SELECT", 7).unwrap();
+        assert_eq!(end.offset, 6);
+    }
+}
@@ -0,0 +1,224 @@
+//! A minimal reader and writer for the protobuf wire format, sufficient for exchanging messages
+//! with `libpg_query` without requiring a full protobuf implementation.
+
+use crate::error::{Error, Result};
+
+#[cfg(feature = "protobuf")]
+pub(crate) mod decode;
+#[cfg(feature = "protobuf")]
+pub(crate) mod encode;
+
+/// The wire types defined by the protobuf encoding.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum WireType {
+    Varint,
+    Fixed64,
+    LengthDelimited,
+    Fixed32,
+}
+
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Reader { data, position: 0 }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    /// Reads the next field key, returning the field number and wire type.
+    pub(crate) fn read_key(&mut self) -> Result<(u32, WireType)> {
+        let key = self.read_varint()?;
+        let wire_type = match key & 0x7 {
+            0 => WireType::Varint,
+            1 => WireType::Fixed64,
+            2 => WireType::LengthDelimited,
+            5 => WireType::Fixed32,
+            other => {
+                return Err(Error::InvalidProtobuf(format!(
+                    "unsupported wire type {}",
+                    other
+                )));
+            }
+        };
+        Ok(((key >> 3) as u32, wire_type))
+    }
+
+    pub(crate) fn read_varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self
+                .data
+                .get(self.position)
+                .ok_or_else(|| Error::InvalidProtobuf("unexpected end of varint".into()))?;
+            self.position += 1;
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(Error::InvalidProtobuf("varint is too long".into()))
+    }
+
+    pub(crate) fn read_int32(&mut self) -> Result<i32> {
+        // Negative values are sign extended to ten bytes, so truncation is intentional
+        Ok(self.read_varint()? as i32)
+    }
+
+    #[cfg(feature = "protobuf")]
+    pub(crate) fn read_double(&mut self) -> Result<f64> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + 8)
+            .ok_or_else(|| Error::InvalidProtobuf("unexpected end of buffer".into()))?;
+        self.position += 8;
+        Ok(f64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    pub(crate) fn read_bytes(&mut self) -> Result<&'a [u8]> {
+        let length = self.read_varint()? as usize;
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| Error::InvalidProtobuf("length exceeds the buffer".into()))?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    /// Skips over the value of a field that isn't understood.
+    pub(crate) fn skip(&mut self, wire_type: WireType) -> Result<()> {
+        let length = match wire_type {
+            WireType::Varint => return self.read_varint().map(|_| ()),
+            WireType::LengthDelimited => return self.read_bytes().map(|_| ()),
+            WireType::Fixed64 => 8,
+            WireType::Fixed32 => 4,
+        };
+        if self.position + length > self.data.len() {
+            return Err(Error::InvalidProtobuf("unexpected end of buffer".into()));
+        }
+        self.position += length;
+        Ok(())
+    }
+}
+
+/// Writes messages in the protobuf wire format. As with proto3, fields holding their default value
+/// are omitted.
+#[cfg(feature = "protobuf")]
+pub(crate) struct Writer {
+    data: Vec<u8>,
+    /// Describes the first part of the tree which couldn't be encoded, if any.
+    error: Option<String>,
+}
+
+#[cfg(feature = "protobuf")]
+impl Writer {
+    pub(crate) fn new() -> Self {
+        Writer {
+            data: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Returns the encoded message, or an error if any part of it couldn't be encoded.
+    pub(crate) fn into_bytes(self) -> Result<Vec<u8>> {
+        match self.error {
+            Some(message) => Err(Error::InvalidAst(message)),
+            None => Ok(self.data),
+        }
+    }
+
+    /// Records that part of the tree couldn't be encoded. Encoding continues, however only the
+    /// first failure is reported.
+    pub(crate) fn fail(&mut self, message: String) {
+        self.error.get_or_insert(message);
+    }
+
+    fn write_key(&mut self, number: u32, wire_type: WireType) {
+        let wire_type = match wire_type {
+            WireType::Varint => 0,
+            WireType::Fixed64 => 1,
+            WireType::LengthDelimited => 2,
+            WireType::Fixed32 => 5,
+        };
+        self.write_varint(u64::from(number) << 3 | wire_type);
+    }
+
+    fn write_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.data.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.data.push(value as u8);
+    }
+
+    /// Writes an integer field. Negative values are sign extended to ten bytes, matching `int32`.
+    pub(crate) fn write_int(&mut self, number: u32, value: i64) {
+        if value != 0 {
+            self.write_key(number, WireType::Varint);
+            self.write_varint(value as u64);
+        }
+    }
+
+    pub(crate) fn write_bool(&mut self, number: u32, value: bool) {
+        if value {
+            self.write_key(number, WireType::Varint);
+            self.write_varint(1);
+        }
+    }
+
+    pub(crate) fn write_double(&mut self, number: u32, value: f64) {
+        if value != 0.0 {
+            self.write_key(number, WireType::Fixed64);
+            self.data.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    pub(crate) fn write_string(&mut self, number: u32, value: &str) {
+        if !value.is_empty() {
+            self.write_bytes(number, value.as_bytes());
+        }
+    }
+
+    /// Writes a single character, which `libpg_query` represents as a string.
+    pub(crate) fn write_char(&mut self, number: u32, value: char) {
+        if value != '\0' {
+            self.write_string(number, value.encode_utf8(&mut [0; 4]));
+        }
+    }
+
+    /// Writes a length delimited field. Unlike other fields this is always written, since an empty
+    /// message is still meaningful, e.g. to select a member of a `oneof`.
+    pub(crate) fn write_bytes(&mut self, number: u32, value: &[u8]) {
+        self.write_key(number, WireType::LengthDelimited);
+        self.write_varint(value.len() as u64);
+        self.data.extend_from_slice(value);
+    }
+
+    /// Writes a nested message, which is built using the given function.
+    pub(crate) fn write_message(&mut self, number: u32, f: impl FnOnce(&mut Writer)) {
+        let mut writer = Writer::new();
+        f(&mut writer);
+        self.write_bytes(number, &writer.data);
+        if let Some(message) = writer.error {
+            self.fail(message);
+        }
+    }
+
+    /// Writes a repeated integer field using the packed encoding.
+    pub(crate) fn write_packed(&mut self, number: u32, values: &[u32]) {
+        if !values.is_empty() {
+            self.write_message(number, |writer| {
+                for value in values {
+                    writer.write_varint(u64::from(*value));
+                }
+            });
+        }
+    }
+}
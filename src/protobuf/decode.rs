@@ -0,0 +1,297 @@
+//! Decodes the protobuf parse tree produced by `libpg_query` straight into the abstract syntax tree.
+//! The decoders for each node are generated from `pg_query.proto` by the build script, while the
+//! helpers they rely on are defined here.
+
+use super::{Reader, WireType};
+use crate::ast::*;
+use crate::error::{Error, Result};
+use std::cell::Cell;
+
+use generated::decode_node;
+
+thread_local! {
+    /// The lowest stack address reached by the decoder on this thread while probing its stack usage.
+    static LOWEST_STACK_ADDRESS: Cell<usize> = const { Cell::new(usize::MAX) };
+}
+
+/// Tracks the nesting depth while decoding so that trees which are too deep for the current stack
+/// are detected rather than overflowing it.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Depth {
+    current: usize,
+    limit: usize,
+    /// Whether to record how far the stack grows at each level, see `probe_stack`.
+    probe: bool,
+}
+
+impl Depth {
+    fn new(limit: usize) -> Self {
+        Depth {
+            current: 0,
+            limit,
+            probe: false,
+        }
+    }
+
+    /// Descends one level, returning `Error::DepthLimitExceeded` if this exceeds the limit.
+    fn nested(self) -> Result<Depth> {
+        if self.current >= self.limit {
+            return Err(Error::DepthLimitExceeded(self.limit));
+        }
+        if self.probe {
+            let marker = 0u8;
+            let address = std::hint::black_box(&marker) as *const u8 as usize;
+            LOWEST_STACK_ADDRESS.with(|lowest| lowest.set(lowest.get().min(address)));
+        }
+        Ok(Depth {
+            current: self.current + 1,
+            ..self
+        })
+    }
+}
+
+/// A message which can be decoded from the protobuf wire format.
+trait Decode: Sized {
+    fn decode(data: &[u8], depth: Depth) -> Result<Self>;
+}
+
+/// An enum which can be decoded from its protobuf value. These are offset by one from the values
+/// used by PostgreSQL, since zero represents an undefined value.
+trait ProtoEnum: Sized {
+    fn from_proto(value: i32) -> Option<Self>;
+}
+
+/// Decodes a `ParseResult` message into the list of statements, failing with
+/// `Error::DepthLimitExceeded` if the tree is nested deeper than `max_depth`.
+pub(crate) fn decode_parse_result(data: &[u8], max_depth: usize) -> Result<Vec<Node>> {
+    decode_stmts(data, Depth::new(max_depth))
+}
+
+fn decode_stmts(data: &[u8], depth: Depth) -> Result<Vec<Node>> {
+    let mut reader = Reader::new(data);
+    let mut stmts = Vec::new();
+    while !reader.is_empty() {
+        match reader.read_key()? {
+            (2, WireType::LengthDelimited) => {
+                let raw = RawStmt::decode(reader.read_bytes()?, depth)?;
+                let stmt = raw
+                    .stmt
+                    .ok_or_else(|| Error::InvalidProtobuf("statement is missing".into()))?;
+                stmts.push(*stmt);
+            }
+            (_, wire_type) => reader.skip(wire_type)?,
+        }
+    }
+    Ok(stmts)
+}
+
+/// Decodes a `ParseResult` message, returning the lowest stack address reached while doing so.
+/// Comparing this for trees of different depths gives the stack used by each level of nesting.
+pub(crate) fn probe_stack(data: &[u8]) -> usize {
+    LOWEST_STACK_ADDRESS.with(|lowest| lowest.set(usize::MAX));
+    let depth = Depth {
+        probe: true,
+        ..Depth::new(usize::MAX)
+    };
+    let _ = decode_stmts(data, depth);
+    LOWEST_STACK_ADDRESS.with(Cell::get)
+}
+
+/// Measures the nesting depth of a `ParseResult` message without decoding it, counting each nested
+/// message as a level. This is at least the depth counted by the decoder, which doesn't count the
+/// messages holding values. The data is read in a single pass, only descending into the fields
+/// which hold messages.
+pub(crate) fn parse_result_depth(data: &[u8]) -> Result<usize> {
+    let mut max = 0;
+    let mut pending = vec![(data, generated::PARSE_RESULT_MESSAGE, 0)];
+    while let Some((data, message, depth)) = pending.pop() {
+        max = max.max(depth);
+        let mut reader = Reader::new(data);
+        while !reader.is_empty() {
+            match reader.read_key()? {
+                (field, WireType::LengthDelimited) => {
+                    let data = reader.read_bytes()?;
+                    if let Some(nested) = generated::nested_message(message, field) {
+                        pending.push((data, nested, depth + 1));
+                    }
+                }
+                (_, wire_type) => reader.skip(wire_type)?,
+            }
+        }
+    }
+    Ok(max)
+}
+
+fn decode_enum<E: ProtoEnum>(value: i32) -> Result<E> {
+    E::from_proto(value).ok_or_else(|| {
+        Error::InvalidProtobuf(format!(
+            "unknown value {} for {}",
+            value,
+            std::any::type_name::<E>()
+        ))
+    })
+}
+
+fn required<T>(value: Option<T>, field: &str) -> Result<T> {
+    value.ok_or_else(|| Error::InvalidProtobuf(format!("missing field {}", field)))
+}
+
+fn decode_str(data: &[u8]) -> Result<String> {
+    std::str::from_utf8(data)
+        .map(String::from)
+        .map_err(|e| Error::InvalidProtobuf(e.to_string()))
+}
+
+/// Single characters are encoded as strings, with an absent value representing `'\0'`.
+fn decode_char(data: &[u8]) -> Result<char> {
+    let value = std::str::from_utf8(data).map_err(|e| Error::InvalidProtobuf(e.to_string()))?;
+    Ok(value.chars().next().unwrap_or_default())
+}
+
+/// Decodes a message containing a single string field, such as `String` or `Float`.
+fn decode_nested_string(data: &[u8]) -> Result<String> {
+    let mut reader = Reader::new(data);
+    let mut value = String::new();
+    while !reader.is_empty() {
+        match reader.read_key()? {
+            (1, WireType::LengthDelimited) => value = decode_str(reader.read_bytes()?)?,
+            (_, wire_type) => reader.skip(wire_type)?,
+        }
+    }
+    Ok(value)
+}
+
+/// Decodes a message containing a single varint field, such as `Integer` or `Boolean`.
+fn decode_nested_varint(data: &[u8]) -> Result<u64> {
+    let mut reader = Reader::new(data);
+    let mut value = 0;
+    while !reader.is_empty() {
+        match reader.read_key()? {
+            (1, WireType::Varint) => value = reader.read_varint()?,
+            (_, wire_type) => reader.skip(wire_type)?,
+        }
+    }
+    Ok(value)
+}
+
+fn decode_packed(values: &mut Vec<u32>, data: &[u8]) -> Result<()> {
+    let mut reader = Reader::new(data);
+    while !reader.is_empty() {
+        values.push(reader.read_varint()? as u32);
+    }
+    Ok(())
+}
+
+/// Decodes a struct as the variant of `Node` which holds it.
+fn decode_variant<T: Decode + Into<Node>>(data: &[u8], depth: Depth) -> Result<Node> {
+    T::decode(data, depth).map(Into::into)
+}
+
+/// Decodes a node within a list. Empty nodes are skipped, the same as when deserializing JSON.
+fn decode_nodes(nodes: &mut Vec<Node>, data: &[u8], depth: Depth) -> Result<()> {
+    if let Some(node) = decode_node(data, depth)? {
+        nodes.push(node);
+    }
+    Ok(())
+}
+
+/// Decodes an `A_Const` message into its value and location.
+pub(super) fn decode_const(data: &[u8]) -> Result<(ConstValue, ParseLoc)> {
+    let mut reader = Reader::new(data);
+    let mut value = None;
+    let mut isnull = false;
+    let mut location = 0;
+    while !reader.is_empty() {
+        match reader.read_key()? {
+            (1, WireType::LengthDelimited) => {
+                let ival = decode_nested_varint(reader.read_bytes()?)? as i32;
+                value = Some(ConstValue::Integer(ival.into()));
+            }
+            (2, WireType::LengthDelimited) => {
+                value = Some(ConstValue::Float(decode_nested_string(
+                    reader.read_bytes()?,
+                )?));
+            }
+            (3, WireType::LengthDelimited) => {
+                let boolval = decode_nested_varint(reader.read_bytes()?)? != 0;
+                value = Some(ConstValue::Bool(boolval));
+            }
+            (4, WireType::LengthDelimited) => {
+                value = Some(ConstValue::String(decode_nested_string(
+                    reader.read_bytes()?,
+                )?));
+            }
+            (5, WireType::LengthDelimited) => {
+                value = Some(ConstValue::BitString(decode_nested_string(
+                    reader.read_bytes()?,
+                )?));
+            }
+            (10, WireType::Varint) => isnull = reader.read_varint()? != 0,
+            (11, WireType::Varint) => location = reader.read_varint()? as ParseLoc,
+            (_, wire_type) => reader.skip(wire_type)?,
+        }
+    }
+    let value = match value {
+        Some(value) => value,
+        None if isnull => ConstValue::Null,
+        None => ConstValue::NotNull,
+    };
+    Ok((value, location))
+}
+
+#[allow(unused_mut, unused_variables, clippy::all)]
+mod generated {
+    use super::*;
+
+    include!(concat!(env!("OUT_DIR"), "/ast_protobuf_decode.rs"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_limits_the_depth() {
+        let depth = Depth::new(1).nested().unwrap();
+        assert!(matches!(depth.nested(), Err(Error::DepthLimitExceeded(1))));
+    }
+
+    #[test]
+    fn it_can_decode_constants() {
+        // ival = Integer { ival: 42 }, location = 7
+        let data = [0x0A, 0x02, 0x08, 0x2A, 0x58, 0x07];
+        assert_eq!(decode_const(&data).unwrap(), (ConstValue::Integer(42), 7));
+        // sval = String { sval: "abc" }
+        let data = [0x22, 0x05, 0x0A, 0x03, b'a', b'b', b'c'];
+        assert_eq!(
+            decode_const(&data).unwrap(),
+            (ConstValue::String("abc".into()), 0)
+        );
+        // isnull = true
+        assert_eq!(decode_const(&[0x50, 0x01]).unwrap(), (ConstValue::Null, 0));
+        // ival = Integer {}, i.e. zero
+        assert_eq!(
+            decode_const(&[0x0A, 0x00]).unwrap(),
+            (ConstValue::Integer(0), 0)
+        );
+        // ival = Integer { ival: -1 }, which is sign extended to ten bytes
+        let mut data = vec![0x0A, 0x0B, 0x08];
+        data.extend([0xFF; 9]);
+        data.push(0x01);
+        assert_eq!(decode_const(&data).unwrap(), (ConstValue::Integer(-1), 0));
+    }
+
+    #[test]
+    fn it_can_measure_the_depth_of_a_parse_result() {
+        // RawStmt, Node, ColumnRef, Node and then String. The string value is also a valid message,
+        // however isn't descended into since the field holds a string.
+        let node = Node::from(ColumnRef {
+            fields: Some(vec![Node::string("\n\0")]),
+            ..ColumnRef::default()
+        });
+        let data = super::super::encode::encode_parse_result(&[node]).unwrap();
+        let depth = parse_result_depth(&data).unwrap();
+        assert_eq!(depth, 5);
+        assert_eq!(decode_parse_result(&data, depth).unwrap().len(), 1);
+    }
+}
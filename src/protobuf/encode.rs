@@ -0,0 +1,136 @@
+//! Encodes the abstract syntax tree into the protobuf parse tree understood by `libpg_query`, which
+//! allows it to be deparsed back into SQL. The encoders for each node are generated from
+//! `pg_query.proto` by the build script, while the helpers they rely on are defined here.
+
+use super::Writer;
+use crate::ast::*;
+use crate::bindings::PG_VERSION_NUM;
+use crate::error::Result;
+use std::fmt;
+
+use generated::encode_node;
+
+/// A message which can be encoded into the protobuf wire format.
+trait Encode {
+    fn encode(&self, writer: &mut Writer);
+}
+
+/// An enum which can be encoded as its protobuf value. As with decoding, these are offset by one
+/// from the values used by PostgreSQL. Values which aren't defined within `pg_query.proto` return
+/// `None`.
+trait ToProto {
+    fn to_proto(self) -> Option<i32>;
+}
+
+/// Encodes the given statements as a `ParseResult` message. Nodes which can't be represented,
+/// such as `Node::Unknown`, return `Error::InvalidAst`.
+pub(crate) fn encode_parse_result(stmts: &[Node]) -> Result<Vec<u8>> {
+    let mut writer = Writer::new();
+    writer.write_int(1, PG_VERSION_NUM as i64);
+    for stmt in stmts {
+        // Each statement is wrapped within a RawStmt
+        writer.write_message(2, |writer| {
+            writer.write_message(1, |writer| encode_node(stmt, writer))
+        });
+    }
+    writer.into_bytes()
+}
+
+/// Writes an enum field, failing if the value isn't defined within `pg_query.proto`.
+fn write_enum<E: ToProto + Copy + fmt::Debug>(writer: &mut Writer, number: u32, value: E) {
+    match value.to_proto() {
+        Some(proto) => writer.write_int(number, proto as i64),
+        None => writer.fail(format!("{value:?} has no protobuf representation")),
+    }
+}
+
+/// Encodes a list of nodes. `libpg_query` represents some clauses, such as `DISTINCT`, as a list
+/// containing a single empty node. These are dropped when decoding, so an empty list is written
+/// back in the same way.
+fn write_nodes(writer: &mut Writer, number: u32, nodes: &[Node]) {
+    if nodes.is_empty() {
+        writer.write_bytes(number, &[]);
+    }
+    for node in nodes {
+        writer.write_message(number, |writer| encode_node(node, writer));
+    }
+}
+
+/// Encodes the value and location of an `A_Const` message.
+fn encode_const(value: &ConstValue, location: ParseLoc, writer: &mut Writer) {
+    match value {
+        ConstValue::Integer(ival) => writer.write_message(1, |writer| writer.write_int(1, *ival)),
+        ConstValue::Float(fval) => writer.write_message(2, |writer| writer.write_string(1, fval)),
+        ConstValue::Bool(boolval) => {
+            writer.write_message(3, |writer| writer.write_bool(1, *boolval))
+        }
+        ConstValue::String(sval) => writer.write_message(4, |writer| writer.write_string(1, sval)),
+        ConstValue::BitString(bsval) => {
+            writer.write_message(5, |writer| writer.write_string(1, bsval))
+        }
+        ConstValue::Null => writer.write_bool(10, true),
+        ConstValue::NotNull => {}
+    }
+    writer.write_int(11, location as i64);
+}
+
+#[allow(unused_variables, clippy::all)]
+mod generated {
+    use super::*;
+
+    include!(concat!(env!("OUT_DIR"), "/ast_protobuf_encode.rs"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protobuf::decode::decode_const;
+
+    #[test]
+    fn it_can_encode_constants() {
+        let values = [
+            ConstValue::Integer(42),
+            ConstValue::Integer(-1),
+            ConstValue::Integer(0),
+            ConstValue::Float("1.5".into()),
+            ConstValue::Bool(true),
+            ConstValue::String("abc".into()),
+            ConstValue::String(String::new()),
+            ConstValue::BitString("b101".into()),
+            ConstValue::Null,
+        ];
+        for value in values {
+            let mut writer = Writer::new();
+            encode_const(&value, 7, &mut writer);
+            assert_eq!(
+                decode_const(&writer.into_bytes().unwrap()).unwrap(),
+                (value, 7)
+            );
+        }
+    }
+
+    #[test]
+    fn it_writes_empty_lists_as_an_empty_node() {
+        let mut writer = Writer::new();
+        write_nodes(&mut writer, 3, &[]);
+        assert_eq!(writer.into_bytes().unwrap(), [0x1A, 0x00]);
+    }
+
+    #[test]
+    fn it_fails_to_encode_unknown_nodes() {
+        let unknown = Node::Unknown {
+            name: "FutureNode".into(),
+            raw: Box::new(serde_json::json!({})),
+        };
+        let stmt = Node::from(SelectStmt {
+            where_clause: Some(Box::new(unknown)),
+            ..Default::default()
+        });
+        assert_eq!(
+            encode_parse_result(&[stmt]),
+            Err(crate::Error::InvalidAst(
+                "unknown node `FutureNode` can't be deparsed".into()
+            ))
+        );
+    }
+}
@@ -1,21 +1,42 @@
-use std::ffi::{CStr, CString};
+use std::ffi::CStr;
+use std::ops::Range;
 use std::os::raw::c_char;
+use std::sync::OnceLock;
 
 use crate::bindings::*;
 use crate::error::*;
+use crate::input::SqlInput;
+use crate::position::Position;
 
 #[derive(Debug, serde::Deserialize)]
-struct ParseResult {
+struct ParseResult<N = crate::ast::Node> {
     #[allow(unused)]
     version: u32,
-    stmts: Vec<Stmt>,
+    stmts: Vec<Stmt<N>>,
 }
 
 #[derive(Debug, serde::Deserialize)]
-struct Stmt {
-    stmt: crate::ast::Node,
-    #[allow(unused)]
-    stmt_len: Option<u32>,
+struct Stmt<N> {
+    stmt: N,
+    #[serde(default)]
+    stmt_location: i32,
+    #[serde(default)]
+    stmt_len: i32,
+}
+
+/// A statement parsed from a (potentially multi-statement) input, along with the location of the
+/// statement within that input.
+#[derive(Debug)]
+pub struct ParsedStatement<'a> {
+    /// The parsed statement.
+    pub node: crate::ast::Node,
+    /// The byte offset of the statement within the input.
+    pub location: usize,
+    /// The length of the statement in bytes.
+    pub length: usize,
+    /// The source text of the statement. This is a slice of the original input and, as reported by
+    /// PostgreSQL, may include any whitespace or comments preceding the statement.
+    pub sql: &'a str,
 }
 
 /// Represents the resulting fingerprint containing both the raw integer form as well as the
@@ -25,6 +46,26 @@ pub struct Fingerprint {
     pub hex: String,
 }
 
+/// Options that control how the parse tree is built.
+#[derive(Clone, Debug, Default)]
+pub struct ParseOptions {
+    /// The maximum nesting depth of the parse tree, counted as the number of nested objects and
+    /// lists within the JSON produced by `libpg_query` (typically two per nested expression).
+    /// Deeper trees are rejected with `Error::DepthLimitExceeded`. Defaults to `None`, in which
+    /// case trees of any depth are accepted.
+    ///
+    /// Deep trees are deserialized on a dedicated thread with a stack large enough to hold them,
+    /// however the resulting nodes are still recursive. Dropping, cloning, formatting, comparing and
+    /// visiting them all recurse on the caller's stack, so a caller accepting arbitrary SQL should
+    /// either set a limit or handle deep trees on a thread with a correspondingly large stack.
+    pub max_depth: Option<usize>,
+    /// When true, nodes which can't be deserialized, such as node types introduced by a newer
+    /// version of `libpg_query`, are preserved as `Node::Unknown` rather than failing the parse with
+    /// `Error::InvalidAst`. Nodes with fields which aren't recognized are also preserved, rather
+    /// than dropping those fields. Defaults to `false`.
+    pub lenient: bool,
+}
+
 /// Parses the given SQL statement into the given abstract syntax tree.
 ///
 /// # Example
@@ -38,25 +79,593 @@ pub struct Fingerprint {
 /// let el: &Node = &result[0];
 /// assert!(matches!(*el, Node::SelectStmt(_)));
 /// ```
-pub fn parse(stmt: &str) -> Result<Vec<crate::ast::Node>> {
+pub fn parse<S: SqlInput + ?Sized>(stmt: &S) -> Result<Vec<crate::ast::Node>> {
+    let parsed = parse_tree(stmt)?;
+    Ok(parsed.stmts.into_iter().map(|s| s.stmt).collect())
+}
+
+/// Similar to `parse`: parses the given SQL statement into the given abstract syntax tree using
+/// the provided options.
+///
+/// # Example
+///
+/// ```rust
+/// use pg_parse::ParseOptions;
+///
+/// let sql = format!("SELECT {}", vec!["1"; 100].join(" + "));
+/// assert!(pg_parse::parse(&sql).is_ok());
+///
+/// let options = ParseOptions {
+///     max_depth: Some(64),
+///     ..ParseOptions::default()
+/// };
+/// let result = pg_parse::parse_with_options(&sql, &options);
+/// assert!(matches!(result, Err(pg_parse::Error::DepthLimitExceeded(64))));
+/// ```
+pub fn parse_with_options<S: SqlInput + ?Sized>(
+    stmt: &S,
+    options: &ParseOptions,
+) -> Result<Vec<crate::ast::Node>> {
+    let parsed = parse_tree_with_options(stmt, PgQueryParseMode_PG_QUERY_PARSE_DEFAULT, options)?;
+    Ok(parsed.stmts.into_iter().map(|s| s.stmt).collect())
+}
+
+/// Parses the given SQL into the given abstract syntax tree, returning each statement together with
+/// its location within the input. This is useful when parsing scripts containing many statements.
+///
+/// # Example
+///
+/// ```rust
+/// use pg_parse::ast::Node;
+///
+/// let sql = "SELECT * FROM contacts; DELETE FROM contacts";
+/// let result = pg_parse::parse_statements(sql).unwrap();
+/// assert_eq!(result.len(), 2);
+/// assert!(matches!(result[0].node, Node::SelectStmt(_)));
+/// assert_eq!(result[0].sql, "SELECT * FROM contacts");
+/// assert!(matches!(result[1].node, Node::DeleteStmt(_)));
+/// assert_eq!(result[1].location, 23);
+/// assert_eq!(result[1].sql, " DELETE FROM contacts");
+/// ```
+pub fn parse_statements(stmt: &str) -> Result<Vec<ParsedStatement<'_>>> {
+    let parsed = parse_tree(stmt)?;
+    parsed
+        .stmts
+        .into_iter()
+        .map(|s| {
+            let range = statement_range(stmt.len(), s.stmt_location, s.stmt_len);
+            let sql = stmt.get(range.clone()).ok_or_else(|| {
+                Error::InvalidAst(format!(
+                    "statement location {}..{} is out of range",
+                    range.start, range.end
+                ))
+            })?;
+            Ok(ParsedStatement {
+                node: s.stmt,
+                location: range.start,
+                length: range.len(),
+                sql,
+            })
+        })
+        .collect()
+}
+
+/// Parses each statement within the given script independently, so that a syntax error within one
+/// statement doesn't prevent the remaining statements from being parsed. The script is split into
+/// statements using the scanner (see `split`), and a result is returned for each statement in the
+/// order they appear.
+///
+/// Locations within the abstract syntax tree, as well as the positions of any errors, are relative
+/// to the start of the script rather than the individual statement.
+///
+/// # Example
+///
+/// ```rust
+/// use pg_parse::ast::Node;
+///
+/// let sql = "SELECT 1; SELEC 2; DELETE FROM contacts";
+/// let result = pg_parse::parse_script(sql);
+/// assert_eq!(result.len(), 3);
+/// assert!(matches!(result[0].as_ref().unwrap().node, Node::SelectStmt(_)));
+/// let Err(pg_parse::Error::ParseError(error)) = &result[1] else {
+///     panic!("Expected a parse error");
+/// };
+/// assert_eq!(error.position.unwrap().offset, 10);
+/// assert_eq!(result[2].as_ref().unwrap().sql, " DELETE FROM contacts");
+/// ```
+pub fn parse_script(stmt: &str) -> Vec<Result<ParsedStatement<'_>>> {
+    let ranges = match split(stmt, SplitMode::Scanner) {
+        Ok(ranges) => ranges,
+        Err(error) => return vec![Err(error)],
+    };
+    let mut statements = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match parse_script_statement(stmt, range) {
+            Ok(parsed) => statements.extend(parsed.into_iter().map(Ok)),
+            Err(error) => statements.push(Err(error)),
+        }
+    }
+    statements
+}
+
+/// Parses a single statement of a script, adjusting all locations to be relative to the script.
+fn parse_script_statement(script: &str, range: Range<usize>) -> Result<Vec<ParsedStatement<'_>>> {
+    let sql = script.get(range.clone()).ok_or_else(|| {
+        Error::InvalidAst(format!(
+            "statement location {}..{} is out of range",
+            range.start, range.end
+        ))
+    })?;
+    let json = unsafe {
+        let c_str = sql.to_c_str().map_err(|error| match error {
+            Error::InteriorNul(offset) => Error::InteriorNul(range.start + offset),
+            error => error,
+        })?;
+        let result = pg_query_parse(c_str.as_ptr() as *const c_char);
+
+        // Capture any errors first, positioning them within the script
+        if !result.error.is_null() {
+            let mut error = parse_error(&*result.error, &c_str);
+            pg_query_free_parse_result(result);
+            if let Error::ParseError(error) = &mut error {
+                error.position = error.position.and_then(|position| {
+                    Position::from_bytes(script.as_bytes(), range.start + position.offset)
+                });
+            }
+            return Err(error);
+        }
+
+        let json = shift_locations(CStr::from_ptr(result.parse_tree).to_bytes(), range.start);
+        pg_query_free_parse_result(result);
+        json
+    };
+
+    let parsed: ParseResult = from_json(&json, None, |e| Error::InvalidAst(e.to_string()))?;
+    parsed
+        .stmts
+        .into_iter()
+        .map(|s| {
+            // The statement location has already been shifted, whereas a length of zero means the
+            // statement extends to the end of this part of the script
+            let location = (s.stmt_location.max(0) as usize).clamp(range.start, range.end);
+            let length = if s.stmt_len <= 0 {
+                range.end - location
+            } else {
+                s.stmt_len as usize
+            };
+            let sql = script.get(location..location + length).ok_or_else(|| {
+                Error::InvalidAst(format!(
+                    "statement location {}..{} is out of range",
+                    location,
+                    location + length
+                ))
+            })?;
+            Ok(ParsedStatement {
+                node: s.stmt,
+                location,
+                length,
+                sql,
+            })
+        })
+        .collect()
+}
+
+/// Shifts every location within the JSON produced by `libpg_query` (i.e. the value of any key
+/// ending with `location`) by the given number of bytes. Unknown locations (`-1`) are unchanged.
+fn shift_locations(json: &[u8], offset: usize) -> Vec<u8> {
+    let mut shifted = Vec::with_capacity(json.len() + json.len() / 8);
+    let mut index = 0;
+    while index < json.len() {
+        let byte = json[index];
+        shifted.push(byte);
+        index += 1;
+        if byte != b'"' {
+            continue;
+        }
+
+        // Copy the string, taking care to skip escaped quotes
+        let start = index;
+        while index < json.len() && json[index] != b'"' {
+            index += if json[index] == b'\\' { 2 } else { 1 };
+        }
+        let end = index.min(json.len());
+        shifted.extend_from_slice(&json[start..end]);
+        if index < json.len() {
+            shifted.push(b'"');
+            index += 1;
+        }
+
+        // Only keys followed by a non-negative integer are locations
+        if !json[start..end].ends_with(b"location") || json.get(index) != Some(&b':') {
+            continue;
+        }
+        shifted.push(b':');
+        index += 1;
+        let digits = json[index..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count();
+        if digits == 0 {
+            continue;
+        }
+        match std::str::from_utf8(&json[index..index + digits])
+            .ok()
+            .and_then(|value| value.parse::<usize>().ok())
+        {
+            Some(location) => shifted.extend_from_slice((location + offset).to_string().as_bytes()),
+            None => shifted.extend_from_slice(&json[index..index + digits]),
+        }
+        index += digits;
+    }
+    shifted
+}
+
+/// A parse tree which retains the JSON produced by `libpg_query`, allowing it to be deserialized into
+/// an abstract syntax tree that borrows from it. See [`crate::ast::borrowed`].
+pub struct ParseTree {
+    json: Box<[u8]>,
+}
+
+impl ParseTree {
+    /// Deserializes the statements within this parse tree. Identifiers and literals borrow from the
+    /// parse tree where possible rather than being allocated.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use pg_parse::ast::borrowed::Node;
+    /// use std::borrow::Cow;
+    ///
+    /// let tree = pg_parse::parse_borrowed("SELECT * FROM contacts").unwrap();
+    /// let statements = tree.statements().unwrap();
+    /// let Node::SelectStmt(stmt) = &statements[0] else {
+    ///     panic!("Expected a select statement");
+    /// };
+    /// let Node::RangeVar(relation) = &stmt.from_clause.as_ref().unwrap()[0] else {
+    ///     panic!("Expected a relation");
+    /// };
+    /// assert!(matches!(relation.relname, Some(Cow::Borrowed("contacts"))));
+    /// ```
+    pub fn statements(&self) -> Result<Vec<crate::ast::borrowed::Node<'_>>> {
+        let parsed: ParseResult<crate::ast::borrowed::Node> =
+            from_json(&self.json, None, |e| Error::InvalidAst(e.to_string()))?;
+        Ok(parsed.stmts.into_iter().map(|s| s.stmt).collect())
+    }
+}
+
+/// Parses the given SQL statement, returning a parse tree from which an abstract syntax tree that
+/// borrows its strings can be built. This avoids allocating a `String` for every identifier and
+/// literal, which can be significantly faster than `parse` when parsing large volumes of SQL.
+///
+/// # Example
+///
+/// ```rust
+/// use pg_parse::ast::borrowed::Node;
+///
+/// let tree = pg_parse::parse_borrowed("SELECT * FROM contacts").unwrap();
+/// let statements = tree.statements().unwrap();
+/// assert!(matches!(statements[0], Node::SelectStmt(_)));
+/// ```
+pub fn parse_borrowed<S: SqlInput + ?Sized>(stmt: &S) -> Result<ParseTree> {
     unsafe {
-        let c_str = CString::new(stmt).unwrap();
+        let c_str = stmt.to_c_str()?;
         let result = pg_query_parse(c_str.as_ptr() as *const c_char);
 
         // Capture any errors first
         if !result.error.is_null() {
-            let error = &*result.error;
-            let message = CStr::from_ptr(error.message).to_string_lossy().into();
+            let error = parse_error(&*result.error, &c_str);
             pg_query_free_parse_result(result);
-            return Err(Error::ParseError(message));
+            return Err(error);
+        }
+
+        // Keep a copy of the JSON so that the tree can borrow from it
+        let json = CStr::from_ptr(result.parse_tree).to_bytes().into();
+        pg_query_free_parse_result(result);
+        Ok(ParseTree { json })
+    }
+}
+
+/// Similar to `parse`: parses the given SQL statement into the given abstract syntax tree, however
+/// decodes the protobuf output of `libpg_query` rather than deserializing JSON. This is typically
+/// faster, and produces the same tree. Requires the `protobuf` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use pg_parse::ast::Node;
+///
+/// let result = pg_parse::parse_protobuf("SELECT * FROM contacts").unwrap();
+/// assert!(matches!(result[0], Node::SelectStmt(_)));
+/// ```
+#[cfg(feature = "protobuf")]
+pub fn parse_protobuf<S: SqlInput + ?Sized>(stmt: &S) -> Result<Vec<crate::ast::Node>> {
+    unsafe {
+        let c_str = stmt.to_c_str()?;
+        let result = pg_query_parse_protobuf(c_str.as_ptr() as *const c_char);
+
+        // Capture any errors first
+        if !result.error.is_null() {
+            let error = parse_error(&*result.error, &c_str);
+            pg_query_free_protobuf_parse_result(result);
+            return Err(error);
+        }
+
+        // Decode the protobuf into the AST
+        let parse_tree = result.parse_tree;
+        let parsed = if parse_tree.len == 0 || parse_tree.data.is_null() {
+            Ok(Vec::new())
+        } else {
+            let data = std::slice::from_raw_parts(parse_tree.data as *const u8, parse_tree.len);
+            from_protobuf(data)
+        };
+        pg_query_free_protobuf_parse_result(result);
+        parsed
+    }
+}
+
+/// Converts the given statements back into SQL using the deparser within `libpg_query`. The tree is
+/// encoded into the same protobuf format that `parse_protobuf` decodes, so any tree produced by
+/// parsing can be deparsed. Requires the `protobuf` feature.
+///
+/// # Example
+///
+/// ```rust
+/// let result = pg_parse::parse("SELECT * FROM contacts WHERE id = 1").unwrap();
+/// let sql = pg_parse::deparse(&result).unwrap();
+/// assert_eq!(sql, "SELECT * FROM contacts WHERE id = 1");
+/// ```
+#[cfg(feature = "protobuf")]
+pub fn deparse(stmts: &[crate::ast::Node]) -> Result<String> {
+    let data = crate::protobuf::encode::encode_parse_result(stmts)?;
+    unsafe {
+        let parse_tree = PgQueryProtobuf {
+            len: data.len(),
+            data: data.as_ptr() as *mut c_char,
+        };
+        let result = pg_query_deparse_protobuf(parse_tree);
+
+        // Capture any errors first
+        if !result.error.is_null() {
+            let error = parse_error(&*result.error, c"");
+            pg_query_free_deparse_result(result);
+            return Err(error);
+        }
+
+        let raw = CStr::from_ptr(result.query);
+        let owned = raw.to_string_lossy().to_string();
+        pg_query_free_deparse_result(result);
+        Ok(owned)
+    }
+}
+
+/// Parses the given type name, such as `numeric(10,2)[]`, into its abstract syntax tree.
+///
+/// # Example
+///
+/// ```rust
+/// use pg_parse::ast::{ConstValue, Node};
+///
+/// let result = pg_parse::parse_type_name("varchar(255)[]").unwrap();
+/// assert_eq!(result.names.as_ref().unwrap().len(), 2);
+/// assert!(matches!(
+///     result.typmods.as_ref().unwrap()[0],
+///     Node::A_Const {
+///         val: ConstValue::Integer(255),
+///         ..
+///     }
+/// ));
+/// assert_eq!(result.array_bounds.as_ref().unwrap().len(), 1);
+/// ```
+pub fn parse_type_name<S: SqlInput + ?Sized>(stmt: &S) -> Result<crate::ast::TypeName> {
+    let parsed = parse_tree_with_mode(stmt, PgQueryParseMode_PG_QUERY_PARSE_TYPE_NAME)?;
+    match parsed.stmts.into_iter().next().map(|s| s.stmt) {
+        Some(crate::ast::Node::TypeName(type_name)) => Ok(*type_name),
+        other => Err(Error::InvalidAst(format!(
+            "expected a type name but found {:?}",
+            other
+        ))),
+    }
+}
+
+/// Parses a standalone expression, such as a column default or check constraint, into its abstract
+/// syntax tree. The input must consist of a single expression without a column alias.
+/// Input which parses but is not such an expression is reported as `Error::InvalidAst`.
+///
+/// # Example
+///
+/// ```rust
+/// use pg_parse::ast::Node;
+///
+/// let result = pg_parse::parse_expression("a + b * 2").unwrap();
+/// assert!(matches!(result, Node::A_Expr(_)));
+///
+/// assert!(pg_parse::parse_expression("a, b").is_err());
+/// ```
+pub fn parse_expression<S: SqlInput + ?Sized>(stmt: &S) -> Result<crate::ast::Node> {
+    use crate::ast::{Node, ResTarget, SelectStmt};
+
+    // Expressions are parsed as the target list of a SELECT statement, e.g. `SELECT <expr>`
+    let parsed = parse_tree_with_mode(stmt, PgQueryParseMode_PG_QUERY_PARSE_PLPGSQL_EXPR)?;
+    let select = match parsed.stmts.into_iter().next().map(|s| s.stmt) {
+        Some(Node::SelectStmt(select)) => select,
+        other => {
+            return Err(Error::InvalidAst(format!(
+                "expected a select statement but found {:?}",
+                other
+            )));
+        }
+    };
+    let SelectStmt {
+        distinct_clause: None,
+        target_list: Some(mut targets),
+        from_clause: None,
+        where_clause: None,
+        group_clause: None,
+        having_clause: None,
+        window_clause: None,
+        sort_clause: None,
+        limit_offset: None,
+        limit_count: None,
+        locking_clause: None,
+        ..
+    } = *select
+    else {
+        return Err(Error::InvalidAst("expected a single expression".into()));
+    };
+    if targets.len() != 1 {
+        return Err(Error::InvalidAst("expected a single expression".into()));
+    }
+    match targets.pop().and_then(Node::into_res_target) {
+        Some(ResTarget {
+            name: None,
+            indirection: None,
+            val: Some(val),
+            ..
+        }) => Ok(*val),
+        _ => Err(Error::InvalidAst(
+            "expected an expression without an alias".into(),
+        )),
+    }
+}
+
+fn parse_tree<S: SqlInput + ?Sized>(stmt: &S) -> Result<ParseResult> {
+    parse_tree_with_mode(stmt, PgQueryParseMode_PG_QUERY_PARSE_DEFAULT)
+}
+
+fn parse_tree_with_mode<S: SqlInput + ?Sized>(
+    stmt: &S,
+    mode: PgQueryParseMode,
+) -> Result<ParseResult> {
+    parse_tree_with_options(stmt, mode, &ParseOptions::default())
+}
+
+fn parse_tree_with_options<S: SqlInput + ?Sized>(
+    stmt: &S,
+    mode: PgQueryParseMode,
+    options: &ParseOptions,
+) -> Result<ParseResult> {
+    unsafe {
+        let c_str = stmt.to_c_str()?;
+        let result = pg_query_parse_opts(c_str.as_ptr() as *const c_char, mode as i32);
+
+        // Capture any errors first
+        if !result.error.is_null() {
+            let error = parse_error(&*result.error, &c_str);
+            pg_query_free_parse_result(result);
+            return Err(error);
         }
 
         // Parse the JSON into the AST
         let raw = CStr::from_ptr(result.parse_tree);
-        let parsed: ParseResult =
-            serde_json::from_slice(raw.to_bytes()).map_err(|e| Error::InvalidAst(e.to_string()))?;
+        let parsed = if options.lenient {
+            from_json_lenient(raw.to_bytes(), options.max_depth)
+        } else {
+            from_json(raw.to_bytes(), options.max_depth, |e| {
+                Error::InvalidAst(e.to_string())
+            })
+        };
         pg_query_free_parse_result(result);
-        Ok(parsed.stmts.into_iter().map(|s| s.stmt).collect())
+        parsed
+    }
+}
+
+/// The method used to split a script into individual statements.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SplitMode {
+    /// Splits statements using only the scanner. This is less accurate than using the parser,
+    /// however it continues to work when statements contain syntax errors.
+    Scanner,
+    /// Splits statements using the full parser. This is the most accurate method, however the input
+    /// must be syntactically valid.
+    Parser,
+}
+
+/// Splits the given SQL into individual statements without building the abstract syntax tree,
+/// returning the byte range of each statement within the input.
+///
+/// Both modes correctly handle semicolons within strings, dollar quoted bodies and comments.
+///
+/// # Example
+///
+/// ```rust
+/// use pg_parse::SplitMode;
+///
+/// let sql = "SELECT 1; SELECT $$a;b$$; SELEC 3";
+/// let result = pg_parse::split(sql, SplitMode::Scanner).unwrap();
+/// assert_eq!(result.len(), 3);
+/// assert_eq!(sql[result[1].clone()].trim(), "SELECT $$a;b$$");
+///
+/// // The parser is more accurate, however requires the statements to be valid
+/// assert!(pg_parse::split(sql, SplitMode::Parser).is_err());
+/// ```
+pub fn split<S: SqlInput + ?Sized>(stmt: &S, mode: SplitMode) -> Result<Vec<Range<usize>>> {
+    unsafe {
+        let c_str = stmt.to_c_str()?;
+        let result = match mode {
+            SplitMode::Scanner => pg_query_split_with_scanner(c_str.as_ptr() as *const c_char),
+            SplitMode::Parser => pg_query_split_with_parser(c_str.as_ptr() as *const c_char),
+        };
+
+        // Capture any errors first
+        if !result.error.is_null() {
+            let error = parse_error(&*result.error, &c_str);
+            pg_query_free_split_result(result);
+            return Err(error);
+        }
+
+        // Collect the statement ranges
+        let mut ranges = Vec::with_capacity(result.n_stmts as usize);
+        for index in 0..result.n_stmts as usize {
+            let split = &**result.stmts.add(index);
+            ranges.push(statement_range(
+                c_str.to_bytes().len(),
+                split.stmt_location,
+                split.stmt_len,
+            ));
+        }
+        pg_query_free_split_result(result);
+        Ok(ranges)
+    }
+}
+
+/// Scans the given SQL into a list of tokens without parsing it. Each token includes its byte
+/// range within the input along with its keyword category, making this useful for tasks such as
+/// syntax highlighting. Scanning continues to work for input that is not syntactically valid.
+///
+/// # Example
+///
+/// ```rust
+/// use pg_parse::{KeywordKind, Token};
+///
+/// let sql = "SELECT name FROM contacts";
+/// let tokens = pg_parse::scan(sql).unwrap();
+/// assert_eq!(tokens.len(), 4);
+/// assert_eq!(tokens[0].token, Token::SELECT);
+/// assert_eq!(tokens[0].keyword_kind, KeywordKind::Reserved);
+/// assert_eq!(tokens[1].token, Token::IDENT);
+/// assert_eq!(&sql[tokens[1].range()], "name");
+/// ```
+pub fn scan<S: SqlInput + ?Sized>(stmt: &S) -> Result<Vec<crate::ScanToken>> {
+    unsafe {
+        let c_str = stmt.to_c_str()?;
+        let result = pg_query_scan(c_str.as_ptr() as *const c_char);
+
+        // Capture any errors first
+        if !result.error.is_null() {
+            let error = parse_error(&*result.error, &c_str);
+            pg_query_free_scan_result(result);
+            return Err(error);
+        }
+
+        // Decode the protobuf result
+        let tokens = if result.pbuf.len == 0 || result.pbuf.data.is_null() {
+            Ok(Vec::new())
+        } else {
+            let data = std::slice::from_raw_parts(result.pbuf.data as *const u8, result.pbuf.len);
+            crate::token::decode_scan_result(data)
+        };
+        pg_query_free_scan_result(result);
+        tokens
     }
 }
 
@@ -75,26 +684,26 @@ pub fn parse(stmt: &str) -> Result<Vec<crate::ast::Node>> {
 /// assert!(matches!(*el, Node::SelectStmt(_)));
 /// assert!(raw.contains("\"SelectStmt\""));
 /// ```
-pub fn parse_debug(stmt: &str) -> Result<(Vec<crate::ast::Node>, String)> {
+pub fn parse_debug<S: SqlInput + ?Sized>(stmt: &S) -> Result<(Vec<crate::ast::Node>, String)> {
     unsafe {
-        let c_str = CString::new(stmt).unwrap();
+        let c_str = stmt.to_c_str()?;
         let result = pg_query_parse(c_str.as_ptr() as *const c_char);
 
         // Capture any errors first
         if !result.error.is_null() {
-            let error = &*result.error;
-            let message = CStr::from_ptr(error.message).to_string_lossy().into();
+            let error = parse_error(&*result.error, &c_str);
             pg_query_free_parse_result(result);
-            return Err(Error::ParseError(message));
+            return Err(error);
         }
 
         // Parse the JSON into the AST
         let raw = CStr::from_ptr(result.parse_tree);
         let debug = raw.to_string_lossy().to_string();
-        let parsed: ParseResult = serde_json::from_slice(raw.to_bytes())
-            .map_err(|e| Error::InvalidAstWithDebug(e.to_string(), debug.to_string()))?;
+        let parsed: Result<ParseResult> = from_json(raw.to_bytes(), None, |e| {
+            Error::InvalidAstWithDebug(e.to_string(), debug.to_string())
+        });
         pg_query_free_parse_result(result);
-        Ok((parsed.stmts.into_iter().map(|s| s.stmt).collect(), debug))
+        Ok((parsed?.stmts.into_iter().map(|s| s.stmt).collect(), debug))
     }
 }
 
@@ -108,17 +717,16 @@ pub fn parse_debug(stmt: &str) -> Result<(Vec<crate::ast::Node>, String)> {
 /// let result = result.unwrap();
 /// assert_eq!(result, "SELECT * FROM contacts WHERE name=$1");
 /// ```
-pub fn normalize(stmt: &str) -> Result<String> {
+pub fn normalize<S: SqlInput + ?Sized>(stmt: &S) -> Result<String> {
     unsafe {
-        let c_str = CString::new(stmt).unwrap();
+        let c_str = stmt.to_c_str()?;
         let result = pg_query_normalize(c_str.as_ptr() as *const c_char);
 
         // Capture any errors first
         if !result.error.is_null() {
-            let error = &*result.error;
-            let message = CStr::from_ptr(error.message).to_string_lossy().into();
+            let error = parse_error(&*result.error, &c_str);
             pg_query_free_normalize_result(result);
-            return Err(Error::ParseError(message));
+            return Err(error);
         }
 
         // Parse the query back
@@ -140,17 +748,16 @@ pub fn normalize(stmt: &str) -> Result<String> {
 /// let result = result.unwrap();
 /// assert_eq!(result.hex, "0e2581a461ece536");
 /// ```
-pub fn fingerprint(stmt: &str) -> Result<Fingerprint> {
+pub fn fingerprint<S: SqlInput + ?Sized>(stmt: &S) -> Result<Fingerprint> {
     unsafe {
-        let c_str = CString::new(stmt).unwrap();
+        let c_str = stmt.to_c_str()?;
         let result = pg_query_fingerprint(c_str.as_ptr() as *const c_char);
 
         // Capture any errors first
         if !result.error.is_null() {
-            let error = &*result.error;
-            let message = CStr::from_ptr(error.message).to_string_lossy().into();
+            let error = parse_error(&*result.error, &c_str);
             pg_query_free_fingerprint_result(result);
-            return Err(Error::ParseError(message));
+            return Err(error);
         }
 
         // Parse the fingerprint
@@ -183,24 +790,573 @@ pub fn fingerprint(stmt: &str) -> Result<Fingerprint> {
 ///     );
 /// assert!(result.is_ok());
 /// ```
-pub fn parse_plpgsql(stmt: &str) -> Result<serde_json::Value> {
+pub fn parse_plpgsql<S: SqlInput + ?Sized>(stmt: &S) -> Result<serde_json::Value> {
     unsafe {
-        let c_str = CString::new(stmt).unwrap();
+        let c_str = stmt.to_c_str()?;
         let result = pg_query_parse_plpgsql(c_str.as_ptr() as *const c_char);
 
         // Capture any errors first
         if !result.error.is_null() {
-            let error = &*result.error;
-            let message = CStr::from_ptr(error.message).to_string_lossy().into();
+            let error = parse_error(&*result.error, &c_str);
             pg_query_free_plpgsql_parse_result(result);
-            return Err(Error::ParseError(message));
+            return Err(error);
         }
 
         // Parse the pglpsql tree
         let raw = CStr::from_ptr(result.plpgsql_funcs);
-        let owned = serde_json::from_str(&raw.to_string_lossy())
-            .map_err(|e| Error::InvalidJson(e.to_string()))?;
+        let owned = from_json(raw.to_bytes(), None, |e| Error::InvalidJson(e.to_string()));
         pg_query_free_plpgsql_parse_result(result);
-        Ok(owned)
+        owned
+    }
+}
+
+/// The nesting depth up to which JSON is deserialized on the calling thread. This is well within
+/// the default recursion limit of `serde_json`, which is known to be safe for typical stack sizes.
+const INLINE_DEPTH: usize = 128;
+
+/// The stack space measured for each level of nesting is multiplied by this when reserving the stack
+/// for a deep tree, allowing for the borrowed AST, which isn't measured.
+const STACK_MARGIN: usize = 4;
+
+/// The least stack space reserved for each level of nesting, should measuring it fail.
+const MIN_STACK_PER_LEVEL: usize = 4 * 1024;
+
+/// The stack space to reserve for each level of nesting when deserializing deeper JSON trees.
+fn stack_per_level() -> usize {
+    static STACK_PER_LEVEL: OnceLock<usize> = OnceLock::new();
+    *STACK_PER_LEVEL
+        .get_or_init(|| (measure_stack_per_level() * STACK_MARGIN).max(MIN_STACK_PER_LEVEL))
+}
+
+/// Measures the stack space used by each level of nesting by deserializing two small trees of
+/// different depths and comparing how far the stack grows for each. The JSON is read one byte at a
+/// time through `StackProbe`, which records the deepest point the stack reaches while reading.
+fn measure_stack_per_level() -> usize {
+    struct StackProbe<'a> {
+        json: &'a [u8],
+        lowest: usize,
+    }
+
+    impl std::io::Read for StackProbe<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let marker = 0u8;
+            let address = std::hint::black_box(&marker) as *const u8 as usize;
+            self.lowest = self.lowest.min(address);
+            self.json.read(buf)
+        }
+    }
+
+    fn measure(expressions: usize) -> (usize, usize) {
+        let json = format!(
+            r#"{}{{"ColumnRef":{{}}}}{}"#,
+            r#"{"A_Expr":{"lexpr":"#.repeat(expressions),
+            r#","kind":"AEXPR_OP"}}"#.repeat(expressions)
+        );
+        let mut probe = StackProbe {
+            json: json.as_bytes(),
+            lowest: usize::MAX,
+        };
+        let _ =
+            serde::Deserialize::deserialize(&mut serde_json::Deserializer::from_reader(&mut probe))
+                .map(|node: crate::ast::Node| node);
+        (json_depth(json.as_bytes()), probe.lowest)
+    }
+
+    // The stack grows downwards on all supported platforms, otherwise this falls back to the minimum
+    let (shallow_depth, shallow) = measure(8);
+    let (deep_depth, deep) = measure(24);
+    shallow.saturating_sub(deep) / (deep_depth - shallow_depth)
+}
+
+/// The stack space to reserve for each level of nesting when decoding deeper protobuf trees.
+#[cfg(feature = "protobuf")]
+fn protobuf_stack_per_level() -> usize {
+    static STACK_PER_LEVEL: OnceLock<usize> = OnceLock::new();
+    *STACK_PER_LEVEL.get_or_init(|| {
+        (measure_protobuf_stack_per_level() * STACK_MARGIN).max(MIN_STACK_PER_LEVEL)
+    })
+}
+
+/// Measures the stack space used by each level of nesting within the protobuf decoder, the same
+/// way as `measure_stack_per_level`. The decoder records the deepest point the stack reaches as it
+/// descends into each message.
+#[cfg(feature = "protobuf")]
+fn measure_protobuf_stack_per_level() -> usize {
+    use crate::ast::{A_Expr, A_Expr_Kind, ColumnRef, Node};
+    use crate::protobuf::decode::{parse_result_depth, probe_stack};
+    use crate::protobuf::encode::encode_parse_result;
+
+    fn measure(expressions: usize) -> (usize, usize) {
+        let mut node = Node::from(ColumnRef::default());
+        for _ in 0..expressions {
+            node = Node::from(A_Expr {
+                kind: Box::new(A_Expr_Kind::AEXPR_OP),
+                lexpr: Some(Box::new(node)),
+                ..A_Expr::default()
+            });
+        }
+        let data = encode_parse_result(&[node]).unwrap_or_default();
+        (
+            parse_result_depth(&data).unwrap_or_default(),
+            probe_stack(&data),
+        )
+    }
+
+    let (shallow_depth, shallow) = measure(8);
+    let (deep_depth, deep) = measure(24);
+    shallow.saturating_sub(deep) / deep_depth.saturating_sub(shallow_depth).max(1)
+}
+
+/// Deserializes the JSON produced by `libpg_query`, supporting trees nested deeper than the
+/// default recursion limit of `serde_json`. Deep trees are deserialized on a dedicated thread with
+/// a stack large enough to hold them, rather than risking overflowing the stack of the caller.
+fn from_json<'de, T, F>(json: &'de [u8], max_depth: Option<usize>, map_err: F) -> Result<T>
+where
+    T: serde::Deserialize<'de> + Send,
+    F: FnOnce(serde_json::Error) -> Error,
+{
+    let depth = json_depth(json);
+    if let Some(max_depth) = max_depth {
+        if depth > max_depth {
+            return Err(Error::DepthLimitExceeded(max_depth));
+        }
+    }
+    if depth <= INLINE_DEPTH {
+        return serde_json::from_slice(json).map_err(map_err);
+    }
+
+    let deserialize = || {
+        let mut deserializer = serde_json::Deserializer::from_slice(json);
+        deserializer.disable_recursion_limit();
+        T::deserialize(&mut deserializer).and_then(|value| {
+            deserializer.end()?;
+            Ok(value)
+        })
+    };
+    on_large_stack(depth, stack_per_level(), deserialize)?.map_err(map_err)
+}
+
+/// Deserializes the JSON produced by `libpg_query`, preserving any node which can't be deserialized
+/// as `Node::Unknown`. Unlike `from_json`, the tree is first read as a `serde_json::Value` so that
+/// fields which aren't recognized can be found, since these are otherwise ignored.
+fn from_json_lenient(json: &[u8], max_depth: Option<usize>) -> Result<ParseResult> {
+    use serde::Deserialize;
+
+    let depth = json_depth(json);
+    if let Some(max_depth) = max_depth {
+        if depth > max_depth {
+            return Err(Error::DepthLimitExceeded(max_depth));
+        }
+    }
+    let deserialize = || {
+        let mut deserializer = serde_json::Deserializer::from_slice(json);
+        deserializer.disable_recursion_limit();
+        let mut value = serde_json::Value::deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        preserve_unknown_nodes(&mut value);
+        ParseResult::deserialize(value)
+    };
+    let result = if depth <= INLINE_DEPTH {
+        deserialize()
+    } else {
+        on_large_stack(depth, stack_per_level(), deserialize)?
+    };
+    result.map_err(|e| Error::InvalidAst(e.to_string()))
+}
+
+/// Replaces each node within the JSON which can't be deserialized, or which contains fields that
+/// aren't recognized, with a `Node::Unknown` containing it. Nodes are checked from the root down,
+/// with their child nodes set aside, so each node is only deserialized once and nothing within an
+/// unknown node is visited.
+fn preserve_unknown_nodes(value: &mut serde_json::Value) {
+    use serde_json::Value;
+
+    match value {
+        Value::Array(values) => values.iter_mut().for_each(preserve_unknown_nodes),
+        Value::Object(fields) if is_json_node(fields) => {
+            if !is_known_node(value) {
+                let Value::Object(fields) = std::mem::take(value) else {
+                    return;
+                };
+                if let Some((name, raw)) = fields.into_iter().next() {
+                    *value = serde_json::json!({ "Unknown": { "name": name, "raw": raw } });
+                }
+                return;
+            }
+            if let Value::Object(fields) = value {
+                fields.values_mut().for_each(preserve_unknown_nodes);
+            }
+        }
+        Value::Object(fields) => fields.values_mut().for_each(preserve_unknown_nodes),
+        _ => {}
+    }
+}
+
+/// Nodes are objects containing a single field named after their type, e.g. `{"A_Star": {}}`,
+/// whereas the fields of a node are named in lower case.
+fn is_json_node(fields: &serde_json::Map<String, serde_json::Value>) -> bool {
+    fields.len() == 1
+        && fields
+            .keys()
+            .all(|name| name.starts_with(|c: char| c.is_ascii_uppercase()))
+}
+
+/// Whether the given node, excluding its child nodes, can be deserialized without losing any of
+/// its fields.
+fn is_known_node(node: &mut serde_json::Value) -> bool {
+    use serde::Deserialize;
+    use serde_json::Value;
+
+    // Child nodes are swapped for a placeholder while deserializing, and are checked separately
+    fn take_children(value: &mut Value, children: &mut Vec<Value>) {
+        match value {
+            Value::Array(values) => values.iter_mut().for_each(|v| take_children(v, children)),
+            Value::Object(fields) if is_json_node(fields) => {
+                children.push(std::mem::replace(
+                    value,
+                    serde_json::json!({ "A_Star": {} }),
+                ));
+            }
+            Value::Object(fields) => fields.values_mut().for_each(|v| take_children(v, children)),
+            _ => {}
+        }
+    }
+    fn restore_children(value: &mut Value, children: &mut impl Iterator<Item = Value>) {
+        match value {
+            Value::Array(values) => values
+                .iter_mut()
+                .for_each(|v| restore_children(v, children)),
+            Value::Object(fields) if is_json_node(fields) => {
+                if let Some(child) = children.next() {
+                    *value = child;
+                }
+            }
+            Value::Object(fields) => fields
+                .values_mut()
+                .for_each(|v| restore_children(v, children)),
+            _ => {}
+        }
+    }
+
+    let Value::Object(node) = node else {
+        return false;
+    };
+    let Some((name, fields)) = node.iter_mut().next() else {
+        return false;
+    };
+    if !has_known_fields(name, fields) {
+        return false;
+    }
+    let mut children = Vec::new();
+    take_children(fields, &mut children);
+    let known = crate::ast::Node::deserialize(&*node).is_ok();
+    if let Some(fields) = node.values_mut().next() {
+        restore_children(fields, &mut children.into_iter());
+    }
+    known
+}
+
+/// Whether every field of the given struct, including those of any structs stored inline, is
+/// recognized. Child nodes aren't visited, since these are checked separately.
+fn has_known_fields(name: &str, fields: &serde_json::Value) -> bool {
+    let Some(known) = crate::ast::json_fields(name) else {
+        return false;
+    };
+    let Some(fields) = fields.as_object() else {
+        // Anything else is left for deserialization to report
+        return true;
+    };
+    fields.iter().all(
+        |(field, value)| match known.iter().find(|(name, _)| name == field) {
+            Some((_, Some(inline))) if value.is_object() => has_known_fields(inline, value),
+            Some(_) => true,
+            None => false,
+        },
+    )
+}
+
+/// Decodes the protobuf produced by `libpg_query`. Trees are decoded on the calling thread up to
+/// the same depth as JSON, with deeper trees measured and then decoded on a dedicated thread.
+#[cfg(feature = "protobuf")]
+fn from_protobuf(data: &[u8]) -> Result<Vec<crate::ast::Node>> {
+    use crate::protobuf::decode::{decode_parse_result, parse_result_depth};
+
+    match decode_parse_result(data, INLINE_DEPTH) {
+        Err(Error::DepthLimitExceeded(_)) => {
+            let depth = parse_result_depth(data)?;
+            on_large_stack(depth, protobuf_stack_per_level(), || {
+                decode_parse_result(data, depth)
+            })?
+        }
+        result => result,
+    }
+}
+
+/// Runs the given function on a dedicated thread with a stack large enough to build a tree of the
+/// given depth, given the stack space used by each level.
+fn on_large_stack<T, F>(depth: usize, stack_per_level: usize, f: F) -> Result<T>
+where
+    T: Send,
+    F: FnOnce() -> T + Send,
+{
+    std::thread::scope(|scope| {
+        let handle = std::thread::Builder::new()
+            .name("pg_parse".into())
+            .stack_size((depth + 1) * stack_per_level)
+            .spawn_scoped(scope, f)
+            .map_err(|e| {
+                Error::InvalidAst(format!(
+                    "unable to deserialize a tree of depth {}: {}",
+                    depth, e
+                ))
+            })?;
+        match handle.join() {
+            Ok(result) => Ok(result),
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    })
+}
+
+/// Calculates the maximum nesting depth of objects and arrays within the given JSON.
+fn json_depth(json: &[u8]) -> usize {
+    let mut depth = 0usize;
+    let mut max = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for byte in json {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'{' | b'[' => {
+                depth += 1;
+                max = max.max(depth);
+            }
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    max
+}
+
+/// Resolves a statement location and length, as reported by PostgreSQL, into a byte range.
+/// A length of zero means that the statement extends to the end of the input.
+fn statement_range(input_len: usize, location: i32, length: i32) -> Range<usize> {
+    let start = location.max(0) as usize;
+    if length <= 0 {
+        start..input_len.max(start)
+    } else {
+        start..start + length as usize
+    }
+}
+
+/// Converts the error reported by `libpg_query` into a structured `Error`.
+unsafe fn parse_error(error: &PgQueryError, stmt: &CStr) -> Error {
+    unsafe {
+        let message = CStr::from_ptr(error.message).to_string_lossy().into();
+        let source_location = match (optional_str(error.funcname), optional_str(error.filename)) {
+            (Some(function), Some(file)) => Some(SourceLocation {
+                function,
+                file,
+                line: error.lineno as u32,
+            }),
+            _ => None,
+        };
+        Error::ParseError(Box::new(ParseError {
+            message,
+            position: Position::from_cursor(stmt.to_bytes(), error.cursorpos),
+            context: optional_str(error.context),
+            source_location,
+        }))
+    }
+}
+
+unsafe fn optional_str(value: *const c_char) -> Option<String> {
+    if value.is_null() {
+        None
+    } else {
+        unsafe { Some(CStr::from_ptr(value).to_string_lossy().into()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Node;
+
+    fn nested_expression(depth: usize) -> String {
+        let mut json = String::from(r#"{"ColumnRef":{"fields":[{"String":{"sval":"a"}}]}}"#);
+        for _ in 0..depth {
+            json = format!(
+                r#"{{"A_Expr":{{"kind":"AEXPR_OP","name":[{{"String":{{"sval":"+"}}}}],"lexpr":{},"rexpr":{{"ColumnRef":{{"fields":[{{"String":{{"sval":"b"}}}}]}}}}}}}}"#,
+                json
+            );
+        }
+        json
+    }
+
+    #[test]
+    fn it_can_shift_locations() {
+        let json =
+            br#"{"location":5,"stmt_location":0,"name_location":-1,"x":7,"sval":"location\":1"}"#;
+        assert_eq!(
+            String::from_utf8(shift_locations(json, 100)).unwrap(),
+            r#"{"location":105,"stmt_location":100,"name_location":-1,"x":7,"sval":"location\":1"}"#
+        );
+    }
+
+    #[test]
+    fn it_can_calculate_the_depth_of_json() {
+        assert_eq!(json_depth(b"1"), 0);
+        assert_eq!(json_depth(br#"{"a":[1,{"b":2}],"c":{}}"#), 3);
+        assert_eq!(json_depth(br#"{"a":"{[\"{"}"#), 1);
+    }
+
+    #[test]
+    fn it_can_deserialize_deeply_nested_json() {
+        let json = nested_expression(5000);
+        let mut node: Node =
+            from_json(json.as_bytes(), None, |e| Error::InvalidAst(e.to_string())).unwrap();
+
+        // Unwind the tree iteratively so that dropping it doesn't require deep recursion
+        let mut depth = 0;
+        while let Node::A_Expr(expr) = node {
+            node = *expr.lexpr.unwrap();
+            depth += 1;
+        }
+        assert_eq!(depth, 5000);
+        assert!(matches!(node, Node::ColumnRef(_)));
+    }
+
+    #[test]
+    fn it_can_measure_the_stack_per_level() {
+        let measured = measure_stack_per_level();
+        assert!(measured > 0);
+        assert!(stack_per_level() >= measured * STACK_MARGIN);
+    }
+
+    #[cfg(feature = "protobuf")]
+    #[test]
+    fn it_can_measure_the_protobuf_stack_per_level() {
+        let measured = measure_protobuf_stack_per_level();
+        assert!(measured > 0);
+        assert!(protobuf_stack_per_level() >= measured * STACK_MARGIN);
+    }
+
+    #[cfg(feature = "protobuf")]
+    #[test]
+    fn it_can_decode_deeply_nested_protobuf() {
+        use crate::ast::{A_Expr, A_Expr_Kind, ColumnRef};
+
+        // Encoding (and dropping) the tree recurses, so this needs a large stack of its own
+        let data = std::thread::Builder::new()
+            .stack_size(256 * 1024 * 1024)
+            .spawn(|| {
+                let mut node = Node::from(ColumnRef::default());
+                for _ in 0..5000 {
+                    node = Node::from(A_Expr {
+                        kind: Box::new(A_Expr_Kind::AEXPR_OP),
+                        lexpr: Some(Box::new(node)),
+                        ..A_Expr::default()
+                    });
+                }
+                crate::protobuf::encode::encode_parse_result(&[node]).unwrap()
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+
+        // The statement, followed by a node and expression for each level and then the column
+        assert_eq!(
+            crate::protobuf::decode::parse_result_depth(&data).unwrap(),
+            1 + 2 * 5000 + 2
+        );
+        let mut node = from_protobuf(&data).unwrap().remove(0);
+
+        // Unwind the tree iteratively so that dropping it doesn't require deep recursion
+        let mut depth = 0;
+        while let Node::A_Expr(expr) = node {
+            node = *expr.lexpr.unwrap();
+            depth += 1;
+        }
+        assert_eq!(depth, 5000);
+        assert!(matches!(node, Node::ColumnRef(_)));
+    }
+
+    #[test]
+    fn it_can_limit_the_depth_of_json() {
+        let json = nested_expression(100);
+        let result: Result<Node> = from_json(json.as_bytes(), Some(150), |e| {
+            Error::InvalidAst(e.to_string())
+        });
+        assert_eq!(result.unwrap_err(), Error::DepthLimitExceeded(150));
+    }
+
+    #[test]
+    fn it_can_preserve_unknown_nodes() {
+        let json = br#"{"version":170000,"stmts":[{"stmt":{"A_Expr":{"kind":"AEXPR_OP",
+            "lexpr":{"FutureExpr":{"arg":{"ColumnRef":{"fields":[{"String":{"sval":"a"}}]}}}},
+            "rexpr":{"ColumnRef":{"fields":"b"}}}}}]}"#;
+        assert!(matches!(
+            from_json::<ParseResult, _>(json, None, |e| Error::InvalidAst(e.to_string())),
+            Err(Error::InvalidAst(_))
+        ));
+
+        let mut parsed = from_json_lenient(json, None).unwrap();
+        let Node::A_Expr(expr) = parsed.stmts.remove(0).stmt else {
+            panic!("expected an A_Expr");
+        };
+        assert_eq!(
+            *expr.lexpr.unwrap(),
+            Node::Unknown {
+                name: "FutureExpr".into(),
+                raw: Box::new(serde_json::json!({
+                    "arg": { "ColumnRef": { "fields": [{ "String": { "sval": "a" } }] } }
+                })),
+            }
+        );
+
+        // Known nodes are also preserved when their fields can't be deserialized
+        assert_eq!(
+            *expr.rexpr.unwrap(),
+            Node::Unknown {
+                name: "ColumnRef".into(),
+                raw: Box::new(serde_json::json!({ "fields": "b" })),
+            }
+        );
+    }
+
+    #[test]
+    fn it_can_preserve_nodes_with_unknown_fields() {
+        let json = br#"{"version":170000,"stmts":[{"stmt":{"A_Expr":{"kind":"AEXPR_OP",
+            "lexpr":{"ColumnRef":{"fields":[{"String":{"sval":"a"}}],"future":true}},
+            "rexpr":{"ColumnRef":{"fields":[{"String":{"sval":"b"}}]}}}}}]}"#;
+        // Serde ignores the field, so this would otherwise be lost
+        assert!(
+            from_json::<ParseResult, _>(json, None, |e| Error::InvalidAst(e.to_string())).is_ok()
+        );
+
+        let mut parsed = from_json_lenient(json, None).unwrap();
+        let Node::A_Expr(expr) = parsed.stmts.remove(0).stmt else {
+            panic!("expected an A_Expr");
+        };
+        assert_eq!(
+            *expr.lexpr.unwrap(),
+            Node::Unknown {
+                name: "ColumnRef".into(),
+                raw: Box::new(serde_json::json!({
+                    "fields": [{ "String": { "sval": "a" } }],
+                    "future": true
+                })),
+            }
+        );
+        assert!(matches!(*expr.rexpr.unwrap(), Node::ColumnRef(_)));
     }
 }
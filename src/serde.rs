@@ -1,28 +1,33 @@
+use std::borrow::Cow;
 use std::fmt;
+use std::marker::PhantomData;
 
-use crate::ast::ConstValue;
-use serde::Deserialize;
+use crate::ast::{ConstValue, ParseLoc, borrowed};
 use serde::de::{Deserializer, Error, SeqAccess, Visitor};
+use serde::ser::{SerializeMap, SerializeSeq, Serializer};
+use serde::{Deserialize, Serialize};
 
-pub(crate) fn deserialize_node_array<'de, D>(
-    deserializer: D,
-) -> Result<Vec<crate::ast::Node>, D::Error>
+pub(crate) fn deserialize_node_array<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
 where
     D: Deserializer<'de>,
+    T: Deserialize<'de>,
 {
     #[derive(serde::Deserialize)]
     #[serde(untagged)]
-    enum NodeOrError {
-        Node(Box<crate::ast::Node>),
+    enum NodeOrError<T> {
+        Node(Box<T>),
         // This consumes one "item" when `T` errors while deserializing.
         // This is necessary to make this work, when instead of having a direct value
         // like integer or string, the deserializer sees a list or map.
         Error(serde::de::IgnoredAny),
     }
 
-    struct NodeArray;
-    impl<'de> Visitor<'de> for NodeArray {
-        type Value = Vec<crate::ast::Node>;
+    struct NodeArray<T>(PhantomData<T>);
+    impl<'de, T> Visitor<'de> for NodeArray<T>
+    where
+        T: Deserialize<'de>,
+    {
+        type Value = Vec<T>;
 
         fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
             formatter.write_str("Vec<Node>")
@@ -43,18 +48,22 @@ where
         }
     }
 
-    deserializer.deserialize_seq(NodeArray)
+    deserializer.deserialize_seq(NodeArray(PhantomData))
 }
 
-pub(crate) fn deserialize_node_array_opt<'de, D>(
+pub(crate) fn deserialize_node_array_opt<'de, D, T>(
     deserializer: D,
-) -> Result<Option<Vec<crate::ast::Node>>, D::Error>
+) -> Result<Option<Vec<T>>, D::Error>
 where
     D: Deserializer<'de>,
+    T: Deserialize<'de>,
 {
-    struct NodeArrayOpt;
-    impl<'de> Visitor<'de> for NodeArrayOpt {
-        type Value = Option<Vec<crate::ast::Node>>;
+    struct NodeArrayOpt<T>(PhantomData<T>);
+    impl<'de, T> Visitor<'de> for NodeArrayOpt<T>
+    where
+        T: Deserialize<'de>,
+    {
+        type Value = Option<Vec<T>>;
 
         fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
             formatter.write_str("Option<Vec<Node>>")
@@ -76,7 +85,7 @@ where
         }
     }
 
-    deserializer.deserialize_option(NodeArrayOpt)
+    deserializer.deserialize_option(NodeArrayOpt(PhantomData))
 }
 
 pub(crate) fn deserialize_nested_string<'de, D>(deserializer: D) -> Result<String, D::Error>
@@ -143,109 +152,301 @@ where
     deserializer.deserialize_option(NestedStringOpt)
 }
 
-impl<'de> serde::Deserialize<'de> for ConstValue {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        struct ConstValueVisitor;
+/// A string which borrows from the input where possible. `Cow<str>` on its own always allocates
+/// unless the field is marked with `#[serde(borrow)]`, which isn't possible within an `Option`.
+#[derive(Deserialize)]
+#[serde(transparent)]
+struct CowStr<'a>(#[serde(borrow)] Cow<'a, str>);
 
-        impl<'de> Visitor<'de> for ConstValueVisitor {
-            type Value = ConstValue;
+pub(crate) fn deserialize_borrowed_str_opt<'de: 'a, 'a, D>(
+    deserializer: D,
+) -> Result<Option<Cow<'a, str>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<CowStr>::deserialize(deserializer)?;
+    Ok(value.map(|value| value.0))
+}
 
-            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("ConnectorTopics")
-            }
+pub(crate) fn deserialize_borrowed_nested_string_opt<'de: 'a, 'a, D>(
+    deserializer: D,
+) -> Result<Option<Cow<'a, str>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct NestedString<'a> {
+        #[serde(borrow)]
+        sval: Cow<'a, str>,
+    }
 
-            fn visit_map<V>(self, mut map: V) -> Result<Self::Value, V::Error>
-            where
-                V: serde::de::MapAccess<'de>,
-            {
-                #[derive(Deserialize)]
-                struct BoolValue {
-                    #[serde(default)]
-                    boolval: bool,
-                }
-                #[derive(Deserialize)]
-                struct IntValue {
-                    #[serde(default)]
-                    ival: i64,
-                }
+    let value = Option::<NestedString>::deserialize(deserializer)?;
+    Ok(value.map(|value| value.sval))
+}
 
-                #[derive(Deserialize)]
-                struct FloatValue {
-                    fval: String,
-                }
+/// Used to skip fields which `libpg_query` omits when they hold their default value, such as
+/// `false`, zero or `'\0'`.
+pub(crate) fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
 
-                #[derive(Deserialize)]
-                struct StringValue {
-                    sval: String,
-                }
+pub(crate) fn serialize_nested_string_opt<S, T>(
+    value: &Option<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: AsRef<str>,
+{
+    #[derive(Serialize)]
+    struct NestedString<'a> {
+        sval: &'a str,
+    }
 
-                #[derive(Deserialize)]
-                struct BitStringValue {
-                    bsval: String,
-                }
+    value
+        .as_ref()
+        .map(|value| NestedString {
+            sval: value.as_ref(),
+        })
+        .serialize(serializer)
+}
 
-                fn maybe_location<'de, V>(mut inner: V) -> Result<(), V::Error>
-                where
-                    V: serde::de::MapAccess<'de>,
-                {
-                    // We may have a location after this which we need to consume
-                    if let Some(_location) = inner.next_key::<String>()? {
-                        let _pos = inner.next_value::<i32>()?;
-                    }
-                    Ok(())
-                }
+/// Serializes an optional list of nodes. `libpg_query` represents some clauses, such as
+/// `DISTINCT`, as a list containing a single empty node. These are dropped when deserializing, so
+/// an empty list is written back in the same way.
+pub(crate) fn serialize_node_array_opt<S, T>(
+    value: &Option<Vec<T>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize,
+{
+    #[derive(Serialize)]
+    struct EmptyNode {}
+
+    match value {
+        Some(nodes) if nodes.is_empty() => {
+            let mut seq = serializer.serialize_seq(Some(1))?;
+            seq.serialize_element(&EmptyNode {})?;
+            seq.end()
+        }
+        value => value.serialize(serializer),
+    }
+}
 
-                if let Some(key) = map.next_key::<String>()? {
-                    match key.as_str() {
-                        "boolval" => {
-                            let value = map.next_value::<BoolValue>()?;
-                            maybe_location(map)?;
-                            Ok(ConstValue::Bool(value.boolval))
-                        }
-                        "ival" => {
-                            let value = map.next_value::<IntValue>()?;
-                            maybe_location(map)?;
-                            Ok(ConstValue::Integer(value.ival))
-                        }
-                        "fval" => {
-                            let value = map.next_value::<FloatValue>()?;
-                            maybe_location(map)?;
-                            Ok(ConstValue::Float(value.fval))
-                        }
-                        "sval" => {
-                            let value = map.next_value::<StringValue>()?;
-                            maybe_location(map)?;
-                            Ok(ConstValue::String(value.sval))
-                        }
-                        "bsval" => {
-                            let value = map.next_value::<BitStringValue>()?;
-                            maybe_location(map)?;
-                            Ok(ConstValue::BitString(value.bsval))
-                        }
-                        "isnull" => {
-                            let null = map.next_value::<bool>()?;
-                            maybe_location(map)?;
-                            if null {
-                                Ok(ConstValue::Null)
-                            } else {
-                                Ok(ConstValue::NotNull)
-                            }
+// The values of an `A_Const` are nested within an object named after their type, e.g.
+// `{ "ival": { "ival": 10 }, "location": 253 }`.
+#[derive(Deserialize, Serialize)]
+struct BoolValue {
+    #[serde(default, skip_serializing_if = "is_default")]
+    boolval: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+struct IntValue {
+    #[serde(default, skip_serializing_if = "is_default")]
+    ival: i64,
+}
+
+#[derive(Deserialize, Serialize)]
+struct FloatValue<'a> {
+    #[serde(borrow)]
+    fval: Cow<'a, str>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct StringValue<'a> {
+    #[serde(borrow)]
+    sval: Cow<'a, str>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct BitStringValue<'a> {
+    #[serde(borrow)]
+    bsval: Cow<'a, str>,
+}
+
+/// Deserializes an `A_Const` into its value along with its location. The value is converted into
+/// the type held by `Node::A_Const`, which may box it.
+pub(crate) fn deserialize_borrowed_const<'de: 'a, 'a, D, T>(
+    deserializer: D,
+) -> Result<(T, ParseLoc), D::Error>
+where
+    D: Deserializer<'de>,
+    T: From<borrowed::ConstValue<'a>>,
+{
+    let (value, location) = deserialize_const_value(deserializer)?;
+    Ok((value.into(), location))
+}
+
+pub(crate) fn deserialize_const<'de, D, T>(deserializer: D) -> Result<(T, ParseLoc), D::Error>
+where
+    D: Deserializer<'de>,
+    T: From<ConstValue>,
+{
+    let (value, location) = deserialize_const_value(deserializer)?;
+    Ok((value.into_owned().into(), location))
+}
+
+fn deserialize_const_value<'de: 'a, 'a, D>(
+    deserializer: D,
+) -> Result<(borrowed::ConstValue<'a>, ParseLoc), D::Error>
+where
+    D: Deserializer<'de>,
+{
+    const FIELDS: &[&str] = &[
+        "boolval", "ival", "fval", "sval", "bsval", "isnull", "location",
+    ];
+
+    struct ConstValueVisitor;
+
+    impl<'de> Visitor<'de> for ConstValueVisitor {
+        type Value = (borrowed::ConstValue<'de>, ParseLoc);
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("A_Const")
+        }
+
+        fn visit_map<V>(self, mut map: V) -> Result<Self::Value, V::Error>
+        where
+            V: serde::de::MapAccess<'de>,
+        {
+            let mut value = None;
+            let mut location = 0;
+            while let Some(key) = map.next_key::<CowStr>()? {
+                value = Some(match &*key.0 {
+                    "boolval" => borrowed::ConstValue::Bool(map.next_value::<BoolValue>()?.boolval),
+                    "ival" => borrowed::ConstValue::Integer(map.next_value::<IntValue>()?.ival),
+                    "fval" => {
+                        borrowed::ConstValue::Float(map.next_value::<FloatValue<'de>>()?.fval)
+                    }
+                    "sval" => {
+                        borrowed::ConstValue::String(map.next_value::<StringValue<'de>>()?.sval)
+                    }
+                    "bsval" => borrowed::ConstValue::BitString(
+                        map.next_value::<BitStringValue<'de>>()?.bsval,
+                    ),
+                    "isnull" => {
+                        if map.next_value::<bool>()? {
+                            borrowed::ConstValue::Null
+                        } else {
+                            borrowed::ConstValue::NotNull
                         }
-                        unknown => Err(Error::unknown_field(
-                            unknown,
-                            &["boolval", "ival", "fval", "sval", "bsval", "isnull"],
-                        )),
                     }
-                } else {
-                    Err(Error::custom("expected value"))
-                }
+                    "location" => {
+                        location = map.next_value()?;
+                        continue;
+                    }
+                    unknown => return Err(Error::unknown_field(unknown, FIELDS)),
+                });
             }
+            Ok((value.unwrap_or(borrowed::ConstValue::NotNull), location))
         }
+    }
 
-        deserializer.deserialize_map(ConstValueVisitor {})
+    deserializer.deserialize_map(ConstValueVisitor)
+}
+
+/// Serializes an `A_Const` the same way as `libpg_query`, omitting the location if it is zero.
+pub(crate) fn serialize_borrowed_const<S>(
+    value: &borrowed::ConstValue<'_>,
+    location: &ParseLoc,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut map = serializer.serialize_map(None)?;
+    match value {
+        borrowed::ConstValue::Bool(boolval) => {
+            map.serialize_entry("boolval", &BoolValue { boolval: *boolval })?
+        }
+        borrowed::ConstValue::Integer(ival) => {
+            map.serialize_entry("ival", &IntValue { ival: *ival })?
+        }
+        borrowed::ConstValue::Float(fval) => map.serialize_entry(
+            "fval",
+            &FloatValue {
+                fval: Cow::Borrowed(fval),
+            },
+        )?,
+        borrowed::ConstValue::String(sval) => map.serialize_entry(
+            "sval",
+            &StringValue {
+                sval: Cow::Borrowed(sval),
+            },
+        )?,
+        borrowed::ConstValue::BitString(bsval) => map.serialize_entry(
+            "bsval",
+            &BitStringValue {
+                bsval: Cow::Borrowed(bsval),
+            },
+        )?,
+        borrowed::ConstValue::Null => map.serialize_entry("isnull", &true)?,
+        borrowed::ConstValue::NotNull => {}
+    }
+    if *location != 0 {
+        map.serialize_entry("location", location)?;
+    }
+    map.end()
+}
+
+pub(crate) fn serialize_const<S>(
+    value: &ConstValue,
+    location: &ParseLoc,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let value = match value {
+        ConstValue::Bool(value) => borrowed::ConstValue::Bool(*value),
+        ConstValue::Integer(value) => borrowed::ConstValue::Integer(*value),
+        ConstValue::Float(value) => borrowed::ConstValue::Float(Cow::Borrowed(value)),
+        ConstValue::String(value) => borrowed::ConstValue::String(Cow::Borrowed(value)),
+        ConstValue::BitString(value) => borrowed::ConstValue::BitString(Cow::Borrowed(value)),
+        ConstValue::Null => borrowed::ConstValue::Null,
+        ConstValue::NotNull => borrowed::ConstValue::NotNull,
+    };
+    serialize_borrowed_const(&value, location, serializer)
+}
+
+impl<'de> serde::Deserialize<'de> for ConstValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (value, _location) = deserialize_const(deserializer)?;
+        Ok(value)
+    }
+}
+
+impl<'de: 'a, 'a> serde::Deserialize<'de> for borrowed::ConstValue<'a> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (value, _location) = deserialize_borrowed_const(deserializer)?;
+        Ok(value)
+    }
+}
+
+impl Serialize for ConstValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_const(self, &0, serializer)
+    }
+}
+
+impl Serialize for borrowed::ConstValue<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_borrowed_const(self, &0, serializer)
     }
 }
 
@@ -272,10 +473,13 @@ mod tests {
         let json = "{ \"values\": [{ \"A_Const\": { \"ival\": { \"ival\": 10 }, \"location\": 253 } }, {}] }";
         let nodes: Nodes = serde_json::from_str(json).unwrap();
         assert_eq!(1, nodes.values.len());
-        assert!(matches!(
+        assert_eq!(
             nodes.values[0],
-            Node::A_Const(ConstValue::Integer(10))
-        ))
+            Node::A_Const {
+                val: Box::new(ConstValue::Integer(10)),
+                location: 253
+            }
+        )
     }
 
     #[test]
@@ -411,16 +615,23 @@ mod tests {
         // Consequently, this test covers these cases
         let null_json = "{ \"A_Const\": { \"isnull\": true, \"location\": 323 } }";
         let null_const: Node = serde_json::from_str(null_json).expect("Failed to deserialize");
-        let Node::A_Const(ConstValue::Null) = null_const else {
-            panic!("Expected A_Const node: {:#?}", null_const);
-        };
+        assert_eq!(
+            null_const,
+            Node::A_Const {
+                val: Box::new(ConstValue::Null),
+                location: 323,
+            }
+        );
 
         let ival_json = "{ \"A_Const\": { \"ival\": { \"ival\": 1 }, \"location\": 123 } }";
         let ival_const: Node = serde_json::from_str(ival_json).expect("Failed to deserialize");
-        let Node::A_Const(ConstValue::Integer(val)) = ival_const else {
-            panic!("Expected A_Const node: {:#?}", ival_const);
-        };
-        assert_eq!(val, 1);
+        assert_eq!(
+            ival_const,
+            Node::A_Const {
+                val: Box::new(ConstValue::Integer(1)),
+                location: 123,
+            }
+        );
     }
 
     #[test]
@@ -429,8 +640,12 @@ mod tests {
         // We should keep an eye on this as 0 could be different than absence of data in the future.
         let json = "{ \"A_Const\": { \"ival\": {}, \"location\": 38 } }";
         let node: Node = serde_json::from_str(json).unwrap();
-        assert!(
-            matches!(node, Node::A_Const(ConstValue::Integer(0))),
+        assert_eq!(
+            node,
+            Node::A_Const {
+                val: Box::new(ConstValue::Integer(0)),
+                location: 38
+            },
             "Expected integer constant to default to 0"
         );
     }
@@ -447,6 +662,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_can_borrow_strings() {
+        use crate::ast::borrowed;
+        use std::borrow::Cow;
+
+        #[derive(Deserialize)]
+        struct Test<'a> {
+            #[serde(
+                borrow,
+                deserialize_with = "crate::serde::deserialize_borrowed_str_opt",
+                default
+            )]
+            name: Option<Cow<'a, str>>,
+            #[serde(
+                borrow,
+                deserialize_with = "crate::serde::deserialize_borrowed_nested_string_opt",
+                default
+            )]
+            extname: Option<Cow<'a, str>>,
+        }
+        let json = "{\"name\":\"a\",\"extname\":{\"sval\":\"b\\\"c\"}}";
+        let node: Test = serde_json::from_str(json).unwrap();
+        assert!(matches!(node.name, Some(Cow::Borrowed("a"))));
+        assert!(matches!(node.extname, Some(Cow::Owned(ref value)) if value == "b\"c"));
+
+        let json = "{}";
+        let node: Test = serde_json::from_str(json).unwrap();
+        assert_eq!(node.name, None);
+        assert_eq!(node.extname, None);
+
+        let json = "{ \"sval\": { \"sval\": \"hello\" }, \"location\": 253 }";
+        let value: borrowed::ConstValue = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            value,
+            borrowed::ConstValue::String(Cow::Borrowed("hello"))
+        ));
+    }
+
     #[test]
     fn it_can_parse_directly_nested_strings() {
         #[derive(Deserialize)]
@@ -465,4 +718,27 @@ mod tests {
         let node: Test = serde_json::from_str(json).unwrap();
         assert_eq!(node.extname, None);
     }
+
+    #[test]
+    fn it_can_serialize_nodes() {
+        use crate::ast::borrowed;
+
+        let tests = [
+            "{\"A_Const\":{\"ival\":{\"ival\":10},\"location\":253}}",
+            "{\"A_Const\":{\"ival\":{},\"location\":38}}",
+            "{\"A_Const\":{\"boolval\":{}}}",
+            "{\"A_Const\":{\"sval\":{\"sval\":\"say \\\"hi\\\"\"},\"location\":7}}",
+            "{\"A_Const\":{\"isnull\":true,\"location\":323}}",
+            "{\"Integer\":{}}",
+            "{\"ColumnRef\":{\"fields\":[{\"String\":{\"sval\":\"a\"}},{\"A_Star\":{}}],\"location\":7}}",
+            "{\"RangeVar\":{\"relname\":\"t\",\"inh\":true,\"relpersistence\":\"p\",\"location\":-1}}",
+            "{\"SelectStmt\":{\"distinctClause\":[{}],\"targetList\":[{\"ResTarget\":{\"val\":{\"ColumnRef\":{\"fields\":[{\"String\":{\"sval\":\"a\"}}],\"location\":16}},\"location\":16}}],\"limitOption\":\"LIMIT_OPTION_DEFAULT\",\"op\":\"SETOP_NONE\"}}",
+        ];
+        for json in tests {
+            let node: Node = serde_json::from_str(json).unwrap();
+            assert_eq!(serde_json::to_string(&node).unwrap(), json);
+            let node: borrowed::Node = serde_json::from_str(json).unwrap();
+            assert_eq!(serde_json::to_string(&node).unwrap(), json);
+        }
+    }
 }
@@ -83,7 +83,7 @@ impl SqlBuilder for Node {
     fn build(&self, buffer: &mut String) -> Result<(), SqlError> {
         match self {
             Node::A_ArrayExpr(a_array_expr) => a_array_expr.build(buffer)?,
-            Node::A_Const(constant) => constant.build(buffer)?,
+            Node::A_Const { val, .. } => val.build(buffer)?,
             Node::A_Expr(a_expr) => a_expr.build_with_context(buffer, Context::None)?,
             Node::A_Indices(a_indices) => a_indices.build(buffer)?,
             Node::A_Indirection(a_indirection) => a_indirection.build(buffer)?,
@@ -313,6 +313,9 @@ impl SqlBuilder for Node {
             | Node::Float { .. }
             | Node::Integer { .. }
             | Node::String { .. } => SqlValue(self).build_with_context(buffer, Context::None)?,
+            Node::Unknown { name, .. } => {
+                return Err(SqlError::Unsupported(format!("Unknown node: {}", name)));
+            }
 
             Node::AlterDatabaseRefreshCollStmt(stmt) => unsupported!(stmt),
             Node::CTECycleClause(clause) => unsupported!(clause),
@@ -712,7 +712,7 @@ impl SqlBuilder for Expr<'_> {
             Node::FuncCall(inner) => inner.build(buffer)?,
             Node::XmlExpr(inner) => inner.build(buffer)?,
             Node::TypeCast(inner) => inner.build(buffer)?,
-            Node::A_Const(inner) => inner.build(buffer)?,
+            Node::A_Const { val, .. } => val.build(buffer)?,
             Node::ColumnRef(inner) => inner.build(buffer)?,
             Node::A_Expr(inner) => inner.build_with_context(buffer, Context::None)?,
             Node::CaseExpr(inner) => inner.build(buffer)?,
@@ -879,7 +879,7 @@ impl SqlBuilder for VarList<'_> {
                 buffer.push_str(", ");
             }
             match node {
-                Node::A_Const(inner) => inner.build_with_context(buffer, Context::Identifier)?,
+                Node::A_Const { val, .. } => val.build_with_context(buffer, Context::Identifier)?,
                 Node::ParamRef(param) => param.build(buffer)?,
                 Node::Integer { ival: Some(value) } => buffer.push_str(&format!("{}", *value)),
                 Node::Float { fval: Some(value) } => buffer.push_str(value),
@@ -1,4 +1,5 @@
 use crate::ast::Node;
+use crate::ast::codes::Persistence;
 use crate::str::SqlError;
 
 macro_rules! must {
@@ -21,7 +22,7 @@ macro_rules! node {
 macro_rules! const_integer {
     ($expr:expr) => {
         match &$expr {
-            Node::A_Const(value) => match &value {
+            Node::A_Const { val: value, .. } => match &**value {
                 crate::ast::ConstValue::Integer(value) => value,
                 unexpected => return Err(SqlError::UnexpectedConstValue(unexpected.name())),
             },
@@ -33,7 +34,7 @@ macro_rules! const_integer {
 macro_rules! const_string {
     ($expr:expr) => {
         match &$expr {
-            Node::A_Const(value) => match &value {
+            Node::A_Const { val: value, .. } => match &**value {
                 crate::ast::ConstValue::String(value) => value,
                 unexpected => return Err(SqlError::UnexpectedConstValue(unexpected.name())),
             },
@@ -123,13 +124,11 @@ pub(in crate::str) fn quote_identifier(ident: &str) -> String {
 }
 
 pub(in crate::str) fn persistence_from_code(code: char) -> Option<&'static str> {
-    match code {
-        // Regular table
-        'p' => None,
-        'u' => Some("UNLOGGED"),
-        't' => Some("TEMPORARY"),
-        // Just ignore rather than error
-        _ => None,
+    // Unknown codes are ignored rather than treated as an error
+    match Persistence::from_code(code)? {
+        Persistence::Permanent => None,
+        Persistence::Unlogged => Some("UNLOGGED"),
+        Persistence::Temporary => Some("TEMPORARY"),
     }
 }
 
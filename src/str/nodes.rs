@@ -1,3 +1,4 @@
+use crate::ast::codes::{ForeignKeyAction, ForeignKeyMatchType, GeneratedWhen, ReplicaIdentity};
 use crate::ast::*;
 use crate::str::ext::*;
 use crate::str::helpers::*;
@@ -1354,13 +1355,13 @@ impl SqlBuilder for Constraint {
             }
             ConstrType::CONSTR_IDENTITY => {
                 buffer.push_str("GENERATED ");
-                match self.generated_when {
-                    constants::ATTRIBUTE_IDENTITY_ALWAYS => buffer.push_str("ALWAYS "),
-                    constants::ATTRIBUTE_IDENTITY_BY_DEFAULT => buffer.push_str("BY DEFAULT "),
-                    unexpected => {
+                match self.generated_when_kind() {
+                    Some(GeneratedWhen::Always) => buffer.push_str("ALWAYS "),
+                    Some(GeneratedWhen::ByDefault) => buffer.push_str("BY DEFAULT "),
+                    None => {
                         return Err(SqlError::Unsupported(format!(
                             "Unexpected attribute identity: {}",
-                            unexpected
+                            self.generated_when
                         )));
                     }
                 }
@@ -1371,7 +1372,7 @@ impl SqlBuilder for Constraint {
                 }
             }
             ConstrType::CONSTR_GENERATED => {
-                if self.generated_when != constants::ATTRIBUTE_IDENTITY_ALWAYS {
+                if self.generated_when_kind() != Some(GeneratedWhen::Always) {
                     return Err(SqlError::Unsupported(format!(
                         "Unexpected attribute identity: {}",
                         self.generated_when
@@ -1473,33 +1474,33 @@ impl SqlBuilder for Constraint {
         }
 
         // Index match types
-        match self.fk_matchtype {
-            constants::FKCONSTR_MATCH_SIMPLE => {} // Default
-            constants::FKCONSTR_MATCH_FULL => buffer.push_str(" MATCH FULL"),
-            constants::FKCONSTR_MATCH_PARTIAL => {
+        match self.fk_match_type() {
+            Some(ForeignKeyMatchType::Simple) => {} // Default
+            Some(ForeignKeyMatchType::Full) => buffer.push_str(" MATCH FULL"),
+            Some(ForeignKeyMatchType::Partial) => {
                 return Err(SqlError::Unsupported("Not implemented in Postgres".into()));
             }
-            _ => {} // Not specified
+            None => {} // Not specified
         }
 
         // Update action
-        match self.fk_upd_action {
-            constants::FKCONSTR_ACTION_NOACTION => {} // Default
-            constants::FKCONSTR_ACTION_RESTRICT => buffer.push_str(" ON UPDATE RESTRICT"),
-            constants::FKCONSTR_ACTION_CASCADE => buffer.push_str(" ON UPDATE CASCADE"),
-            constants::FKCONSTR_ACTION_SETNULL => buffer.push_str(" ON UPDATE SET NULL"),
-            constants::FKCONSTR_ACTION_SETDEFAULT => buffer.push_str(" ON UPDATE SET DEFAULT"),
-            _ => {} // Not specified
+        match self.fk_update_action() {
+            Some(ForeignKeyAction::NoAction) => {} // Default
+            Some(ForeignKeyAction::Restrict) => buffer.push_str(" ON UPDATE RESTRICT"),
+            Some(ForeignKeyAction::Cascade) => buffer.push_str(" ON UPDATE CASCADE"),
+            Some(ForeignKeyAction::SetNull) => buffer.push_str(" ON UPDATE SET NULL"),
+            Some(ForeignKeyAction::SetDefault) => buffer.push_str(" ON UPDATE SET DEFAULT"),
+            None => {} // Not specified
         }
 
         // Delete action
-        match self.fk_del_action {
-            constants::FKCONSTR_ACTION_NOACTION => {} // Default
-            constants::FKCONSTR_ACTION_RESTRICT => buffer.push_str(" ON DELETE RESTRICT"),
-            constants::FKCONSTR_ACTION_CASCADE => buffer.push_str(" ON DELETE CASCADE"),
-            constants::FKCONSTR_ACTION_SETNULL => buffer.push_str(" ON DELETE SET NULL"),
-            constants::FKCONSTR_ACTION_SETDEFAULT => buffer.push_str(" ON DELETE SET DEFAULT"),
-            _ => {} // Not specified
+        match self.fk_delete_action() {
+            Some(ForeignKeyAction::NoAction) => {} // Default
+            Some(ForeignKeyAction::Restrict) => buffer.push_str(" ON DELETE RESTRICT"),
+            Some(ForeignKeyAction::Cascade) => buffer.push_str(" ON DELETE CASCADE"),
+            Some(ForeignKeyAction::SetNull) => buffer.push_str(" ON DELETE SET NULL"),
+            Some(ForeignKeyAction::SetDefault) => buffer.push_str(" ON DELETE SET DEFAULT"),
+            None => {} // Not specified
         }
 
         // Includes
@@ -3923,16 +3924,16 @@ impl SqlBuilder for RenameStmt {
 
 impl SqlBuilder for ReplicaIdentityStmt {
     fn build(&self, buffer: &mut String) -> Result<(), SqlError> {
-        match self.identity_type {
-            constants::REPLICA_IDENTITY_NOTHING => buffer.push_str("NOTHING"),
-            constants::REPLICA_IDENTITY_FULL => buffer.push_str("FULL"),
-            constants::REPLICA_IDENTITY_DEFAULT => buffer.push_str("DEFAULT"),
-            constants::REPLICA_IDENTITY_INDEX => {
+        match self.replica_identity() {
+            Some(ReplicaIdentity::Nothing) => buffer.push_str("NOTHING"),
+            Some(ReplicaIdentity::Full) => buffer.push_str("FULL"),
+            Some(ReplicaIdentity::Default) => buffer.push_str("DEFAULT"),
+            Some(ReplicaIdentity::Index) => {
                 let name = must!(self.name);
                 buffer.push_str("USING INDEX ");
                 buffer.push_str(&quote_identifier(name));
             }
-            _ => {}
+            None => {}
         }
         Ok(())
     }
@@ -4154,8 +4155,8 @@ impl SqlBuilder for SelectStmt {
                 }
             };
 
-            let all = if let Node::A_Const(ref value) = **limit {
-                matches!(value, ConstValue::Null)
+            let all = if let Node::A_Const { ref val, .. } = **limit {
+                matches!(**val, ConstValue::Null)
             } else {
                 false
             };
@@ -4358,7 +4359,7 @@ impl SqlBuilder for TypeCast {
                 return Ok(());
             }
 
-            Node::A_Const(value) => {
+            Node::A_Const { val: value, .. } => {
                 let names = must!(type_name.names);
                 let names = node_vec_to_string_vec(names);
                 if names.len() == 2 && names[0].eq("pg_catalog") {
@@ -4527,7 +4528,7 @@ impl SqlBuilder for TypeName {
                     buffer.push_str(", ");
                 }
                 match typ {
-                    Node::A_Const(val) => val.build(buffer)?,
+                    Node::A_Const { val, .. } => val.build(buffer)?,
                     Node::ParamRef(param_ref) => param_ref.build(buffer)?,
                     Node::ColumnRef(column_ref) => column_ref.build(buffer)?,
                     ty => return Err(SqlError::UnexpectedNodeType(ty.name())),
@@ -5468,8 +5469,8 @@ impl SqlBuilder for XmlExpr {
                     .next()
                     .ok_or_else(|| SqlError::Missing("Missing element (2)".into()))?;
                 match arg {
-                    Node::A_Const(value) => {
-                        if let ConstValue::Null = value {
+                    Node::A_Const { val, .. } => {
+                        if let ConstValue::Null = **val {
                             buffer.push_str("NO VALUE");
                         } else {
                             Expr(arg).build(buffer)?;
@@ -0,0 +1,163 @@
+use std::ops::Range;
+
+use crate::error::{Error, Result};
+use crate::protobuf::{Reader, WireType};
+
+include!(concat!(env!("OUT_DIR"), "/tokens.rs"));
+
+/// The category of a keyword, which determines where it may be used as an identifier.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum KeywordKind {
+    /// The token is not a keyword.
+    NoKeyword,
+    /// A keyword that may be used as any kind of identifier.
+    Unreserved,
+    /// A keyword that may be used as a column name, but not as a function or type name.
+    ColName,
+    /// A keyword that may be used as a function or type name, but not as a column name.
+    TypeFuncName,
+    /// A keyword that may only be used as an identifier when quoted.
+    Reserved,
+}
+
+impl KeywordKind {
+    fn from_i32(value: i32) -> Option<KeywordKind> {
+        match value {
+            0 => Some(KeywordKind::NoKeyword),
+            1 => Some(KeywordKind::Unreserved),
+            2 => Some(KeywordKind::ColName),
+            3 => Some(KeywordKind::TypeFuncName),
+            4 => Some(KeywordKind::Reserved),
+            _ => None,
+        }
+    }
+}
+
+/// A single token produced by the PostgreSQL scanner.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ScanToken {
+    /// The byte offset at which the token starts.
+    pub start: usize,
+    /// The byte offset at which the token ends (exclusive).
+    pub end: usize,
+    /// The type of token.
+    pub token: Token,
+    /// The keyword category, if the token is a keyword.
+    pub keyword_kind: KeywordKind,
+}
+
+impl ScanToken {
+    /// The byte range of the token within the input.
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Whether the token is a keyword of any category.
+    pub fn is_keyword(&self) -> bool {
+        self.keyword_kind != KeywordKind::NoKeyword
+    }
+}
+
+/// Decodes a `ScanResult` protobuf message into the list of tokens.
+pub(crate) fn decode_scan_result(data: &[u8]) -> Result<Vec<ScanToken>> {
+    let mut reader = Reader::new(data);
+    let mut tokens = Vec::new();
+    while !reader.is_empty() {
+        match reader.read_key()? {
+            (2, WireType::LengthDelimited) => {
+                tokens.push(decode_scan_token(reader.read_bytes()?)?);
+            }
+            (_, wire_type) => reader.skip(wire_type)?,
+        }
+    }
+    Ok(tokens)
+}
+
+fn decode_scan_token(data: &[u8]) -> Result<ScanToken> {
+    let mut reader = Reader::new(data);
+    let mut start = 0;
+    let mut end = 0;
+    let mut token = 0;
+    let mut keyword_kind = 0;
+    while !reader.is_empty() {
+        match reader.read_key()? {
+            (1, WireType::Varint) => start = reader.read_int32()?,
+            (2, WireType::Varint) => end = reader.read_int32()?,
+            (4, WireType::Varint) => token = reader.read_int32()?,
+            (5, WireType::Varint) => keyword_kind = reader.read_int32()?,
+            (_, wire_type) => reader.skip(wire_type)?,
+        }
+    }
+    Ok(ScanToken {
+        start: start.max(0) as usize,
+        end: end.max(0) as usize,
+        token: Token::from_i32(token)
+            .ok_or_else(|| Error::InvalidProtobuf(format!("unknown token {}", token)))?,
+        keyword_kind: KeywordKind::from_i32(keyword_kind).ok_or_else(|| {
+            Error::InvalidProtobuf(format!("unknown keyword kind {}", keyword_kind))
+        })?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varint(mut value: u64, out: &mut Vec<u8>) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn token(start: u64, end: u64, token: Token, keyword_kind: u64) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (field, value) in [(1, start), (2, end), (4, token as u64), (5, keyword_kind)] {
+            if value != 0 {
+                varint(field << 3, &mut out);
+                varint(value, &mut out);
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn it_can_decode_a_scan_result() {
+        let mut data = Vec::new();
+        varint(1 << 3, &mut data);
+        varint(170004, &mut data);
+        for message in [token(0, 6, Token::SELECT, 4), token(7, 8, Token::ICONST, 0)] {
+            varint(2 << 3 | 2, &mut data);
+            varint(message.len() as u64, &mut data);
+            data.extend(message);
+        }
+
+        let tokens = decode_scan_result(&data).unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                ScanToken {
+                    start: 0,
+                    end: 6,
+                    token: Token::SELECT,
+                    keyword_kind: KeywordKind::Reserved,
+                },
+                ScanToken {
+                    start: 7,
+                    end: 8,
+                    token: Token::ICONST,
+                    keyword_kind: KeywordKind::NoKeyword,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_rejects_truncated_input() {
+        assert!(matches!(
+            decode_scan_result(&[0x12, 0x05, 0x08]),
+            Err(Error::InvalidProtobuf(_))
+        ));
+    }
+}
@@ -0,0 +1,104 @@
+use pg_parse::ast::structural::StructuralHash;
+use pg_parse::ast::{Alias, Node, RangeVar, SelectStmt, SetOperation};
+
+mod common;
+use common::strip_locations;
+
+fn build_select() -> SelectStmt {
+    let contacts = RangeVar::builder()
+        .relname("contacts")
+        .inh(true)
+        .relpersistence('p')
+        .alias(Alias::builder().aliasname("c"));
+    SelectStmt::builder()
+        .target_as(Node::column_ref("c", "name"), "n")
+        .target(Node::star())
+        .from(contacts)
+        .where_(Node::op(
+            "=",
+            Node::column_ref("c", "id"),
+            Node::integer_const(1),
+        ))
+        .where_(Node::op(
+            "=",
+            Node::column_ref("c", "active"),
+            Node::bool_const(true),
+        ))
+        .order_by(Node::column_ref("c", "name"))
+        .limit(Node::integer_const(10))
+        .build()
+}
+
+#[test]
+fn it_can_build_a_select_statement() {
+    let expected = pg_parse::parse(
+        "SELECT c.name AS n, * FROM contacts c WHERE c.id = 1 AND c.active = TRUE ORDER BY c.name LIMIT 10",
+    )
+    .unwrap();
+    assert_eq!(
+        strip_locations(&[build_select().into()]),
+        strip_locations(&expected)
+    );
+}
+
+#[test]
+fn it_defaults_to_the_same_values_as_postgres() {
+    let expected = pg_parse::parse("SELECT * FROM contacts").unwrap();
+    let stmt = Node::from(
+        SelectStmt::builder()
+            .target(Node::star())
+            .from(Node::table("contacts")),
+    );
+    assert!(stmt.eq_ignoring_locations(&expected[0]));
+
+    // Fields which aren't set keep the values PostgreSQL uses for a new node, with locations unknown
+    let select = SelectStmt::default();
+    assert_eq!(*select.op, SetOperation::SETOP_NONE);
+    assert!(!select.all);
+    assert_eq!(RangeVar::default().location, -1);
+    assert_eq!(Node::table("contacts").location(), None);
+    assert_eq!(Node::integer_const(1).location(), None);
+}
+
+#[test]
+fn it_extends_an_existing_where_clause() {
+    let select = SelectStmt::builder()
+        .target(Node::star())
+        .from(Node::table("t"))
+        .where_(Node::op("=", Node::column("a"), Node::integer_const(1)))
+        .where_(Node::op("=", Node::column("b"), Node::integer_const(2)))
+        .where_(Node::op("=", Node::column("c"), Node::integer_const(3)))
+        .build();
+    let Some(Node::BoolExpr(clause)) = select.where_clause.as_deref() else {
+        panic!("Unexpected where clause {:#?}", select.where_clause);
+    };
+    assert_eq!(clause.args.as_ref().map(Vec::len), Some(3));
+
+    let expected = pg_parse::parse("SELECT * FROM t WHERE a = 1 AND b = 2 AND c = 3").unwrap();
+    assert!(Node::from(select).eq_ignoring_locations(&expected[0]));
+}
+
+#[cfg(feature = "str")]
+#[test]
+fn it_can_convert_a_built_statement_to_string() {
+    let stmt = Node::from(build_select());
+    assert_eq!(
+        stmt.to_string(),
+        "SELECT c.name AS n, * FROM contacts c WHERE c.id = 1 AND c.active = TRUE ORDER BY c.name LIMIT 10"
+    );
+
+    let stmt = Node::from(
+        SelectStmt::builder()
+            .target(Node::string_const("Paul"))
+            .target(Node::null_const())
+            .from(Node::table("contacts"))
+            .where_(Node::or([
+                Node::op(">", Node::column("age"), Node::integer_const(18)),
+                Node::op("=", Node::column("name"), Node::string_const("O'Brien")),
+            ])),
+    );
+    assert_eq!(
+        stmt.to_string(),
+        "SELECT 'Paul', NULL FROM contacts WHERE age > 18 OR name = 'O''Brien'"
+    );
+}
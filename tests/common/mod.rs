@@ -0,0 +1,8 @@
+use pg_parse::ast::Node;
+use regex::Regex;
+
+/// Formats the tree, stripping out the location of each node.
+pub fn strip_locations(tree: &[Node]) -> String {
+    let location = Regex::new(r",\slocation:\s(-)?[\d]+").unwrap();
+    location.replace_all(&format!("{:?}", tree), "").to_string()
+}
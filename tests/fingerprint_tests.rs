@@ -10,8 +10,13 @@ fn it_can_fingerprint_a_simple_statement() {
 fn it_will_error_on_invalid_input() {
     let result = pg_parse::fingerprint("CREATE RANDOM ix_test ON contacts.person;");
     assert!(result.is_err());
+    let error = result.err().unwrap();
     assert_eq!(
-        result.err().unwrap(),
-        pg_parse::Error::ParseError("syntax error at or near \"RANDOM\"".into())
+        error.to_string(),
+        "Parse Error: syntax error at or near \"RANDOM\""
     );
+    let pg_parse::Error::ParseError(error) = error else {
+        panic!("Expected a parse error: {:?}", error);
+    };
+    assert_eq!(error.message, "syntax error at or near \"RANDOM\"");
 }
@@ -1,4 +1,6 @@
-use pg_parse::ast::{ConstValue, ConstrType, InsertStmt, List, Node, ParamRef, SelectStmt};
+use pg_parse::ast::raw::RawVisit;
+use pg_parse::ast::{ConstValue, ConstrType, List, Node, ParamRef, SelectStmt};
+use pg_parse::{ParseOptions, Position};
 
 #[test]
 fn it_can_generate_a_create_index_ast() {
@@ -75,14 +77,14 @@ fn it_can_generate_a_create_table_ast() {
                 None => panic!("No type mods found"),
             };
             assert_eq!(mods.len(), 2, "Mods length");
-            match &mods[0] {
-                Node::A_Const(ConstValue::Integer(value)) => {
+            match mods[0].as_a_const() {
+                Some(ConstValue::Integer(value)) => {
                     assert_eq!(*value, 5);
                 }
                 unexpected => panic!("Unexpected type for mods[0] {:?}", unexpected),
             }
-            match &mods[1] {
-                Node::A_Const(ConstValue::Integer(value)) => {
+            match mods[1].as_a_const() {
+                Some(ConstValue::Integer(value)) => {
                     assert_eq!(*value, 12);
                 }
                 unexpected => panic!("Unexpected type for mods[0] {:?}", unexpected),
@@ -96,10 +98,160 @@ fn it_can_generate_a_create_table_ast() {
 fn it_will_error_on_invalid_input() {
     let result = pg_parse::parse("CREATE RANDOM ix_test ON contacts.person;");
     assert!(result.is_err());
+    let error = result.err().unwrap();
     assert_eq!(
-        result.err().unwrap(),
-        pg_parse::Error::ParseError("syntax error at or near \"RANDOM\"".into())
+        error.to_string(),
+        "Parse Error: syntax error at or near \"RANDOM\""
     );
+    let pg_parse::Error::ParseError(error) = error else {
+        panic!("Expected a parse error: {:?}", error);
+    };
+    assert_eq!(error.message, "syntax error at or near \"RANDOM\"");
+    assert_eq!(
+        error.position,
+        Some(Position {
+            offset: 7,
+            line: 1,
+            byte_column: 8,
+            char_column: 8,
+            utf16_column: 8,
+        })
+    );
+    let source = error.source_location.expect("source location");
+    assert_eq!(source.function, "scanner_yyerror");
+    assert_eq!(source.file, "scan.l");
+}
+
+#[test]
+fn it_will_error_on_input_containing_nul_bytes() {
+    let result = pg_parse::parse("SELECT 1;\0 DROP TABLE contacts");
+    assert_eq!(result.unwrap_err(), pg_parse::Error::InteriorNul(9));
+    assert!(pg_parse::normalize("SELECT \0").is_err());
+    assert!(pg_parse::fingerprint(b"SELECT \0 1").is_err());
+
+    let result = pg_parse::parse_script("SELECT 1;\0 DROP TABLE contacts");
+    assert_eq!(result.len(), 1);
+    assert!(matches!(result[0], Err(pg_parse::Error::InteriorNul(9))));
+}
+
+#[test]
+fn it_can_parse_c_strings_and_bytes() {
+    let result = pg_parse::parse(c"SELECT * FROM contacts").unwrap();
+    assert!(matches!(result[0], Node::SelectStmt(_)));
+    let result = pg_parse::parse(&b"SELECT * FROM contacts"[..]).unwrap();
+    assert!(matches!(result[0], Node::SelectStmt(_)));
+
+    // Only a C string may be NUL terminated, rather than silently truncating other input
+    let result = pg_parse::parse(b"SELECT * FROM contacts\0");
+    assert_eq!(result.unwrap_err(), pg_parse::Error::InteriorNul(22));
+    let result = pg_parse::parse("SELECT * FROM contacts\0");
+    assert_eq!(result.unwrap_err(), pg_parse::Error::InteriorNul(22));
+
+    // Errors are still reported against the original input
+    let Err(pg_parse::Error::ParseError(error)) = pg_parse::parse(c"SELECT * FRO contacts") else {
+        panic!("Expected a parse error");
+    };
+    assert_eq!(error.position.unwrap().offset, 9);
+
+    // Including input which isn't valid UTF-8, where PostgreSQL counts each invalid byte as a character
+    let Err(pg_parse::Error::ParseError(error)) =
+        pg_parse::parse(b"SELECT /* \xFF */ * FRO contacts")
+    else {
+        panic!("Expected a parse error");
+    };
+    assert_eq!(error.position.unwrap().offset, 17);
+}
+
+#[test]
+fn it_reports_the_line_and_column_of_an_error() {
+    let sql = "SELECT 1;\nSELECT * FRO contacts";
+    let result = pg_parse::parse(sql);
+    let Err(pg_parse::Error::ParseError(error)) = result else {
+        panic!("Expected a parse error: {:?}", result);
+    };
+    assert_eq!(error.message, "syntax error at or near \"FRO\"");
+    let position = error.position.expect("position");
+    assert_eq!(position.line, 2);
+    assert_eq!(position.char_column, 10);
+    assert_eq!(&sql[position.offset..], "FRO contacts");
+}
+
+#[test]
+fn it_can_return_the_location_of_each_statement() {
+    let sql = "CREATE TABLE a (id int);\nINSERT INTO a VALUES (1);\nSELECT * FROM a";
+    let result = pg_parse::parse_statements(sql).unwrap();
+    assert_eq!(3, result.len(), "Statement count");
+
+    assert!(matches!(result[0].node, Node::CreateStmt(_)));
+    assert_eq!(result[0].location, 0);
+    assert_eq!(result[0].sql, "CREATE TABLE a (id int)");
+
+    assert!(matches!(result[1].node, Node::InsertStmt(_)));
+    assert_eq!(result[1].location, 24);
+    assert_eq!(result[1].sql.trim(), "INSERT INTO a VALUES (1)");
+
+    assert!(matches!(result[2].node, Node::SelectStmt(_)));
+    assert_eq!(result[2].sql.trim(), "SELECT * FROM a");
+    assert_eq!(result[2].location + result[2].length, sql.len());
+
+    for statement in &result {
+        assert_eq!(
+            &sql[statement.location..statement.location + statement.length],
+            statement.sql
+        );
+    }
+}
+
+#[test]
+fn it_can_clone_and_compare_trees() {
+    let tree = pg_parse::parse("SELECT a, b FROM contacts WHERE id = 1").unwrap();
+    let copy = tree.clone();
+    assert_eq!(tree, copy);
+    assert_eq!(
+        tree,
+        pg_parse::parse("SELECT a, b FROM contacts WHERE id = 1").unwrap()
+    );
+    assert_ne!(
+        tree,
+        pg_parse::parse("SELECT a, b FROM contacts WHERE id = 2").unwrap()
+    );
+
+    // Trees can also be used as keys
+    let mut seen = std::collections::HashSet::new();
+    assert!(seen.insert(tree[0].clone()));
+    assert!(!seen.insert(copy[0].clone()));
+}
+
+#[test]
+fn it_can_parse_a_script_with_invalid_statements() {
+    let sql = "CREATE TABLE a (id int);\nINSERT INTO a VALUES (1));\nSELECT name FROM a";
+    let result = pg_parse::parse_script(sql);
+    assert_eq!(3, result.len(), "Statement count");
+
+    let create = result[0].as_ref().unwrap();
+    assert!(matches!(create.node, Node::CreateStmt(_)));
+    assert_eq!(create.sql, "CREATE TABLE a (id int)");
+
+    let Err(pg_parse::Error::ParseError(error)) = &result[1] else {
+        panic!("Expected a parse error: {:?}", result[1]);
+    };
+    assert_eq!(error.message, "syntax error at or near \")\"");
+    let position = error.position.unwrap();
+    assert_eq!(position.line, 2);
+    assert_eq!(position.char_column, 25);
+    assert_eq!(&sql[position.offset..], ");\nSELECT name FROM a");
+
+    // Locations within the tree are relative to the script
+    let select = result[2].as_ref().unwrap();
+    assert_eq!(select.location, 51);
+    assert_eq!(select.sql.trim(), "SELECT name FROM a");
+    let Node::SelectStmt(stmt) = &select.node else {
+        panic!("Expected a select statement");
+    };
+    let Node::ResTarget(target) = &stmt.target_list.as_ref().unwrap()[0] else {
+        panic!("Expected a target");
+    };
+    assert_eq!(&sql[target.location as usize..][..4], "name");
 }
 
 #[test]
@@ -110,15 +262,9 @@ fn it_can_parse_lists_of_values() {
     let el: &Node = &result[0];
 
     match el {
-        Node::InsertStmt(InsertStmt {
-            select_stmt: Some(select_stmt),
-            ..
-        }) => match select_stmt.as_ref() {
-            Node::SelectStmt(SelectStmt {
-                values_lists: Some(values_lists),
-                ..
-            }) => {
-                let values = &values_lists[0];
+        Node::InsertStmt(stmt) => match stmt.select_stmt.as_deref() {
+            Some(Node::SelectStmt(stmt)) => {
+                let values = &stmt.values_lists.as_ref().expect("values lists")[0];
 
                 match values {
                     Node::List(List { items }) => {
@@ -193,8 +339,8 @@ fn it_can_parse_a_table_of_defaults() {
             assert_eq!(*c2.contype, ConstrType::CONSTR_DEFAULT);
             assert!(c2.raw_expr.is_some());
             let raw_expr = c2.raw_expr.as_ref().unwrap();
-            let value = match **raw_expr {
-                Node::A_Const(ref value) => value,
+            let value = match raw_expr.as_a_const() {
+                Some(val) => val,
                 _ => panic!("Expected constant value"),
             };
             assert_eq!(*value, ConstValue::Null, "Expected NULL");
@@ -203,6 +349,154 @@ fn it_can_parse_a_table_of_defaults() {
     }
 }
 
+#[test]
+fn it_can_parse_a_type_name() {
+    let type_name = pg_parse::parse_type_name("numeric(10,2)[]").unwrap();
+    let names = type_name.names.as_ref().expect("names");
+    assert_eq!(
+        names
+            .iter()
+            .map(|name| match name {
+                Node::String { sval } => sval.as_deref().unwrap(),
+                node => panic!("Unexpected type {:#?}", &node),
+            })
+            .collect::<Vec<_>>(),
+        vec!["pg_catalog", "numeric"]
+    );
+    let typmods = type_name.typmods.as_ref().expect("typmods");
+    assert_eq!(typmods[0].as_a_const(), Some(&ConstValue::Integer(10)));
+    assert_eq!(typmods[1].as_a_const(), Some(&ConstValue::Integer(2)));
+    assert_eq!(type_name.array_bounds.as_ref().expect("bounds").len(), 1);
+
+    assert!(pg_parse::parse_type_name("numeric numeric").is_err());
+}
+
+#[test]
+fn it_can_parse_an_expression() {
+    let expr = pg_parse::parse_expression("a + b * 2").unwrap();
+    let Node::A_Expr(expr) = expr else {
+        panic!("Unexpected type {:#?}", &expr);
+    };
+    assert!(matches!(
+        &expr.name.as_ref().unwrap()[0],
+        Node::String { sval: Some(op) } if op == "+"
+    ));
+    assert!(matches!(expr.lexpr.as_deref(), Some(Node::ColumnRef(_))));
+    assert!(matches!(expr.rexpr.as_deref(), Some(Node::A_Expr(_))));
+
+    let expr = pg_parse::parse_expression("price > 0 AND discount <= price").unwrap();
+    assert!(matches!(expr, Node::BoolExpr(_)));
+
+    // Statements which parse, but aren't a single expression
+    for invalid in ["a, b", "a AS b", "a FROM t"] {
+        assert!(
+            matches!(
+                pg_parse::parse_expression(invalid),
+                Err(pg_parse::Error::InvalidAst(_))
+            ),
+            "Expected {} to fail",
+            invalid
+        );
+    }
+    assert!(matches!(
+        pg_parse::parse_expression("a +"),
+        Err(pg_parse::Error::ParseError(_))
+    ));
+}
+
+#[test]
+fn it_can_parse_deeply_nested_expressions() {
+    let terms = 2000;
+    let sql = format!("SELECT {}", vec!["1"; terms].join(" + "));
+    let result = pg_parse::parse(&sql).unwrap();
+    let Node::SelectStmt(select) = &result[0] else {
+        panic!("Unexpected type {:#?}", &result[0]);
+    };
+    let Node::ResTarget(target) = &select.target_list.as_ref().unwrap()[0] else {
+        panic!("Expected a target");
+    };
+
+    // Addition is left associative, so each term is nested within the left hand side
+    let mut depth = 0;
+    let mut node = target.val.as_deref().unwrap();
+    while let Node::A_Expr(expr) = node {
+        node = expr.lexpr.as_deref().unwrap();
+        depth += 1;
+    }
+    assert_eq!(depth, terms - 1);
+}
+
+#[test]
+fn it_can_parse_deeply_nested_subqueries() {
+    let levels = 200;
+    let sql = format!(
+        "SELECT {}1{}",
+        "(SELECT ".repeat(levels),
+        ")".repeat(levels)
+    );
+    let result = pg_parse::parse(&sql).unwrap();
+    assert!(matches!(result[0], Node::SelectStmt(_)));
+}
+
+#[test]
+fn it_can_limit_the_depth_of_the_parse_tree() {
+    let sql = format!("SELECT {}", vec!["1"; 500].join(" + "));
+    let options = ParseOptions {
+        max_depth: Some(100),
+        ..ParseOptions::default()
+    };
+    let result = pg_parse::parse_with_options(&sql, &options);
+    assert_eq!(
+        result.unwrap_err(),
+        pg_parse::Error::DepthLimitExceeded(100)
+    );
+
+    let options = ParseOptions {
+        max_depth: Some(10_000),
+        ..ParseOptions::default()
+    };
+    assert!(pg_parse::parse_with_options(&sql, &options).is_ok());
+}
+
+#[test]
+fn it_can_parse_a_borrowed_tree() {
+    use pg_parse::ast::borrowed;
+    use std::borrow::Cow;
+
+    let tree = pg_parse::parse_borrowed("SELECT name, 'say \"hi\"' FROM contacts").unwrap();
+    let statements = tree.statements().unwrap();
+    let borrowed::Node::SelectStmt(stmt) = &statements[0] else {
+        panic!("Unexpected type {:#?}", &statements[0]);
+    };
+    let targets = stmt.target_list.as_ref().unwrap();
+
+    // Identifiers are borrowed from the parse tree
+    let borrowed::Node::ResTarget(target) = &targets[0] else {
+        panic!("Expected a target");
+    };
+    let Some(borrowed::Node::ColumnRef(column)) = target.val.as_deref() else {
+        panic!("Expected a column");
+    };
+    assert!(matches!(
+        column.fields.as_ref().unwrap()[0],
+        borrowed::Node::String {
+            sval: Some(Cow::Borrowed("name"))
+        }
+    ));
+
+    // Whereas strings which are escaped within the JSON need to be copied
+    let borrowed::Node::ResTarget(target) = &targets[1] else {
+        panic!("Expected a target");
+    };
+    let Some(borrowed::Node::A_Const { val, .. }) = target.val.as_deref() else {
+        panic!("Expected a constant: {:?}", target.val);
+    };
+    let borrowed::ConstValue::String(Cow::Owned(value)) = &**val else {
+        panic!("Expected an owned string: {:?}", val);
+    };
+    assert_eq!(value, "say \"hi\"");
+}
+
 #[test]
 fn it_can_parse_tests() {
     // This is a set of tests inspired by libpg_query that test various situations. The scenario that
@@ -211,23 +505,23 @@ fn it_can_parse_tests() {
     const TESTS: [(&str, &str); 26] = [
         (
             "SELECT 1",
-            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(A_Const(Integer(1))), location: 7 })]), from_clause: None, where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })]",
+            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(A_Const { val: Integer(1), location: 7 }), location: 7 })]), from_clause: None, where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })]",
         ),
         (
             "SELECT 1; SELECT 2",
-            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(A_Const(Integer(1))), location: 7 })]), from_clause: None, where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None }), SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(A_Const(Integer(2))), location: 17 })]), from_clause: None, where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })]",
+            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(A_Const { val: Integer(1), location: 7 }), location: 7 })]), from_clause: None, where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None }), SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(A_Const { val: Integer(2), location: 17 }), location: 17 })]), from_clause: None, where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })]",
         ),
         (
             "select sum(unique1) FILTER (WHERE unique1 IN (SELECT unique1 FROM onek where unique1 < 100)) FROM tenk1",
-            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(FuncCall(FuncCall { funcname: Some([String { sval: Some(\"sum\") }]), args: Some([ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"unique1\") }]), location: 11 })]), agg_order: None, agg_filter: Some(SubLink(SubLink { sub_link_type: ANY_SUBLINK, sub_link_id: 0, testexpr: Some(ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"unique1\") }]), location: 34 })), oper_name: None, subselect: Some(SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"unique1\") }]), location: 53 })), location: 53 })]), from_clause: Some([RangeVar(RangeVar { catalogname: None, schemaname: None, relname: Some(\"onek\"), inh: true, relpersistence: 'p', alias: None, location: 66 })]), where_clause: Some(A_Expr(A_Expr { kind: AEXPR_OP, name: Some([String { sval: Some(\"<\") }]), lexpr: Some(ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"unique1\") }]), location: 77 })), rexpr: Some(A_Const(Integer(100))), location: 85 })), group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })), location: 42 })), over: None, agg_within_group: false, agg_star: false, agg_distinct: false, func_variadic: false, funcformat: COERCE_EXPLICIT_CALL, location: 7 })), location: 7 })]), from_clause: Some([RangeVar(RangeVar { catalogname: None, schemaname: None, relname: Some(\"tenk1\"), inh: true, relpersistence: 'p', alias: None, location: 98 })]), where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })]",
+            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(FuncCall(FuncCall { funcname: Some([String { sval: Some(\"sum\") }]), args: Some([ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"unique1\") }]), location: 11 })]), agg_order: None, agg_filter: Some(SubLink(SubLink { sub_link_type: ANY_SUBLINK, sub_link_id: 0, testexpr: Some(ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"unique1\") }]), location: 34 })), oper_name: None, subselect: Some(SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"unique1\") }]), location: 53 })), location: 53 })]), from_clause: Some([RangeVar(RangeVar { catalogname: None, schemaname: None, relname: Some(\"onek\"), inh: true, relpersistence: 'p', alias: None, location: 66 })]), where_clause: Some(A_Expr(A_Expr { kind: AEXPR_OP, name: Some([String { sval: Some(\"<\") }]), lexpr: Some(ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"unique1\") }]), location: 77 })), rexpr: Some(A_Const { val: Integer(100), location: 87 }), location: 85 })), group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })), location: 42 })), over: None, agg_within_group: false, agg_star: false, agg_distinct: false, func_variadic: false, funcformat: COERCE_EXPLICIT_CALL, location: 7 })), location: 7 })]), from_clause: Some([RangeVar(RangeVar { catalogname: None, schemaname: None, relname: Some(\"tenk1\"), inh: true, relpersistence: 'p', alias: None, location: 98 })]), where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })]",
         ),
         (
             "select sum(unique1) FILTER (WHERE unique1 = ANY (SELECT unique1 FROM onek where unique1 < 100)) FROM tenk1",
-            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(FuncCall(FuncCall { funcname: Some([String { sval: Some(\"sum\") }]), args: Some([ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"unique1\") }]), location: 11 })]), agg_order: None, agg_filter: Some(SubLink(SubLink { sub_link_type: ANY_SUBLINK, sub_link_id: 0, testexpr: Some(ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"unique1\") }]), location: 34 })), oper_name: Some([String { sval: Some(\"=\") }]), subselect: Some(SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"unique1\") }]), location: 56 })), location: 56 })]), from_clause: Some([RangeVar(RangeVar { catalogname: None, schemaname: None, relname: Some(\"onek\"), inh: true, relpersistence: 'p', alias: None, location: 69 })]), where_clause: Some(A_Expr(A_Expr { kind: AEXPR_OP, name: Some([String { sval: Some(\"<\") }]), lexpr: Some(ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"unique1\") }]), location: 80 })), rexpr: Some(A_Const(Integer(100))), location: 88 })), group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })), location: 42 })), over: None, agg_within_group: false, agg_star: false, agg_distinct: false, func_variadic: false, funcformat: COERCE_EXPLICIT_CALL, location: 7 })), location: 7 })]), from_clause: Some([RangeVar(RangeVar { catalogname: None, schemaname: None, relname: Some(\"tenk1\"), inh: true, relpersistence: 'p', alias: None, location: 101 })]), where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })]",
+            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(FuncCall(FuncCall { funcname: Some([String { sval: Some(\"sum\") }]), args: Some([ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"unique1\") }]), location: 11 })]), agg_order: None, agg_filter: Some(SubLink(SubLink { sub_link_type: ANY_SUBLINK, sub_link_id: 0, testexpr: Some(ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"unique1\") }]), location: 34 })), oper_name: Some([String { sval: Some(\"=\") }]), subselect: Some(SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"unique1\") }]), location: 56 })), location: 56 })]), from_clause: Some([RangeVar(RangeVar { catalogname: None, schemaname: None, relname: Some(\"onek\"), inh: true, relpersistence: 'p', alias: None, location: 69 })]), where_clause: Some(A_Expr(A_Expr { kind: AEXPR_OP, name: Some([String { sval: Some(\"<\") }]), lexpr: Some(ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"unique1\") }]), location: 80 })), rexpr: Some(A_Const { val: Integer(100), location: 90 }), location: 88 })), group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })), location: 42 })), over: None, agg_within_group: false, agg_star: false, agg_distinct: false, func_variadic: false, funcformat: COERCE_EXPLICIT_CALL, location: 7 })), location: 7 })]), from_clause: Some([RangeVar(RangeVar { catalogname: None, schemaname: None, relname: Some(\"tenk1\"), inh: true, relpersistence: 'p', alias: None, location: 101 })]), where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })]",
         ),
         (
             "CREATE FOREIGN TABLE films (code char(5) NOT NULL, title varchar(40) NOT NULL, did integer NOT NULL, date_prod date, kind varchar(10), len interval hour to minute) SERVER film_server;",
-            "[CreateForeignTableStmt(CreateForeignTableStmt { base: CreateStmt { relation: Some(RangeVar { catalogname: None, schemaname: None, relname: Some(\"films\"), inh: true, relpersistence: 'p', alias: None, location: 21 }), table_elts: Some([ColumnDef(ColumnDef { colname: Some(\"code\"), type_name: Some(TypeName { names: Some([String { sval: Some(\"pg_catalog\") }, String { sval: Some(\"bpchar\") }]), type_oid: 0, setof: false, pct_type: false, typmods: Some([A_Const(Integer(5))]), typemod: -1, array_bounds: None, location: 33 }), compression: None, inhcount: 0, is_local: true, is_not_null: false, is_from_type: false, storage: '\\0', storage_name: None, raw_default: None, cooked_default: None, identity: '\\0', identity_sequence: None, generated: '\\0', coll_clause: None, coll_oid: 0, constraints: Some([Constraint(Constraint { contype: CONSTR_NOTNULL, conname: None, deferrable: false, initdeferred: false, skip_validation: false, initially_valid: false, is_no_inherit: false, raw_expr: None, cooked_expr: None, generated_when: '\\0', inhcount: 0, nulls_not_distinct: false, keys: None, including: None, exclusions: None, options: None, indexname: None, indexspace: None, reset_default_tblspc: false, access_method: None, where_clause: None, pktable: None, fk_attrs: None, pk_attrs: None, fk_matchtype: '\\0', fk_upd_action: '\\0', fk_del_action: '\\0', fk_del_set_cols: None, old_conpfeqop: None, old_pktable_oid: 0, location: 41 })]), fdwoptions: None, location: 28 }), ColumnDef(ColumnDef { colname: Some(\"title\"), type_name: Some(TypeName { names: Some([String { sval: Some(\"pg_catalog\") }, String { sval: Some(\"varchar\") }]), type_oid: 0, setof: false, pct_type: false, typmods: Some([A_Const(Integer(40))]), typemod: -1, array_bounds: None, location: 57 }), compression: None, inhcount: 0, is_local: true, is_not_null: false, is_from_type: false, storage: '\\0', storage_name: None, raw_default: None, cooked_default: None, identity: '\\0', identity_sequence: None, generated: '\\0', coll_clause: None, coll_oid: 0, constraints: Some([Constraint(Constraint { contype: CONSTR_NOTNULL, conname: None, deferrable: false, initdeferred: false, skip_validation: false, initially_valid: false, is_no_inherit: false, raw_expr: None, cooked_expr: None, generated_when: '\\0', inhcount: 0, nulls_not_distinct: false, keys: None, including: None, exclusions: None, options: None, indexname: None, indexspace: None, reset_default_tblspc: false, access_method: None, where_clause: None, pktable: None, fk_attrs: None, pk_attrs: None, fk_matchtype: '\\0', fk_upd_action: '\\0', fk_del_action: '\\0', fk_del_set_cols: None, old_conpfeqop: None, old_pktable_oid: 0, location: 69 })]), fdwoptions: None, location: 51 }), ColumnDef(ColumnDef { colname: Some(\"did\"), type_name: Some(TypeName { names: Some([String { sval: Some(\"pg_catalog\") }, String { sval: Some(\"int4\") }]), type_oid: 0, setof: false, pct_type: false, typmods: None, typemod: -1, array_bounds: None, location: 83 }), compression: None, inhcount: 0, is_local: true, is_not_null: false, is_from_type: false, storage: '\\0', storage_name: None, raw_default: None, cooked_default: None, identity: '\\0', identity_sequence: None, generated: '\\0', coll_clause: None, coll_oid: 0, constraints: Some([Constraint(Constraint { contype: CONSTR_NOTNULL, conname: None, deferrable: false, initdeferred: false, skip_validation: false, initially_valid: false, is_no_inherit: false, raw_expr: None, cooked_expr: None, generated_when: '\\0', inhcount: 0, nulls_not_distinct: false, keys: None, including: None, exclusions: None, options: None, indexname: None, indexspace: None, reset_default_tblspc: false, access_method: None, where_clause: None, pktable: None, fk_attrs: None, pk_attrs: None, fk_matchtype: '\\0', fk_upd_action: '\\0', fk_del_action: '\\0', fk_del_set_cols: None, old_conpfeqop: None, old_pktable_oid: 0, location: 91 })]), fdwoptions: None, location: 79 }), ColumnDef(ColumnDef { colname: Some(\"date_prod\"), type_name: Some(TypeName { names: Some([String { sval: Some(\"date\") }]), type_oid: 0, setof: false, pct_type: false, typmods: None, typemod: -1, array_bounds: None, location: 111 }), compression: None, inhcount: 0, is_local: true, is_not_null: false, is_from_type: false, storage: '\\0', storage_name: None, raw_default: None, cooked_default: None, identity: '\\0', identity_sequence: None, generated: '\\0', coll_clause: None, coll_oid: 0, constraints: None, fdwoptions: None, location: 101 }), ColumnDef(ColumnDef { colname: Some(\"kind\"), type_name: Some(TypeName { names: Some([String { sval: Some(\"pg_catalog\") }, String { sval: Some(\"varchar\") }]), type_oid: 0, setof: false, pct_type: false, typmods: Some([A_Const(Integer(10))]), typemod: -1, array_bounds: None, location: 122 }), compression: None, inhcount: 0, is_local: true, is_not_null: false, is_from_type: false, storage: '\\0', storage_name: None, raw_default: None, cooked_default: None, identity: '\\0', identity_sequence: None, generated: '\\0', coll_clause: None, coll_oid: 0, constraints: None, fdwoptions: None, location: 117 }), ColumnDef(ColumnDef { colname: Some(\"len\"), type_name: Some(TypeName { names: Some([String { sval: Some(\"pg_catalog\") }, String { sval: Some(\"interval\") }]), type_oid: 0, setof: false, pct_type: false, typmods: Some([A_Const(Integer(3072))]), typemod: -1, array_bounds: None, location: 139 }), compression: None, inhcount: 0, is_local: true, is_not_null: false, is_from_type: false, storage: '\\0', storage_name: None, raw_default: None, cooked_default: None, identity: '\\0', identity_sequence: None, generated: '\\0', coll_clause: None, coll_oid: 0, constraints: None, fdwoptions: None, location: 135 })]), inh_relations: None, partbound: None, partspec: None, of_typename: None, constraints: None, options: None, oncommit: ONCOMMIT_NOOP, tablespacename: None, access_method: None, if_not_exists: false }, servername: Some(\"film_server\"), options: None })]",
+            "[CreateForeignTableStmt(CreateForeignTableStmt { base: CreateStmt { relation: Some(RangeVar { catalogname: None, schemaname: None, relname: Some(\"films\"), inh: true, relpersistence: 'p', alias: None, location: 21 }), table_elts: Some([ColumnDef(ColumnDef { colname: Some(\"code\"), type_name: Some(TypeName { names: Some([String { sval: Some(\"pg_catalog\") }, String { sval: Some(\"bpchar\") }]), type_oid: 0, setof: false, pct_type: false, typmods: Some([A_Const { val: Integer(5), location: 38 }]), typemod: -1, array_bounds: None, location: 33 }), compression: None, inhcount: 0, is_local: true, is_not_null: false, is_from_type: false, storage: '\\0', storage_name: None, raw_default: None, cooked_default: None, identity: '\\0', identity_sequence: None, generated: '\\0', coll_clause: None, coll_oid: 0, constraints: Some([Constraint(Constraint { contype: CONSTR_NOTNULL, conname: None, deferrable: false, initdeferred: false, skip_validation: false, initially_valid: false, is_no_inherit: false, raw_expr: None, cooked_expr: None, generated_when: '\\0', inhcount: 0, nulls_not_distinct: false, keys: None, including: None, exclusions: None, options: None, indexname: None, indexspace: None, reset_default_tblspc: false, access_method: None, where_clause: None, pktable: None, fk_attrs: None, pk_attrs: None, fk_matchtype: '\\0', fk_upd_action: '\\0', fk_del_action: '\\0', fk_del_set_cols: None, old_conpfeqop: None, old_pktable_oid: 0, location: 41 })]), fdwoptions: None, location: 28 }), ColumnDef(ColumnDef { colname: Some(\"title\"), type_name: Some(TypeName { names: Some([String { sval: Some(\"pg_catalog\") }, String { sval: Some(\"varchar\") }]), type_oid: 0, setof: false, pct_type: false, typmods: Some([A_Const { val: Integer(40), location: 65 }]), typemod: -1, array_bounds: None, location: 57 }), compression: None, inhcount: 0, is_local: true, is_not_null: false, is_from_type: false, storage: '\\0', storage_name: None, raw_default: None, cooked_default: None, identity: '\\0', identity_sequence: None, generated: '\\0', coll_clause: None, coll_oid: 0, constraints: Some([Constraint(Constraint { contype: CONSTR_NOTNULL, conname: None, deferrable: false, initdeferred: false, skip_validation: false, initially_valid: false, is_no_inherit: false, raw_expr: None, cooked_expr: None, generated_when: '\\0', inhcount: 0, nulls_not_distinct: false, keys: None, including: None, exclusions: None, options: None, indexname: None, indexspace: None, reset_default_tblspc: false, access_method: None, where_clause: None, pktable: None, fk_attrs: None, pk_attrs: None, fk_matchtype: '\\0', fk_upd_action: '\\0', fk_del_action: '\\0', fk_del_set_cols: None, old_conpfeqop: None, old_pktable_oid: 0, location: 69 })]), fdwoptions: None, location: 51 }), ColumnDef(ColumnDef { colname: Some(\"did\"), type_name: Some(TypeName { names: Some([String { sval: Some(\"pg_catalog\") }, String { sval: Some(\"int4\") }]), type_oid: 0, setof: false, pct_type: false, typmods: None, typemod: -1, array_bounds: None, location: 83 }), compression: None, inhcount: 0, is_local: true, is_not_null: false, is_from_type: false, storage: '\\0', storage_name: None, raw_default: None, cooked_default: None, identity: '\\0', identity_sequence: None, generated: '\\0', coll_clause: None, coll_oid: 0, constraints: Some([Constraint(Constraint { contype: CONSTR_NOTNULL, conname: None, deferrable: false, initdeferred: false, skip_validation: false, initially_valid: false, is_no_inherit: false, raw_expr: None, cooked_expr: None, generated_when: '\\0', inhcount: 0, nulls_not_distinct: false, keys: None, including: None, exclusions: None, options: None, indexname: None, indexspace: None, reset_default_tblspc: false, access_method: None, where_clause: None, pktable: None, fk_attrs: None, pk_attrs: None, fk_matchtype: '\\0', fk_upd_action: '\\0', fk_del_action: '\\0', fk_del_set_cols: None, old_conpfeqop: None, old_pktable_oid: 0, location: 91 })]), fdwoptions: None, location: 79 }), ColumnDef(ColumnDef { colname: Some(\"date_prod\"), type_name: Some(TypeName { names: Some([String { sval: Some(\"date\") }]), type_oid: 0, setof: false, pct_type: false, typmods: None, typemod: -1, array_bounds: None, location: 111 }), compression: None, inhcount: 0, is_local: true, is_not_null: false, is_from_type: false, storage: '\\0', storage_name: None, raw_default: None, cooked_default: None, identity: '\\0', identity_sequence: None, generated: '\\0', coll_clause: None, coll_oid: 0, constraints: None, fdwoptions: None, location: 101 }), ColumnDef(ColumnDef { colname: Some(\"kind\"), type_name: Some(TypeName { names: Some([String { sval: Some(\"pg_catalog\") }, String { sval: Some(\"varchar\") }]), type_oid: 0, setof: false, pct_type: false, typmods: Some([A_Const { val: Integer(10), location: 130 }]), typemod: -1, array_bounds: None, location: 122 }), compression: None, inhcount: 0, is_local: true, is_not_null: false, is_from_type: false, storage: '\\0', storage_name: None, raw_default: None, cooked_default: None, identity: '\\0', identity_sequence: None, generated: '\\0', coll_clause: None, coll_oid: 0, constraints: None, fdwoptions: None, location: 117 }), ColumnDef(ColumnDef { colname: Some(\"len\"), type_name: Some(TypeName { names: Some([String { sval: Some(\"pg_catalog\") }, String { sval: Some(\"interval\") }]), type_oid: 0, setof: false, pct_type: false, typmods: Some([A_Const { val: Integer(3072), location: 148 }]), typemod: -1, array_bounds: None, location: 139 }), compression: None, inhcount: 0, is_local: true, is_not_null: false, is_from_type: false, storage: '\\0', storage_name: None, raw_default: None, cooked_default: None, identity: '\\0', identity_sequence: None, generated: '\\0', coll_clause: None, coll_oid: 0, constraints: None, fdwoptions: None, location: 135 })]), inh_relations: None, partbound: None, partspec: None, of_typename: None, constraints: None, options: None, oncommit: ONCOMMIT_NOOP, tablespacename: None, access_method: None, if_not_exists: false }, servername: Some(\"film_server\"), options: None })]",
         ),
         (
             "CREATE FOREIGN TABLE ft1 () SERVER no_server",
@@ -244,7 +538,7 @@ fn it_can_parse_tests() {
         ),
         (
             "WITH w AS NOT MATERIALIZED (SELECT * FROM big_table) SELECT * FROM w LIMIT 1",
-            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(ColumnRef(ColumnRef { fields: Some([A_Star(A_Star)]), location: 60 })), location: 60 })]), from_clause: Some([RangeVar(RangeVar { catalogname: None, schemaname: None, relname: Some(\"w\"), inh: true, relpersistence: 'p', alias: None, location: 67 })]), where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: Some(A_Const(Integer(1))), limit_option: LIMIT_OPTION_COUNT, locking_clause: None, with_clause: Some(WithClause { ctes: Some([CommonTableExpr(CommonTableExpr { ctename: Some(\"w\"), aliascolnames: None, ctematerialized: CTEMaterializeNever, ctequery: Some(SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(ColumnRef(ColumnRef { fields: Some([A_Star(A_Star)]), location: 35 })), location: 35 })]), from_clause: Some([RangeVar(RangeVar { catalogname: None, schemaname: None, relname: Some(\"big_table\"), inh: true, relpersistence: 'p', alias: None, location: 42 })]), where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })), search_clause: None, cycle_clause: None, location: 5, cterecursive: false, cterefcount: 0, ctecolnames: None, ctecoltypes: None, ctecoltypmods: None, ctecolcollations: None })]), recursive: false, location: 0 }), op: SETOP_NONE, all: false, larg: None, rarg: None })]",
+            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(ColumnRef(ColumnRef { fields: Some([A_Star(A_Star)]), location: 60 })), location: 60 })]), from_clause: Some([RangeVar(RangeVar { catalogname: None, schemaname: None, relname: Some(\"w\"), inh: true, relpersistence: 'p', alias: None, location: 67 })]), where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: Some(A_Const { val: Integer(1), location: 75 }), limit_option: LIMIT_OPTION_COUNT, locking_clause: None, with_clause: Some(WithClause { ctes: Some([CommonTableExpr(CommonTableExpr { ctename: Some(\"w\"), aliascolnames: None, ctematerialized: CTEMaterializeNever, ctequery: Some(SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(ColumnRef(ColumnRef { fields: Some([A_Star(A_Star)]), location: 35 })), location: 35 })]), from_clause: Some([RangeVar(RangeVar { catalogname: None, schemaname: None, relname: Some(\"big_table\"), inh: true, relpersistence: 'p', alias: None, location: 42 })]), where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })), search_clause: None, cycle_clause: None, location: 5, cterecursive: false, cterefcount: 0, ctecolnames: None, ctecoltypes: None, ctecoltypmods: None, ctecolcollations: None })]), recursive: false, location: 0 }), op: SETOP_NONE, all: false, larg: None, rarg: None })]",
         ),
         (
             "CREATE USER test PASSWORD $1",
@@ -280,11 +574,11 @@ fn it_can_parse_tests() {
         ),
         (
             "SELECT INTERVAL $1 YEAR",
-            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(TypeCast(TypeCast { arg: Some(ParamRef(ParamRef { number: 1, location: 16 })), type_name: Some(TypeName { names: Some([String { sval: Some(\"pg_catalog\") }, String { sval: Some(\"interval\") }]), type_oid: 0, setof: false, pct_type: false, typmods: Some([A_Const(Integer(4))]), typemod: -1, array_bounds: None, location: 7 }), location: -1 })), location: 7 })]), from_clause: None, where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })]",
+            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(TypeCast(TypeCast { arg: Some(ParamRef(ParamRef { number: 1, location: 16 })), type_name: Some(TypeName { names: Some([String { sval: Some(\"pg_catalog\") }, String { sval: Some(\"interval\") }]), type_oid: 0, setof: false, pct_type: false, typmods: Some([A_Const { val: Integer(4), location: 19 }]), typemod: -1, array_bounds: None, location: 7 }), location: -1 })), location: 7 })]), from_clause: None, where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })]",
         ),
         (
             "SELECT INTERVAL (6) $1",
-            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(TypeCast(TypeCast { arg: Some(ParamRef(ParamRef { number: 1, location: 20 })), type_name: Some(TypeName { names: Some([String { sval: Some(\"pg_catalog\") }, String { sval: Some(\"interval\") }]), type_oid: 0, setof: false, pct_type: false, typmods: Some([A_Const(Integer(32767)), A_Const(Integer(6))]), typemod: -1, array_bounds: None, location: 7 }), location: -1 })), location: 7 })]), from_clause: None, where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })]",
+            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(TypeCast(TypeCast { arg: Some(ParamRef(ParamRef { number: 1, location: 20 })), type_name: Some(TypeName { names: Some([String { sval: Some(\"pg_catalog\") }, String { sval: Some(\"interval\") }]), type_oid: 0, setof: false, pct_type: false, typmods: Some([A_Const { val: Integer(32767), location: -1 }, A_Const { val: Integer(6), location: 17 }]), typemod: -1, array_bounds: None, location: 7 }), location: -1 })), location: 7 })]), from_clause: None, where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })]",
         ),
         (
             "SET search_path = $1",
@@ -296,11 +590,11 @@ fn it_can_parse_tests() {
         ),
         (
             "WITH a AS (SELECT * FROM x WHERE x.y = $1 AND x.z = 1) SELECT * FROM a WHERE b = 5",
-            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(ColumnRef(ColumnRef { fields: Some([A_Star(A_Star)]), location: 62 })), location: 62 })]), from_clause: Some([RangeVar(RangeVar { catalogname: None, schemaname: None, relname: Some(\"a\"), inh: true, relpersistence: 'p', alias: None, location: 69 })]), where_clause: Some(A_Expr(A_Expr { kind: AEXPR_OP, name: Some([String { sval: Some(\"=\") }]), lexpr: Some(ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"b\") }]), location: 77 })), rexpr: Some(A_Const(Integer(5))), location: 79 })), group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: Some(WithClause { ctes: Some([CommonTableExpr(CommonTableExpr { ctename: Some(\"a\"), aliascolnames: None, ctematerialized: CTEMaterializeDefault, ctequery: Some(SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(ColumnRef(ColumnRef { fields: Some([A_Star(A_Star)]), location: 18 })), location: 18 })]), from_clause: Some([RangeVar(RangeVar { catalogname: None, schemaname: None, relname: Some(\"x\"), inh: true, relpersistence: 'p', alias: None, location: 25 })]), where_clause: Some(BoolExpr(BoolExpr { boolop: AND_EXPR, args: Some([A_Expr(A_Expr { kind: AEXPR_OP, name: Some([String { sval: Some(\"=\") }]), lexpr: Some(ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"x\") }, String { sval: Some(\"y\") }]), location: 33 })), rexpr: Some(ParamRef(ParamRef { number: 1, location: 39 })), location: 37 }), A_Expr(A_Expr { kind: AEXPR_OP, name: Some([String { sval: Some(\"=\") }]), lexpr: Some(ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"x\") }, String { sval: Some(\"z\") }]), location: 46 })), rexpr: Some(A_Const(Integer(1))), location: 50 })]), location: 42 })), group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })), search_clause: None, cycle_clause: None, location: 5, cterecursive: false, cterefcount: 0, ctecolnames: None, ctecoltypes: None, ctecoltypmods: None, ctecolcollations: None })]), recursive: false, location: 0 }), op: SETOP_NONE, all: false, larg: None, rarg: None })]",
+            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(ColumnRef(ColumnRef { fields: Some([A_Star(A_Star)]), location: 62 })), location: 62 })]), from_clause: Some([RangeVar(RangeVar { catalogname: None, schemaname: None, relname: Some(\"a\"), inh: true, relpersistence: 'p', alias: None, location: 69 })]), where_clause: Some(A_Expr(A_Expr { kind: AEXPR_OP, name: Some([String { sval: Some(\"=\") }]), lexpr: Some(ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"b\") }]), location: 77 })), rexpr: Some(A_Const { val: Integer(5), location: 81 }), location: 79 })), group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: Some(WithClause { ctes: Some([CommonTableExpr(CommonTableExpr { ctename: Some(\"a\"), aliascolnames: None, ctematerialized: CTEMaterializeDefault, ctequery: Some(SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(ColumnRef(ColumnRef { fields: Some([A_Star(A_Star)]), location: 18 })), location: 18 })]), from_clause: Some([RangeVar(RangeVar { catalogname: None, schemaname: None, relname: Some(\"x\"), inh: true, relpersistence: 'p', alias: None, location: 25 })]), where_clause: Some(BoolExpr(BoolExpr { boolop: AND_EXPR, args: Some([A_Expr(A_Expr { kind: AEXPR_OP, name: Some([String { sval: Some(\"=\") }]), lexpr: Some(ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"x\") }, String { sval: Some(\"y\") }]), location: 33 })), rexpr: Some(ParamRef(ParamRef { number: 1, location: 39 })), location: 37 }), A_Expr(A_Expr { kind: AEXPR_OP, name: Some([String { sval: Some(\"=\") }]), lexpr: Some(ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"x\") }, String { sval: Some(\"z\") }]), location: 46 })), rexpr: Some(A_Const { val: Integer(1), location: 52 }), location: 50 })]), location: 42 })), group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })), search_clause: None, cycle_clause: None, location: 5, cterecursive: false, cterefcount: 0, ctecolnames: None, ctecoltypes: None, ctecoltypmods: None, ctecolcollations: None })]), recursive: false, location: 0 }), op: SETOP_NONE, all: false, larg: None, rarg: None })]",
         ),
         (
             "SELECT count(*) from testjsonb  WHERE j->'array' ? 'bar'",
-            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(FuncCall(FuncCall { funcname: Some([String { sval: Some(\"count\") }]), args: None, agg_order: None, agg_filter: None, over: None, agg_within_group: false, agg_star: true, agg_distinct: false, func_variadic: false, funcformat: COERCE_EXPLICIT_CALL, location: 7 })), location: 7 })]), from_clause: Some([RangeVar(RangeVar { catalogname: None, schemaname: None, relname: Some(\"testjsonb\"), inh: true, relpersistence: 'p', alias: None, location: 21 })]), where_clause: Some(A_Expr(A_Expr { kind: AEXPR_OP, name: Some([String { sval: Some(\"?\") }]), lexpr: Some(A_Expr(A_Expr { kind: AEXPR_OP, name: Some([String { sval: Some(\"->\") }]), lexpr: Some(ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"j\") }]), location: 38 })), rexpr: Some(A_Const(String(\"array\"))), location: 39 })), rexpr: Some(A_Const(String(\"bar\"))), location: 49 })), group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })]",
+            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(FuncCall(FuncCall { funcname: Some([String { sval: Some(\"count\") }]), args: None, agg_order: None, agg_filter: None, over: None, agg_within_group: false, agg_star: true, agg_distinct: false, func_variadic: false, funcformat: COERCE_EXPLICIT_CALL, location: 7 })), location: 7 })]), from_clause: Some([RangeVar(RangeVar { catalogname: None, schemaname: None, relname: Some(\"testjsonb\"), inh: true, relpersistence: 'p', alias: None, location: 21 })]), where_clause: Some(A_Expr(A_Expr { kind: AEXPR_OP, name: Some([String { sval: Some(\"?\") }]), lexpr: Some(A_Expr(A_Expr { kind: AEXPR_OP, name: Some([String { sval: Some(\"->\") }]), lexpr: Some(ColumnRef(ColumnRef { fields: Some([String { sval: Some(\"j\") }]), location: 38 })), rexpr: Some(A_Const { val: String(\"array\"), location: 41 }), location: 39 })), rexpr: Some(A_Const { val: String(\"bar\"), location: 51 }), location: 49 })), group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })]",
         ),
         (
             "SELECT DISTINCT a FROM b",
@@ -308,11 +602,11 @@ fn it_can_parse_tests() {
         ),
         (
             "SELECT * FROM generate_series(1, 2)",
-            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(ColumnRef(ColumnRef { fields: Some([A_Star(A_Star)]), location: 7 })), location: 7 })]), from_clause: Some([RangeFunction(RangeFunction { lateral: false, ordinality: false, is_rowsfrom: false, functions: Some([List(List { items: [FuncCall(FuncCall { funcname: Some([String { sval: Some(\"generate_series\") }]), args: Some([A_Const(Integer(1)), A_Const(Integer(2))]), agg_order: None, agg_filter: None, over: None, agg_within_group: false, agg_star: false, agg_distinct: false, func_variadic: false, funcformat: COERCE_EXPLICIT_CALL, location: 14 })] })]), alias: None, coldeflist: None })]), where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })]",
+            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(ColumnRef(ColumnRef { fields: Some([A_Star(A_Star)]), location: 7 })), location: 7 })]), from_clause: Some([RangeFunction(RangeFunction { lateral: false, ordinality: false, is_rowsfrom: false, functions: Some([List(List { items: [FuncCall(FuncCall { funcname: Some([String { sval: Some(\"generate_series\") }]), args: Some([A_Const { val: Integer(1), location: 30 }, A_Const { val: Integer(2), location: 33 }]), agg_order: None, agg_filter: None, over: None, agg_within_group: false, agg_star: false, agg_distinct: false, func_variadic: false, funcformat: COERCE_EXPLICIT_CALL, location: 14 })] })]), alias: None, coldeflist: None })]), where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })]",
         ),
         (
             "SELECT 1 + 1",
-            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(A_Expr(A_Expr { kind: AEXPR_OP, name: Some([String { sval: Some(\"+\") }]), lexpr: Some(A_Const(Integer(1))), rexpr: Some(A_Const(Integer(1))), location: 9 })), location: 7 })]), from_clause: None, where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })]",
+            "[SelectStmt(SelectStmt { distinct_clause: None, into_clause: None, target_list: Some([ResTarget(ResTarget { name: None, indirection: None, val: Some(A_Expr(A_Expr { kind: AEXPR_OP, name: Some([String { sval: Some(\"+\") }]), lexpr: Some(A_Const { val: Integer(1), location: 7 }), rexpr: Some(A_Const { val: Integer(1), location: 11 }), location: 9 })), location: 7 })]), from_clause: None, where_clause: None, group_clause: None, group_distinct: false, having_clause: None, window_clause: None, values_lists: None, sort_clause: None, limit_offset: None, limit_count: None, limit_option: LIMIT_OPTION_DEFAULT, locking_clause: None, with_clause: None, op: SETOP_NONE, all: false, larg: None, rarg: None })]",
         ),
     ];
 
@@ -333,5 +627,420 @@ fn it_can_parse_tests() {
             expr,
             debug
         );
+
+        // The borrowed tree has an identical structure
+        let borrowed = pg_parse::parse_borrowed(expr).unwrap();
+        assert_eq!(
+            format!("{:?}", borrowed.statements().unwrap()),
+            tree,
+            "Borrowed: {}",
+            expr
+        );
+
+        // Only nodes produced by the raw parser are present
+        assert_eq!(analyzed_nodes(&stmt), Vec::<&str>::new(), "{expr}");
+    }
+}
+
+#[test]
+fn it_can_serialize_to_libpg_query_json() {
+    // The top level of the JSON produced by libpg_query isn't part of the AST, so is defined here
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct ParseResult {
+        version: u32,
+        stmts: Vec<RawStmt>,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct RawStmt {
+        stmt: Node,
+        #[serde(default, skip_serializing_if = "is_zero")]
+        stmt_location: i32,
+        #[serde(default, skip_serializing_if = "is_zero")]
+        stmt_len: i32,
+    }
+
+    fn is_zero(value: &i32) -> bool {
+        *value == 0
+    }
+
+    let tests = [
+        "SELECT 1",
+        "SELECT DISTINCT a, b FROM t",
+        "SELECT 1; SELECT 2",
+        "SELECT -1, 0, 1.5, 'say \"hi\"', B'0101', true, false, NULL",
+        "SELECT * FROM a LEFT JOIN b ON a.id = b.id WHERE a.x IN (1, 2, 3) ORDER BY a.y DESC NULLS LAST LIMIT 10",
+        "SELECT count(*) OVER (PARTITION BY a ORDER BY b ROWS BETWEEN 1 PRECEDING AND CURRENT ROW) FROM t",
+        "WITH w AS NOT MATERIALIZED (SELECT * FROM big_table) SELECT * FROM w LIMIT 1",
+        "INSERT INTO t (a, b) VALUES ($1, $2) ON CONFLICT (a) DO UPDATE SET b = excluded.b RETURNING *",
+        "CREATE TABLE t (id serial PRIMARY KEY, name varchar(40) NOT NULL DEFAULT '', parent int REFERENCES t (id) ON DELETE CASCADE)",
+        "CREATE FOREIGN TABLE films (code char(5) NOT NULL, len interval hour to minute) SERVER film_server;",
+        "ALTER TABLE t ALTER COLUMN a SET DEFAULT 1, ADD CONSTRAINT c CHECK (a > 0)",
+        "SELECT interval '1 day', '2020-01-01'::date, ARRAY[1, 2]::int[]",
+    ];
+    for sql in tests {
+        let (stmts, debug) = pg_parse::parse_debug(sql).unwrap();
+        let result: ParseResult = serde_json::from_str(&debug).unwrap();
+        assert_eq!(
+            serde_json::to_string(&result).unwrap(),
+            debug,
+            "Failed to round trip: {}",
+            sql
+        );
+
+        // Each node serializes to the same JSON as was parsed
+        for (stmt, raw) in stmts.iter().zip(&result.stmts) {
+            let json = serde_json::to_string(stmt).unwrap();
+            assert_eq!(json, serde_json::to_string(&raw.stmt).unwrap());
+            assert_eq!(&serde_json::from_str::<Node>(&json).unwrap(), stmt);
+        }
     }
 }
+
+#[test]
+fn it_can_downcast_nodes() {
+    use pg_parse::ast::{NodeIteratorExt, RangeVar};
+
+    let mut result = pg_parse::parse("SELECT 1, a FROM b, c, generate_series(1, 2)").unwrap();
+    assert!(result[0].is_select_stmt());
+    assert!(!result[0].is_insert_stmt());
+    assert!(result[0].as_insert_stmt().is_none());
+
+    let stmt = result[0].as_select_stmt().unwrap();
+    let target = stmt.target_list.as_ref().unwrap()[0]
+        .as_res_target()
+        .unwrap();
+    let val = target.val.as_deref().unwrap();
+    assert!(val.is_a_const());
+    assert_eq!(val.as_a_const(), Some(&ConstValue::Integer(1)));
+    assert!(val.as_string().is_none());
+
+    // Filtering by variant
+    let tables = stmt
+        .from_clause
+        .iter()
+        .flatten()
+        .only::<RangeVar>()
+        .map(|table| table.relname.as_deref().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(tables, ["b", "c"]);
+
+    // Including mutably, and by value
+    let stmt = result[0].as_select_stmt_mut().unwrap();
+    for table in stmt.from_clause.iter_mut().flatten().only::<RangeVar>() {
+        table.schemaname = Some("public".into());
+    }
+    let stmt = result.remove(0).into_select_stmt().unwrap();
+    let tables = stmt
+        .from_clause
+        .unwrap()
+        .into_iter()
+        .only::<RangeVar>()
+        .map(|table| table.schemaname.unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(tables, ["public", "public"]);
+}
+
+#[test]
+fn it_can_resolve_the_location_of_nodes() {
+    let sql = "SELECT a,\n  'é', count(*) FROM t";
+    let result = pg_parse::parse(sql).unwrap();
+    let stmt = result[0].as_select_stmt().unwrap();
+    let targets = stmt
+        .target_list
+        .as_ref()
+        .unwrap()
+        .iter()
+        .map(|target| target.as_res_target().unwrap().val.as_deref().unwrap())
+        .collect::<Vec<_>>();
+    let locations = targets
+        .iter()
+        .map(|node| node.location())
+        .collect::<Vec<_>>();
+    assert_eq!(locations, [Some(7), Some(12), Some(18)]);
+    assert_eq!(stmt.from_clause.as_ref().unwrap()[0].location(), Some(32));
+
+    // Locations are byte offsets, which can be converted into a line and column
+    let position = Position::from_offset(sql, 18).unwrap();
+    assert_eq!(position.line, 2);
+    assert_eq!(position.byte_column, 9);
+    assert_eq!(position.char_column, 8);
+    assert_eq!(&sql[18..], "count(*) FROM t");
+
+    // Not every node has a location
+    assert_eq!(result[0].location(), None);
+}
+
+#[test]
+fn it_can_decode_packed_flags() {
+    use pg_parse::ast::flags::*;
+
+    let result = pg_parse::parse(
+        "SELECT sum(x) OVER (ROWS 1 PRECEDING), \
+                sum(x) OVER (ORDER BY y GROUPS BETWEEN CURRENT ROW AND UNBOUNDED FOLLOWING EXCLUDE TIES), \
+                sum(x) OVER (ORDER BY y) \
+         FROM t",
+    )
+    .unwrap();
+    let stmt = result[0].as_select_stmt().unwrap();
+    let windows = stmt
+        .target_list
+        .as_ref()
+        .unwrap()
+        .iter()
+        .map(|target| {
+            let val = target.as_res_target().unwrap().val.as_deref().unwrap();
+            val.as_func_call().unwrap().over.as_deref().unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    let frame = windows[0].frame().unwrap();
+    assert_eq!(frame.mode, FrameMode::Rows);
+    assert!(
+        matches!(frame.start, FrameBound::Preceding(offset) if offset.as_a_const() == Some(&ConstValue::Integer(1)))
+    );
+    assert_eq!(frame.end, FrameBound::CurrentRow);
+    assert_eq!(frame.exclusion, None);
+    assert!(!windows[0].frame_flags().contains(FrameOptions::BETWEEN));
+
+    let frame = windows[1].frame().unwrap();
+    assert_eq!(frame.mode, FrameMode::Groups);
+    assert_eq!(frame.start, FrameBound::CurrentRow);
+    assert_eq!(frame.end, FrameBound::UnboundedFollowing);
+    assert_eq!(frame.exclusion, Some(FrameExclusion::Ties));
+    assert!(windows[1].frame_flags().contains(FrameOptions::BETWEEN));
+
+    assert_eq!(windows[2].frame(), None);
+
+    let result = pg_parse::parse(
+        "CREATE TRIGGER check_update BEFORE INSERT OR UPDATE ON accounts \
+         FOR EACH ROW EXECUTE FUNCTION check_account_update()",
+    )
+    .unwrap();
+    let stmt = result[0].as_create_trig_stmt().unwrap();
+    assert_eq!(stmt.trigger_timing(), Some(TriggerTiming::Before));
+    assert_eq!(
+        stmt.trigger_events(),
+        TriggerEvents::INSERT | TriggerEvents::UPDATE
+    );
+
+    let result =
+        pg_parse::parse("SELECT '1'::interval, '1'::interval day to second(3), '1'::interval hour")
+            .unwrap();
+    let stmt = result[0].as_select_stmt().unwrap();
+    let types = stmt
+        .target_list
+        .as_ref()
+        .unwrap()
+        .iter()
+        .map(|target| {
+            let val = target.as_res_target().unwrap().val.as_deref().unwrap();
+            val.as_type_cast().unwrap().type_name.as_deref().unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(types[0].interval_fields(), None);
+    assert_eq!(
+        types[1].interval_fields(),
+        Some(
+            IntervalFields::DAY
+                | IntervalFields::HOUR
+                | IntervalFields::MINUTE
+                | IntervalFields::SECOND
+        )
+    );
+    assert_eq!(types[1].interval_precision(), Some(3));
+    assert_eq!(types[2].interval_fields(), Some(IntervalFields::HOUR));
+    assert_eq!(types[2].interval_precision(), None);
+}
+
+#[test]
+fn it_can_decode_char_codes() {
+    use pg_parse::ast::PartitionStrategy;
+    use pg_parse::ast::codes::*;
+
+    let result = pg_parse::parse(
+        "CREATE TEMP TABLE t (\
+            id int GENERATED BY DEFAULT AS IDENTITY, \
+            a_id int REFERENCES a MATCH FULL ON UPDATE SET NULL ON DELETE RESTRICT\
+         ) PARTITION BY HASH (id)",
+    )
+    .unwrap();
+    let stmt = result[0].as_create_stmt().unwrap();
+    assert_eq!(
+        stmt.relation.as_ref().unwrap().persistence(),
+        Some(Persistence::Temporary)
+    );
+    assert_eq!(
+        *stmt.partspec.as_ref().unwrap().strategy,
+        PartitionStrategy::PARTITION_STRATEGY_HASH
+    );
+
+    // The bounds of a partition record the strategy implied by their syntax
+    for (sql, strategy) in [
+        (
+            "CREATE TABLE p1 PARTITION OF p FOR VALUES IN (1, 2)",
+            Some(PartitionStrategy::PARTITION_STRATEGY_LIST),
+        ),
+        (
+            "CREATE TABLE p1 PARTITION OF p FOR VALUES FROM (1) TO (10)",
+            Some(PartitionStrategy::PARTITION_STRATEGY_RANGE),
+        ),
+        (
+            "CREATE TABLE p1 PARTITION OF p FOR VALUES WITH (MODULUS 4, REMAINDER 0)",
+            Some(PartitionStrategy::PARTITION_STRATEGY_HASH),
+        ),
+        ("CREATE TABLE p1 PARTITION OF p DEFAULT", None),
+    ] {
+        let result = pg_parse::parse(sql).unwrap();
+        let stmt = result[0].as_create_stmt().unwrap();
+        assert_eq!(
+            stmt.partbound.as_ref().unwrap().strategy_kind(),
+            strategy,
+            "{sql}"
+        );
+    }
+
+    let constraints = stmt
+        .table_elts
+        .as_ref()
+        .unwrap()
+        .iter()
+        .map(|elt| {
+            let column = elt.as_column_def().unwrap();
+            column.constraints.as_ref().unwrap()[0]
+                .as_constraint()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        constraints[0].generated_when_kind(),
+        Some(GeneratedWhen::ByDefault)
+    );
+    assert_eq!(
+        constraints[1].fk_match_type(),
+        Some(ForeignKeyMatchType::Full)
+    );
+    assert_eq!(
+        constraints[1].fk_update_action(),
+        Some(ForeignKeyAction::SetNull)
+    );
+    assert_eq!(
+        constraints[1].fk_delete_action(),
+        Some(ForeignKeyAction::Restrict)
+    );
+
+    let result = pg_parse::parse("ALTER TABLE t REPLICA IDENTITY FULL").unwrap();
+    let stmt = result[0].as_alter_table_stmt().unwrap();
+    let cmd = stmt.cmds.as_ref().unwrap()[0].as_alter_table_cmd().unwrap();
+    let identity = cmd
+        .def
+        .as_deref()
+        .unwrap()
+        .as_replica_identity_stmt()
+        .unwrap();
+    assert_eq!(identity.replica_identity(), Some(ReplicaIdentity::Full));
+}
+
+#[test]
+fn it_can_compare_statements_structurally() {
+    use pg_parse::ast::structural::{StructuralHash, StructuralKey, StructuralOptions};
+    use std::collections::HashSet;
+
+    let queries = [
+        "SELECT a, b FROM t AS x WHERE x.id = $1",
+        "select a,\n       b\n  from t as x\n where x.id = $1",
+        "SELECT a, b FROM t AS y WHERE x.id = $1",
+        "SELECT a, b FROM t AS x WHERE x.id = $2",
+    ];
+    let stmts = queries
+        .iter()
+        .map(|query| pg_parse::parse(query).unwrap().remove(0))
+        .collect::<Vec<_>>();
+    assert_ne!(stmts[0], stmts[1]);
+    assert!(stmts[0].eq_ignoring_locations(&stmts[1]));
+    assert!(!stmts[0].eq_ignoring_locations(&stmts[2]));
+    assert!(!stmts[0].eq_ignoring_locations(&stmts[3]));
+
+    let unique = stmts.iter().map(StructuralKey::new).collect::<HashSet<_>>();
+    assert_eq!(unique.len(), 3);
+
+    let options = StructuralOptions {
+        ignore_alias_names: true,
+    };
+    let unique = stmts
+        .iter()
+        .map(|stmt| StructuralKey::with_options(stmt, options))
+        .collect::<HashSet<_>>();
+    assert_eq!(unique.len(), 2);
+}
+
+#[test]
+fn it_can_parse_leniently() {
+    let sql = "SELECT a, count(*) FROM t GROUP BY a";
+    let options = ParseOptions {
+        lenient: true,
+        ..ParseOptions::default()
+    };
+    let result = pg_parse::parse_with_options(sql, &options).unwrap();
+    assert_eq!(result, pg_parse::parse(sql).unwrap());
+}
+
+/// Collects the name of any node which the raw parser shouldn't produce.
+#[derive(Default)]
+struct AnalyzedNodes(Vec<&'static str>);
+
+impl<'ast> RawVisit<'ast> for AnalyzedNodes {
+    fn visit_analyzed_node(&mut self, node: &'ast Node) {
+        self.0.push(node.name());
+    }
+}
+
+fn analyzed_nodes(stmts: &[Node]) -> Vec<&'static str> {
+    let mut analyzed = AnalyzedNodes::default();
+    for stmt in stmts {
+        match stmt.as_raw() {
+            Some(raw) => analyzed.visit_raw_node(raw),
+            None => analyzed.visit_analyzed_node(stmt),
+        }
+    }
+    analyzed.0
+}
+
+#[test]
+fn it_only_produces_raw_nodes() {
+    // Every script within the corpus is checked, along with statements using less common syntax
+    let corpus = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/sql");
+    let mut scripts = std::fs::read_dir(corpus)
+        .unwrap()
+        .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+        .collect::<Vec<_>>();
+    assert!(!scripts.is_empty());
+    scripts.extend([
+        "SELECT a.x, count(*) FILTER (WHERE b > 1) OVER (PARTITION BY c ORDER BY d ROWS 2 PRECEDING), \
+         CASE WHEN e IS NULL THEN 1 ELSE coalesce(f, greatest(g, 2)) END, h::int[], i COLLATE \"C\", \
+         ARRAY[1, 2][1], ROW(1, 2), (j).k, current_date, l IN (SELECT m FROM n), o = ANY(p) \
+         FROM a TABLESAMPLE SYSTEM (10) JOIN b USING (id) WHERE NOT q AND r IS TRUE \
+         GROUP BY GROUPING SETS ((a.x), ()) ORDER BY 1 FOR UPDATE",
+        "WITH RECURSIVE t AS (SELECT 1 UNION ALL SELECT n + 1 FROM t) SELECT * FROM t",
+        "INSERT INTO t (a) VALUES (DEFAULT) ON CONFLICT (a) DO UPDATE SET a = excluded.a RETURNING *",
+        "MERGE INTO t USING s ON t.id = s.id WHEN MATCHED THEN UPDATE SET a = s.a \
+         WHEN NOT MATCHED THEN INSERT VALUES (s.id, s.a)",
+        "SELECT json_object('a': 1), json_arrayagg(a), b IS JSON, json_value(c, '$.d') FROM t",
+        "SELECT * FROM xmltable('/a' PASSING b COLUMNS c int), json_table(d, '$' COLUMNS (e int))",
+        "UPDATE t SET (a, b) = (SELECT 1, 2) WHERE CURRENT OF c",
+    ]
+    .map(String::from));
+    for script in scripts {
+        let stmts = pg_parse::parse(&script).unwrap();
+        assert_eq!(analyzed_nodes(&stmts), Vec::<&str>::new(), "{script}");
+    }
+}
+
+#[test]
+fn it_boxes_large_nodes() {
+    // Large structs (and the value of constants) are boxed so that no variant holds more than 32
+    // bytes, which along with the discriminant keeps lists of small nodes (e.g. strings) compact
+    assert_eq!(size_of::<Node>(), 40);
+    assert_eq!(size_of::<pg_parse::ast::borrowed::Node>(), 40);
+    assert!(size_of::<SelectStmt>() > 40);
+}
@@ -0,0 +1,78 @@
+#[cfg(feature = "protobuf")]
+mod tests {
+    use pg_parse::ast::Node;
+
+    fn assert_same_tree(sql: &str) {
+        let json = match pg_parse::parse(sql) {
+            Ok(tree) => tree,
+            Err(e) => panic!("Failed to parse: {}: \"{}\"", e, sql),
+        };
+        let protobuf = match pg_parse::parse_protobuf(sql) {
+            Ok(tree) => tree,
+            Err(e) => panic!("Failed to decode: {}: \"{}\"", e, sql),
+        };
+        assert_eq!(
+            json, protobuf,
+            "JSON and protobuf trees differ for \"{}\"",
+            sql
+        );
+    }
+
+    #[test]
+    fn it_decodes_the_same_tree_as_json() {
+        let tests = [
+            "SELECT 1",
+            "SELECT 1; SELECT 2",
+            "SELECT DISTINCT a, b, * FROM c WHERE d = e",
+            "SELECT -1, 0, 1.5, 'str', B'0101', true, false, NULL",
+            "SELECT * FROM a LEFT JOIN b ON a.id = b.id WHERE a.x IN (1, 2, 3) ORDER BY a.y DESC NULLS LAST LIMIT 10",
+            "SELECT count(*) OVER (PARTITION BY a ORDER BY b ROWS BETWEEN 1 PRECEDING AND CURRENT ROW) FROM t",
+            "WITH w AS NOT MATERIALIZED (SELECT * FROM big_table) SELECT * FROM w LIMIT 1",
+            "INSERT INTO t (a, b) VALUES ($1, $2) ON CONFLICT (a) DO UPDATE SET b = excluded.b RETURNING *",
+            "UPDATE accounts SET balance = balance - 100.00 WHERE id = 1",
+            "DELETE FROM t USING u WHERE t.id = u.id",
+            "CREATE TABLE t (id serial PRIMARY KEY, name varchar(40) NOT NULL DEFAULT '', parent int REFERENCES t (id) ON DELETE CASCADE)",
+            "CREATE FOREIGN TABLE ft1 () SERVER no_server",
+            "CREATE UNIQUE INDEX CONCURRENTLY idx ON t USING btree (a, lower(b)) WHERE c IS NOT NULL",
+            "CREATE TRIGGER trg BEFORE INSERT OR UPDATE ON t FOR EACH ROW EXECUTE FUNCTION f()",
+            "ALTER ROLE postgres LOGIN SUPERUSER PASSWORD 'xyz'",
+            "SELECT * FROM t TABLESAMPLE SYSTEM (10) REPEATABLE (42)",
+            "SELECT interval '1 day', '2020-01-01'::date, ARRAY[1, 2]::int[]",
+        ];
+        for sql in tests {
+            assert_same_tree(sql);
+        }
+    }
+
+    #[test]
+    fn it_decodes_the_same_tree_as_json_for_scripts() {
+        let scripts = [
+            include_str!("data/sql/table_1.sql"),
+            include_str!("data/sql/view_1.sql"),
+            include_str!("data/sql/func_1.sql"),
+            include_str!("data/sql/func_2.sql"),
+        ];
+        for sql in scripts {
+            assert_same_tree(sql);
+        }
+    }
+
+    #[test]
+    fn it_can_decode_deep_trees() {
+        let sql = format!("SELECT {}", vec!["1"; 2000].join(" + "));
+        assert_same_tree(&sql);
+
+        let sql = format!("SELECT {}1{}", "(SELECT ".repeat(200), ")".repeat(200));
+        let result = pg_parse::parse_protobuf(&sql).unwrap();
+        assert!(matches!(result[0], Node::SelectStmt(_)));
+    }
+
+    #[test]
+    fn it_reports_parse_errors() {
+        let result = pg_parse::parse_protobuf("SELECT * FRO contacts");
+        let Err(pg_parse::Error::ParseError(error)) = result else {
+            panic!("Expected a parse error: {:?}", result);
+        };
+        assert_eq!(error.message, "syntax error at or near \"FRO\"");
+    }
+}
@@ -0,0 +1,94 @@
+use pg_parse::{KeywordKind, ScanToken, Token};
+
+fn scan(sql: &str) -> Vec<(&str, Token, KeywordKind)> {
+    pg_parse::scan(sql)
+        .unwrap()
+        .into_iter()
+        .map(|token| (&sql[token.range()], token.token, token.keyword_kind))
+        .collect()
+}
+
+#[test]
+fn it_can_scan_a_simple_statement() {
+    let sql = "SELECT a, 1 FROM contacts WHERE name = 'Paul'";
+    assert_eq!(
+        scan(sql),
+        vec![
+            ("SELECT", Token::SELECT, KeywordKind::Reserved),
+            ("a", Token::IDENT, KeywordKind::NoKeyword),
+            (",", Token::ASCII_44, KeywordKind::NoKeyword),
+            ("1", Token::ICONST, KeywordKind::NoKeyword),
+            ("FROM", Token::FROM, KeywordKind::Reserved),
+            ("contacts", Token::IDENT, KeywordKind::NoKeyword),
+            ("WHERE", Token::WHERE, KeywordKind::Reserved),
+            ("name", Token::NAME_P, KeywordKind::Unreserved),
+            ("=", Token::ASCII_61, KeywordKind::NoKeyword),
+            ("'Paul'", Token::SCONST, KeywordKind::NoKeyword),
+        ]
+    );
+}
+
+#[test]
+fn it_can_scan_keyword_categories() {
+    let sql = "SELECT coalesce(x, 1)::integer FROM t LEFT JOIN u USING (id)";
+    let tokens = scan(sql);
+    let kind = |text: &str| {
+        tokens
+            .iter()
+            .find(|(t, _, _)| *t == text)
+            .map(|(_, _, kind)| *kind)
+            .unwrap()
+    };
+    assert_eq!(kind("coalesce"), KeywordKind::ColName);
+    assert_eq!(kind("integer"), KeywordKind::ColName);
+    assert_eq!(kind("LEFT"), KeywordKind::TypeFuncName);
+    assert_eq!(kind("USING"), KeywordKind::Reserved);
+    assert_eq!(kind("x"), KeywordKind::NoKeyword);
+}
+
+#[test]
+fn it_can_scan_comments_and_invalid_statements() {
+    let sql = "SELEC 1 -- comment\n/* block */";
+    let tokens = pg_parse::scan(sql).unwrap();
+    assert_eq!(
+        tokens,
+        vec![
+            ScanToken {
+                start: 0,
+                end: 5,
+                token: Token::IDENT,
+                keyword_kind: KeywordKind::NoKeyword,
+            },
+            ScanToken {
+                start: 6,
+                end: 7,
+                token: Token::ICONST,
+                keyword_kind: KeywordKind::NoKeyword,
+            },
+            ScanToken {
+                start: 8,
+                end: 18,
+                token: Token::SQL_COMMENT,
+                keyword_kind: KeywordKind::NoKeyword,
+            },
+            ScanToken {
+                start: 19,
+                end: 30,
+                token: Token::C_COMMENT,
+                keyword_kind: KeywordKind::NoKeyword,
+            },
+        ]
+    );
+}
+
+#[test]
+fn it_reports_scanner_errors() {
+    let result = pg_parse::scan("SELECT 'unterminated");
+    let Err(pg_parse::Error::ParseError(error)) = result else {
+        panic!("Expected a parse error: {:?}", result);
+    };
+    assert_eq!(
+        error.message,
+        "unterminated quoted string at or near \"'unterminated\""
+    );
+}
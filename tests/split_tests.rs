@@ -0,0 +1,67 @@
+use pg_parse::SplitMode;
+
+fn split(sql: &str, mode: SplitMode) -> Vec<&str> {
+    pg_parse::split(sql, mode)
+        .unwrap()
+        .into_iter()
+        .map(|range| sql[range].trim())
+        .collect()
+}
+
+#[test]
+fn it_can_split_simple_statements() {
+    let sql = "SELECT 1; SELECT 2;\nINSERT INTO a VALUES (1)";
+    for mode in [SplitMode::Scanner, SplitMode::Parser] {
+        assert_eq!(
+            split(sql, mode),
+            vec!["SELECT 1", "SELECT 2", "INSERT INTO a VALUES (1)"],
+            "{:?}",
+            mode
+        );
+    }
+}
+
+#[test]
+fn it_respects_strings_dollar_quotes_and_comments() {
+    let sql = "SELECT ';'; -- a comment; with a semicolon\nCREATE FUNCTION f() RETURNS int AS $$ SELECT 1; $$ LANGUAGE sql; /* ; */ SELECT 2";
+    for mode in [SplitMode::Scanner, SplitMode::Parser] {
+        let result = split(sql, mode);
+        assert_eq!(result.len(), 3, "{:?}: {:?}", mode, result);
+        assert_eq!(result[0], "SELECT ';'");
+        assert!(
+            result[1].ends_with("CREATE FUNCTION f() RETURNS int AS $$ SELECT 1; $$ LANGUAGE sql"),
+            "{:?}: {}",
+            mode,
+            result[1]
+        );
+        assert!(result[2].ends_with("SELECT 2"), "{:?}: {}", mode, result[2]);
+    }
+}
+
+#[test]
+fn it_can_split_begin_atomic_bodies_with_the_parser() {
+    let sql = "CREATE FUNCTION f() RETURNS int LANGUAGE sql BEGIN ATOMIC SELECT 1; SELECT 2; END; SELECT 3";
+    let result = split(sql, SplitMode::Parser);
+    assert_eq!(
+        result,
+        vec![
+            "CREATE FUNCTION f() RETURNS int LANGUAGE sql BEGIN ATOMIC SELECT 1; SELECT 2; END",
+            "SELECT 3"
+        ]
+    );
+}
+
+#[test]
+fn it_can_split_invalid_statements_with_the_scanner() {
+    let sql = "SELECT 1; SELEC 2; SELECT 3";
+    assert_eq!(
+        split(sql, SplitMode::Scanner),
+        vec!["SELECT 1", "SELEC 2", "SELECT 3"]
+    );
+
+    let result = pg_parse::split(sql, SplitMode::Parser);
+    let Err(pg_parse::Error::ParseError(error)) = result else {
+        panic!("Expected a parse error: {:?}", result);
+    };
+    assert_eq!(error.message, "syntax error at or near \"SELEC\"");
+}
@@ -1,6 +1,10 @@
-#[cfg(feature = "str")]
+#[cfg(any(feature = "str", feature = "protobuf"))]
+mod common;
+
+#[cfg(any(feature = "str", feature = "protobuf"))]
 mod tests {
-    use regex::Regex;
+    use crate::common::strip_locations;
+    use pg_parse::ast::Node;
 
     fn execute_tests<const N: usize>(tests: [&str; N]) {
         for test in tests {
@@ -11,23 +15,49 @@ mod tests {
             // println!("Tree: {:?}, Debug: {}", tree, debug);
 
             // Compare converting this back to a string
-            let sql = tree
-                .iter()
-                .map(|stmt| stmt.to_string())
-                .collect::<Vec<_>>()
-                .join("; ");
-            assert_eq!(
-                test, sql,
-                "expected <> generated to_string:\n\n{:?}\n\nDebug: {}\n\n",
-                tree, debug,
-            );
-
-            // Parse it back and compare the original trees
-            let reparsed_tree = pg_parse::parse(&sql).unwrap();
-            assert_eq!(format!("{:?}", tree), format!("{:?}", reparsed_tree));
+            #[cfg(feature = "str")]
+            {
+                let sql = tree
+                    .iter()
+                    .map(|stmt| stmt.to_string())
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                assert_eq!(
+                    test, sql,
+                    "expected <> generated to_string:\n\n{:?}\n\nDebug: {}\n\n",
+                    tree, debug,
+                );
+
+                // Parse it back and compare the original trees
+                let reparsed_tree = pg_parse::parse(&sql).unwrap();
+                assert_eq!(tree, reparsed_tree);
+            }
+
+            // Also compare deparsing using libpg_query
+            #[cfg(feature = "protobuf")]
+            assert_deparses(test, &tree, &debug);
         }
     }
 
+    /// Deparses the tree using libpg_query and parses the result back. The deparser may format the
+    /// statement differently, so only the trees are compared.
+    #[cfg(feature = "protobuf")]
+    fn assert_deparses(test: &str, tree: &[Node], debug: &str) {
+        let sql = match pg_parse::deparse(tree) {
+            Ok(sql) => sql,
+            Err(e) => panic!("Failed to deparse: {}: \"{}\"", e, test),
+        };
+        let reparsed_tree = pg_parse::parse(&sql).unwrap();
+        assert_eq!(
+            strip_locations(tree),
+            strip_locations(&reparsed_tree),
+            "expected <> deparsed:\n\n{}\n\n{}\n\nDebug: {}\n\n",
+            test,
+            sql,
+            debug,
+        );
+    }
+
     #[test]
     fn it_correctly_converts_to_string_for_select_tests() {
         let tests = [
@@ -692,7 +722,6 @@ mod tests {
 
     #[test]
     fn it_correctly_converts_to_string_for_complex_cases() {
-        let location = Regex::new(r",\slocation:\s(-)?[\d]+").unwrap();
         let tests = [
             include_str!("data/sql/table_1.sql"),
             include_str!("data/sql/view_1.sql"),
@@ -708,21 +737,21 @@ mod tests {
             };
 
             // Convert back to a string
-            let sql = tree
-                .iter()
-                .map(|stmt| stmt.to_string())
-                .collect::<Vec<_>>()
-                .join("; ");
-
-            // Parse it back and compare the original tree
-            let reparsed_tree = pg_parse::parse(&sql).unwrap();
-
-            // We strip out the location from each first
-            let original = format!("{:?}", tree);
-            let reparsed = format!("{:?}", reparsed_tree);
-            let original = location.replace_all(&original, "").to_string();
-            let reparsed = location.replace_all(&reparsed, "").to_string();
-            assert_eq!(original, reparsed);
+            #[cfg(feature = "str")]
+            {
+                let sql = tree
+                    .iter()
+                    .map(|stmt| stmt.to_string())
+                    .collect::<Vec<_>>()
+                    .join("; ");
+
+                // Parse it back and compare the original tree, stripping out the locations first
+                let reparsed_tree = pg_parse::parse(&sql).unwrap();
+                assert_eq!(strip_locations(&tree), strip_locations(&reparsed_tree));
+            }
+
+            #[cfg(feature = "protobuf")]
+            assert_deparses(test, &tree, "");
         }
     }
 }
@@ -0,0 +1,235 @@
+use pg_parse::ast::visit::{Visit, walk_column_ref, walk_range_var};
+use pg_parse::ast::{ColumnRef, ConstValue, Node, ParseLoc, RangeVar};
+
+/// Collects the name of each table referenced by the tree.
+#[derive(Default)]
+struct Tables<'ast>(Vec<&'ast str>);
+
+impl<'ast> Visit<'ast> for Tables<'ast> {
+    fn visit_range_var(&mut self, node: &'ast RangeVar) {
+        if let Some(name) = &node.relname {
+            self.0.push(name);
+        }
+        walk_range_var(self, node);
+    }
+}
+
+/// Collects each column reference, joining the fields with a `.`.
+#[derive(Default)]
+struct Columns(Vec<String>);
+
+impl<'ast> Visit<'ast> for Columns {
+    fn visit_column_ref(&mut self, node: &'ast ColumnRef) {
+        let fields = node
+            .fields
+            .iter()
+            .flatten()
+            .map(|field| match field {
+                Node::String { sval: Some(sval) } => sval.as_str(),
+                Node::A_Star(_) => "*",
+                _ => "?",
+            })
+            .collect::<Vec<_>>();
+        self.0.push(fields.join("."));
+        walk_column_ref(self, node);
+    }
+}
+
+/// Collects each constant along with its location.
+#[derive(Default)]
+struct Constants<'ast>(Vec<(&'ast ConstValue, ParseLoc)>);
+
+impl<'ast> Visit<'ast> for Constants<'ast> {
+    fn visit_a_const(&mut self, val: &'ast ConstValue, location: &'ast ParseLoc) {
+        self.0.push((val, *location));
+    }
+}
+
+fn visit<'ast, V: Visit<'ast>>(mut visitor: V, tree: &'ast [Node]) -> V {
+    for stmt in tree {
+        visitor.visit_node(stmt);
+    }
+    visitor
+}
+
+#[test]
+fn it_can_collect_table_names() {
+    let tree = pg_parse::parse(
+        "WITH x AS (SELECT * FROM a) \
+         SELECT * FROM x JOIN public.b ON x.id = b.id \
+         WHERE EXISTS (SELECT 1 FROM c WHERE c.id IN (SELECT id FROM d)); \
+         INSERT INTO e SELECT * FROM f",
+    )
+    .unwrap();
+    let tables = visit(Tables::default(), &tree);
+    assert_eq!(tables.0, ["x", "b", "c", "d", "a", "e", "f"]);
+}
+
+#[test]
+fn it_can_collect_column_refs() {
+    let tree = pg_parse::parse(
+        "SELECT a, t.b, t.* FROM t WHERE c = 1 GROUP BY d HAVING count(e) > 1 ORDER BY f",
+    )
+    .unwrap();
+    let columns = visit(Columns::default(), &tree);
+    assert_eq!(columns.0, ["a", "t.b", "t.*", "c", "d", "e", "f"]);
+}
+
+#[test]
+fn it_can_visit_value_nodes() {
+    let tree = pg_parse::parse("SELECT 1, 'two' WHERE x = 3").unwrap();
+    let constants = visit(Constants::default(), &tree);
+    assert_eq!(
+        constants.0,
+        [
+            (&ConstValue::Integer(1), 7),
+            (&ConstValue::String("two".into()), 10),
+            (&ConstValue::Integer(3), 26),
+        ]
+    );
+}
+
+#[cfg(feature = "str")]
+mod rewrite {
+    use pg_parse::ast::fold::{self, Fold};
+    use pg_parse::ast::visit_mut::{VisitMut, walk_range_var_mut, walk_select_stmt_mut};
+    use pg_parse::ast::{
+        BoolExpr, BoolExprType, ConstValue, Node, ParamRef, ParseLoc, RangeVar, SelectStmt,
+    };
+
+    fn to_string(tree: &[Node]) -> String {
+        tree.iter()
+            .map(|stmt| stmt.to_string())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    /// Renames every reference to a table.
+    struct Rename(&'static str, &'static str);
+
+    impl VisitMut for Rename {
+        fn visit_range_var_mut(&mut self, node: &mut RangeVar) {
+            if node.relname.as_deref() == Some(self.0) {
+                node.relname = Some(self.1.into());
+            }
+            walk_range_var_mut(self, node);
+        }
+    }
+
+    /// Adds a predicate to the `WHERE` clause of every `SELECT`.
+    struct Predicate(Node);
+
+    impl VisitMut for Predicate {
+        fn visit_select_stmt_mut(&mut self, node: &mut SelectStmt) {
+            walk_select_stmt_mut(self, node);
+            if node.from_clause.is_none() {
+                return;
+            }
+            let predicate = self.0.clone();
+            node.where_clause = Some(Box::new(match node.where_clause.take() {
+                Some(existing) => Node::from(BoolExpr {
+                    boolop: Box::new(BoolExprType::AND_EXPR),
+                    args: Some(vec![*existing, predicate]),
+                    location: -1,
+                }),
+                None => predicate,
+            }));
+        }
+    }
+
+    /// Replaces each constant with a parameter.
+    #[derive(Default)]
+    struct Parameterize(i32);
+
+    impl Fold for Parameterize {
+        fn fold_a_const(&mut self, _val: ConstValue, location: ParseLoc) -> Node {
+            self.0 += 1;
+            Node::ParamRef(ParamRef {
+                number: self.0,
+                location,
+            })
+        }
+    }
+
+    /// Replaces `now()` with `CURRENT_TIMESTAMP`, i.e. a different variant of node.
+    struct Now;
+
+    impl Fold for Now {
+        fn fold_node(&mut self, node: Node) -> Node {
+            match node {
+                Node::FuncCall(call)
+                    if call.args.is_none()
+                        && matches!(
+                            call.funcname.as_deref(),
+                            Some([Node::String { sval: Some(name) }]) if name == "now"
+                        ) =>
+                {
+                    pg_parse::parse_expression("CURRENT_TIMESTAMP").unwrap()
+                }
+                node => fold::walk_node(self, node),
+            }
+        }
+    }
+
+    #[test]
+    fn it_can_rename_tables() {
+        let mut tree = pg_parse::parse(
+            "SELECT * FROM contacts c INNER JOIN orders o ON c.id = o.contact_id; DELETE FROM contacts WHERE id = 1",
+        )
+        .unwrap();
+        for stmt in &mut tree {
+            Rename("contacts", "people").visit_node_mut(stmt);
+        }
+        assert_eq!(
+            to_string(&tree),
+            "SELECT * FROM people c INNER JOIN orders o ON c.id = o.contact_id; DELETE FROM people WHERE id = 1"
+        );
+    }
+
+    #[test]
+    fn it_can_inject_predicates() {
+        let predicate = pg_parse::parse_expression("tenant_id = 42").unwrap();
+        let mut tree = pg_parse::parse(
+            "SELECT * FROM a WHERE x = 1; SELECT * FROM b WHERE y IN (SELECT y FROM c)",
+        )
+        .unwrap();
+        for stmt in &mut tree {
+            Predicate(predicate.clone()).visit_node_mut(stmt);
+        }
+        assert_eq!(
+            to_string(&tree),
+            "SELECT * FROM a WHERE x = 1 AND tenant_id = 42; \
+             SELECT * FROM b WHERE y IN (SELECT y FROM c WHERE tenant_id = 42) AND tenant_id = 42"
+        );
+    }
+
+    #[test]
+    fn it_can_replace_constants() {
+        let tree = pg_parse::parse(
+            "SELECT * FROM contacts WHERE id = 1 AND name = 'Paul' AND active = TRUE LIMIT 5",
+        )
+        .unwrap();
+        let mut folder = Parameterize::default();
+        let tree = tree
+            .into_iter()
+            .map(|stmt| folder.fold_node(stmt))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            to_string(&tree),
+            "SELECT * FROM contacts WHERE id = $1 AND name = $2 AND active = $3 LIMIT $4"
+        );
+    }
+
+    #[test]
+    fn it_can_replace_nodes_with_another_variant() {
+        let tree = pg_parse::parse("SELECT now(), now_utc() FROM a WHERE b > now()").unwrap();
+        let tree = tree
+            .into_iter()
+            .map(|stmt| Now.fold_node(stmt))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            to_string(&tree),
+            "SELECT current_timestamp, now_utc() FROM a WHERE b > current_timestamp"
+        );
+    }
+}